@@ -0,0 +1,119 @@
+//! Text encodings of RF files.
+//!
+//! RF reads tables and names stored in binary files as Windows-1252 (the default ANSI code page of western Windows)
+//! but tables edited with modern tools are sometimes saved as UTF-8.
+
+use std::fmt::{self, Display};
+
+/// Characters of bytes 0x80-0x9F in Windows-1252. Bytes not defined by the code page map to C1 control characters
+/// (like in the WHATWG Encoding Standard) so every byte sequence can be decoded and encoded back without loss.
+/// Bytes 0xA0-0xFF map to the same Unicode code points.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+/// Character written instead of characters that cannot be encoded (when replacing is enabled)
+const REPLACEMENT_BYTE: u8 = b'?';
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Windows1252,
+    Utf8,
+}
+
+impl Encoding {
+    pub fn name(self) -> &'static str {
+        match self {
+            Encoding::Windows1252 => "windows-1252",
+            Encoding::Utf8 => "utf-8",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Encoding::Windows1252, Encoding::Utf8]
+            .into_iter()
+            .find(|e| e.name().eq_ignore_ascii_case(name))
+    }
+}
+
+#[derive(Debug)]
+pub struct EncodeError {
+    pub line: usize,
+    pub character: char,
+}
+
+impl Display for EncodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}: character '{}' cannot be represented in Windows-1252",
+            self.line, self.character
+        )
+    }
+}
+
+impl std::error::Error for EncodeError {}
+
+fn decode_windows_1252_byte(b: u8) -> char {
+    match b {
+        0x80..=0x9F => WINDOWS_1252_HIGH[usize::from(b - 0x80)],
+        _ => char::from(b),
+    }
+}
+
+fn encode_windows_1252_char(c: char) -> Option<u8> {
+    match u32::from(c) {
+        0..=0x7F | 0xA0..=0xFF => Some(c as u8),
+        _ => WINDOWS_1252_HIGH
+            .iter()
+            .position(|&h| h == c)
+            .map(|i| 0x80 + i as u8),
+    }
+}
+
+/// Decodes table content. If encoding is not given, UTF-8 is used for files that are valid UTF-8 and contain
+/// non-ASCII characters (Windows-1252 text with accented characters is practically never valid UTF-8) and
+/// Windows-1252 otherwise. Returns the encoding that was used.
+pub fn decode(bytes: &[u8], encoding: Option<Encoding>) -> (String, Encoding) {
+    let encoding = encoding.unwrap_or_else(|| match std::str::from_utf8(bytes) {
+        Ok(s) if !s.is_ascii() => Encoding::Utf8,
+        _ => Encoding::Windows1252,
+    });
+    let text = match encoding {
+        Encoding::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+        Encoding::Windows1252 => bytes
+            .iter()
+            .copied()
+            .map(decode_windows_1252_byte)
+            .collect(),
+    };
+    (text, encoding)
+}
+
+/// Encodes table content. Characters that cannot be represented in Windows-1252 are replaced with '?' if
+/// `replace_unmappable` is set, otherwise an error is returned.
+pub fn encode(
+    text: &str,
+    encoding: Encoding,
+    replace_unmappable: bool,
+) -> Result<Vec<u8>, EncodeError> {
+    if encoding == Encoding::Utf8 {
+        return Ok(text.as_bytes().to_vec());
+    }
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut line = 1;
+    for c in text.chars() {
+        if c == '\n' {
+            line += 1;
+        }
+        match encode_windows_1252_char(c) {
+            Some(b) => bytes.push(b),
+            None if replace_unmappable => bytes.push(REPLACEMENT_BYTE),
+            None => return Err(EncodeError { line, character: c }),
+        }
+    }
+    Ok(bytes)
+}
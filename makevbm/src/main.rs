@@ -5,6 +5,11 @@ use std::path;
 
 use byteorder::{LittleEndian, WriteBytesExt};
 use image::GenericImageView;
+use log::{Level, LevelFilter};
+use std::io::Write;
+
+#[macro_use]
+extern crate log;
 
 #[derive(Clone, Copy)]
 enum VbmColorMode {
@@ -108,7 +113,7 @@ fn make_vbm(
     wrt.write_u32::<LittleEndian>(0)?; // TODO: num_mipmaps
 
     for i in 0..num_frames {
-        println!("Processing frame {}/{}...", i, num_frames);
+        info!("Processing frame {}/{}...", i, num_frames);
         let frame_file_name = build_frame_file_name(prefix, i, dot_ext);
         let frame_img = image::open(frame_file_name)?;
         write_frame(&mut wrt, clr_mode, &frame_img)?;
@@ -116,11 +121,45 @@ fn make_vbm(
     Ok(())
 }
 
+fn init_logger(verbose: u8, quiet: bool) {
+    let level = if quiet {
+        LevelFilter::Error
+    } else {
+        match verbose {
+            0 => LevelFilter::Warn,
+            1 => LevelFilter::Info,
+            2 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        }
+    };
+    env_logger::Builder::new()
+        .filter_module("makevbm", level)
+        .format(|buf, record| match record.level() {
+            Level::Error => writeln!(buf, "Error: {}", record.args()),
+            Level::Warn => writeln!(buf, "Warning! {}", record.args()),
+            _ => writeln!(buf, "{}", record.args()),
+        })
+        .init();
+}
+
 fn main() {
-    println!("makevbm {} created by Rafalh", env!("CARGO_PKG_VERSION"));
-    let args = env::args().collect::<Vec<_>>();
-    if env::args().len() != 4 {
-        println!("Usage: {} colormode framerate input_file", args[0]);
+    let mut verbose = 0;
+    let mut quiet = false;
+    let mut args = Vec::new();
+    for arg in env::args() {
+        match arg.as_str() {
+            "--verbose" => verbose += 1,
+            "-q" | "--quiet" => quiet = true,
+            _ => args.push(arg),
+        }
+    }
+    init_logger(verbose, quiet);
+    info!("makevbm {} created by Rafalh", env!("CARGO_PKG_VERSION"));
+    if args.len() != 4 {
+        println!(
+            "Usage: {} [--verbose] [-q] colormode framerate input_file",
+            args[0]
+        );
         println!();
         println!("Available color modes:");
         println!("  1555 - 5 bits for each RGB channel and 1 bit for alpha channel");
@@ -139,7 +178,7 @@ fn main() {
     }
 
     if let Err(e) = make_vbm(&args[1], &args[2], &args[3]) {
-        eprintln!("Error: {}", e);
+        error!("{}", e);
         std::process::exit(1);
     }
 }
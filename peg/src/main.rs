@@ -3,12 +3,16 @@
 mod targa;
 
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use log::{Level, LevelFilter};
 use std::cmp;
 use std::convert::TryInto;
 use std::env;
 use std::fs::File;
 use std::io::{BufReader, BufWriter, Read, Result, Seek, SeekFrom, Write};
 
+#[macro_use]
+extern crate log;
+
 enum PegBmType {
     Mpeg2_16 = 1,
     Mpeg2_32 = 2,
@@ -45,7 +49,7 @@ impl PegFileHeader {
             panic!("Unsupported version: {}", self.version);
         }
         if self.version > 6 {
-            eprintln!("Warning: version {} is too new", self.version);
+            warn!("Version {} is too new", self.version);
         }
     }
 
@@ -182,7 +186,7 @@ fn extract_peg_mipmap<R: Read>(
         frame,
         level
     );
-    println!(
+    info!(
         "Writing mip level {} ({}x{}) to {}.",
         level, width, height, output_filename
     );
@@ -253,7 +257,7 @@ fn extract_mpeg_video<R: Read>(
     let unk0 = rdr.read_u32::<LittleEndian>()?;
     let unk1 = rdr.read_u32::<LittleEndian>()?;
     let unk2 = rdr.read_u32::<LittleEndian>()?;
-    println!(
+    debug!(
         "MPEG2 video header: {:x} {:x} {} {}",
         total_size, unk0, unk1, unk2
     );
@@ -334,7 +338,7 @@ fn extract_peg_file(pathname: &str, output_dir: Option<&str>) -> Result<()> {
     }
 
     for e in &entries {
-        println!("Extracting {} (offset {:x})...", e.filename, e.data_offset);
+        info!("Extracting {} (offset {:x})...", e.filename, e.data_offset);
         rdr.seek(SeekFrom::Start(e.data_offset.try_into().unwrap()))?;
         extract_peg_bitmap(&mut rdr, e, output_dir)?;
     }
@@ -352,15 +356,16 @@ enum Operation {
 struct ParsedArgs {
     op: Operation,
     positional: Vec<String>,
-    #[allow(dead_code)]
-    verbose: bool,
+    verbose: u8,
+    quiet: bool,
     output_dir: Option<String>,
 }
 
 fn parse_args() -> ParsedArgs {
     let mut op_opt = None;
     let mut positional = Vec::<String>::new();
-    let mut verbose = false;
+    let mut verbose = 0;
+    let mut quiet = false;
     let mut output_dir = None;
 
     let mut args_it = env::args().skip(1);
@@ -370,7 +375,8 @@ fn parse_args() -> ParsedArgs {
             "-v" => op_opt = Some(Operation::Version),
             "-x" => op_opt = Some(Operation::Extract),
             "-O" => output_dir = Some(args_it.next().expect("expected output dir")),
-            "--verbose" => verbose = true,
+            "--verbose" => verbose += 1,
+            "-q" | "--quiet" => quiet = true,
             _ => positional.push(arg),
         }
     }
@@ -387,6 +393,7 @@ fn parse_args() -> ParsedArgs {
         op,
         positional,
         verbose,
+        quiet,
         output_dir,
     }
 }
@@ -397,14 +404,39 @@ fn print_help() {
     println!("    shows information about PEG file");
     println!("  peg -x file.peg");
     println!("    extract PEG file");
+    println!("Additional options:");
+    println!("  --verbose   - print more information (can be used up to 3 times)");
+    println!("  -q, --quiet - do not print warnings (errors are still printed)");
 }
 
 fn print_version() {
     println!("PEG Tool {} by Rafalh", env!("CARGO_PKG_VERSION"));
 }
 
+fn init_logger(verbose: u8, quiet: bool) {
+    let level = if quiet {
+        LevelFilter::Error
+    } else {
+        match verbose {
+            0 => LevelFilter::Warn,
+            1 => LevelFilter::Info,
+            2 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        }
+    };
+    env_logger::Builder::new()
+        .filter_module("peg", level)
+        .format(|buf, record| match record.level() {
+            Level::Error => writeln!(buf, "Error: {}", record.args()),
+            Level::Warn => writeln!(buf, "Warning! {}", record.args()),
+            _ => writeln!(buf, "{}", record.args()),
+        })
+        .init();
+}
+
 fn main() -> Result<()> {
     let args = parse_args();
+    init_logger(args.verbose, args.quiet);
     match args.op {
         Operation::Info => {
             for pathname in &args.positional {
@@ -0,0 +1,126 @@
+use crate::rfl::{self, get_section_name, Object, RflFile};
+use std::io::Result;
+
+fn format_object(object: &Object) -> String {
+    let [x, y, z] = object.pos;
+    let mut s = format!(
+        "#{} {} at ({:.2}, {:.2}, {:.2})",
+        object.uid, object.class_name, x, y, z
+    );
+    if !object.script_name.is_empty() {
+        s += &format!(" \"{}\"", object.script_name);
+    }
+    s
+}
+
+fn unwrap_or_warn<T: Default>(result: Result<T>, section_type: u32) -> T {
+    result.unwrap_or_else(|e| {
+        warn!(
+            "Cannot parse {} section: {}",
+            get_section_name(section_type),
+            e
+        );
+        T::default()
+    })
+}
+
+fn print_sections(rfl: &RflFile) {
+    println!("Sections:");
+    for section in &rfl.sections {
+        println!(
+            "  {:08X} {:24} {} bytes",
+            section.section_type,
+            get_section_name(section.section_type),
+            section.data.len()
+        );
+    }
+}
+
+fn print_file_lists(rfl: &RflFile) {
+    let file_lists = [
+        ("Textures", rfl::TGA_FILES_SECTION),
+        ("Animated textures", rfl::VCM_FILES_SECTION),
+        ("Animations", rfl::MVF_FILES_SECTION),
+        ("Meshes", rfl::V3D_FILES_SECTION),
+        ("Effects", rfl::VFX_FILES_SECTION),
+    ];
+    println!("Referenced files:");
+    for (title, section_type) in file_lists {
+        let files = unwrap_or_warn(rfl::read_file_list(rfl, section_type), section_type);
+        if files.is_empty() {
+            continue;
+        }
+        println!("  {} ({}):", title, files.len());
+        for file in files {
+            println!("    {}", file);
+        }
+    }
+}
+
+pub fn print_level_info(rfl: &RflFile, verbose: bool) {
+    println!("Level: {}", rfl.header.level_name);
+    println!("Version: 0x{:X}", rfl.header.version);
+    if !rfl.header.mod_name.is_empty() {
+        println!("Mod: {}", rfl.header.mod_name);
+    }
+    let level_info = unwrap_or_warn(rfl::read_level_info(rfl), rfl::LEVEL_INFO_SECTION);
+    if let Some(level_info) = level_info {
+        println!("Author: {}", level_info.author);
+        println!("Date: {}", level_info.date);
+        println!(
+            "Multiplayer: {}",
+            if level_info.multiplayer_level {
+                "yes"
+            } else {
+                "no"
+            }
+        );
+    }
+    if verbose {
+        print_sections(rfl);
+    }
+
+    let entities = unwrap_or_warn(rfl::read_entities(rfl), rfl::ENTITIES_SECTION);
+    println!("Entities ({}):", entities.len());
+    for entity in &entities {
+        let mut line = format_object(&entity.object);
+        if !entity.default_primary_weapon.is_empty() {
+            line += &format!(", weapon {}", entity.default_primary_weapon);
+        }
+        if !entity.skin.is_empty() {
+            line += &format!(", skin {}", entity.skin);
+        }
+        println!("  {}", line);
+    }
+
+    let items = unwrap_or_warn(rfl::read_items(rfl), rfl::ITEMS_SECTION);
+    println!("Items ({}):", items.len());
+    for item in &items {
+        println!("  {}, count {}", format_object(&item.object), item.count);
+    }
+
+    let clutters = unwrap_or_warn(rfl::read_clutters(rfl), rfl::CLUTTERS_SECTION);
+    println!("Clutters ({}):", clutters.len());
+    for clutter in &clutters {
+        let mut line = format_object(&clutter.object);
+        if !clutter.skin.is_empty() {
+            line += &format!(", skin {}", clutter.skin);
+        }
+        println!("  {}", line);
+    }
+
+    let events = unwrap_or_warn(rfl::read_events(rfl), rfl::EVENTS_SECTION);
+    println!("Events ({}):", events.len());
+    for event in &events {
+        let [x, y, z] = event.pos;
+        println!(
+            "  #{} {} at ({:.2}, {:.2}, {:.2}), links {:?}",
+            event.uid, event.class_name, x, y, z, event.links
+        );
+    }
+
+    let num_movers = unwrap_or_warn(rfl::count_movers(rfl), rfl::MOVERS_SECTION);
+    println!("Movers: {}", num_movers);
+
+    print_file_lists(rfl);
+}
@@ -0,0 +1,115 @@
+use crate::rfl::{read_lightmap_rooms, read_lightmaps, Lightmap, RflFile};
+use crate::tga::TgaHeader;
+use crate::Result;
+use binrw::BinWriterExt;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+fn write_lightmap_tga(filename: &Path, width: u32, height: u32, rgb_pixels: &[u8]) -> Result<()> {
+    let tga_hdr = TgaHeader::new(width.try_into()?, height.try_into()?, 24);
+    let file = File::create(filename)?;
+    let mut writer = BufWriter::new(file);
+    writer.write_le(&tga_hdr)?;
+    // TGA stores pixels in BGR order
+    for rgb in rgb_pixels.chunks_exact(3) {
+        writer.write_all(&[rgb[2], rgb[1], rgb[0]])?;
+    }
+    Ok(())
+}
+
+struct Placement {
+    x: u32,
+    y: u32,
+}
+
+fn compute_sheet_layout(lightmaps: &[&Lightmap]) -> (u32, u32, Vec<Placement>) {
+    // Simple shelf packing - lightmaps are placed in rows in the original order
+    let total_area: u64 = lightmaps
+        .iter()
+        .map(|lm| u64::from(lm.width) * u64::from(lm.height))
+        .sum();
+    let max_width = lightmaps.iter().map(|lm| lm.width).max().unwrap_or(1);
+    let sheet_width = max_width.max(((total_area as f32).sqrt().ceil() as u32).next_power_of_two());
+    let mut placements = Vec::with_capacity(lightmaps.len());
+    let (mut x, mut y, mut row_height) = (0, 0, 0);
+    for lm in lightmaps {
+        if x + lm.width > sheet_width {
+            x = 0;
+            y += row_height;
+            row_height = 0;
+        }
+        placements.push(Placement { x, y });
+        x += lm.width;
+        row_height = row_height.max(lm.height);
+    }
+    (sheet_width, y + row_height, placements)
+}
+
+fn write_lightmap_sheet(filename: &Path, lightmaps: &[&Lightmap]) -> Result<()> {
+    let (width, height, placements) = compute_sheet_layout(lightmaps);
+    let mut sheet = vec![0_u8; width as usize * height as usize * 3];
+    for (lm, placement) in lightmaps.iter().zip(&placements) {
+        let row_len = lm.width as usize * 3;
+        for row in 0..lm.height as usize {
+            let src_offset = row * row_len;
+            let dst_offset =
+                ((placement.y as usize + row) * width as usize + placement.x as usize) * 3;
+            sheet[dst_offset..dst_offset + row_len]
+                .copy_from_slice(&lm.pixels[src_offset..src_offset + row_len]);
+        }
+    }
+    write_lightmap_tga(filename, width, height, &sheet)
+}
+
+pub fn export_lightmaps(rfl: &RflFile, prefix: &Path, stitch: bool) -> Result<()> {
+    let lightmaps = read_lightmaps(rfl)?;
+    if lightmaps.is_empty() {
+        warn!("Level has no lightmaps");
+        return Ok(());
+    }
+    if stitch {
+        let rooms = read_lightmap_rooms(rfl, lightmaps.len()).unwrap_or_else(|e| {
+            warn!(
+                "Cannot read rooms from static geometry ({}) - all lightmaps are stitched into a single image",
+                e
+            );
+            vec![None; lightmaps.len()]
+        });
+        // lightmaps not used by any room are stitched together
+        let mut groups: BTreeMap<Option<u32>, Vec<&Lightmap>> = BTreeMap::new();
+        for (lm, room) in lightmaps.iter().zip(rooms) {
+            groups.entry(room).or_default().push(lm);
+        }
+        for (room, group) in &groups {
+            let mut file_name = prefix.file_name().unwrap().to_owned();
+            match room {
+                Some(room_index) => file_name.push(format!("_room{:03}_lightmaps.tga", room_index)),
+                None => file_name.push("_lightmaps.tga"),
+            }
+            let output_path = prefix.with_file_name(file_name);
+            info!(
+                "Writing {} lightmaps to {}",
+                group.len(),
+                output_path.display()
+            );
+            write_lightmap_sheet(&output_path, group)?;
+        }
+    } else {
+        for (i, lm) in lightmaps.iter().enumerate() {
+            let mut file_name = prefix.file_name().unwrap().to_owned();
+            file_name.push(format!("_lm{:04}.tga", i));
+            let output_path = prefix.with_file_name(file_name);
+            info!(
+                "Writing lightmap {} ({}x{}) to {}",
+                i,
+                lm.width,
+                lm.height,
+                output_path.display()
+            );
+            write_lightmap_tga(&output_path, lm.width, lm.height, &lm.pixels)?;
+        }
+    }
+    Ok(())
+}
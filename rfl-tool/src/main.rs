@@ -0,0 +1,114 @@
+mod info;
+mod lightmaps;
+mod rfl;
+mod tga;
+
+use clap::{ArgAction, Parser, Subcommand};
+use log::{Level, LevelFilter};
+use rfl::RflFile;
+use std::io::{BufReader, Write};
+use std::{error::Error, fs::File, path::Path, path::PathBuf};
+
+#[macro_use]
+extern crate log;
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+fn load_rfl(path: &Path) -> Result<RflFile> {
+    let file = File::open(path)?;
+    let mut rdr = BufReader::new(file);
+    Ok(RflFile::read(&mut rdr)?)
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Show level information: entities, items, clutters, events, movers and referenced files
+    Info {
+        /// Input RFL files
+        #[clap(required = true)]
+        rfl_files: Vec<PathBuf>,
+    },
+
+    /// Export level lightmaps as TGA images
+    Lightmaps {
+        /// Input RFL files
+        #[clap(required = true)]
+        rfl_files: Vec<PathBuf>,
+
+        /// Output directory
+        #[clap(short = 'O', default_value = ".")]
+        output_dir: PathBuf,
+
+        /// Stitch lightmaps of every room into a single image. Lightmaps not used by any room are stitched together
+        #[clap(long)]
+        stitch: bool,
+    },
+}
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, about = "RFL (Red Faction level) tool")]
+pub struct Args {
+    #[clap(subcommand)]
+    command: Command,
+
+    /// Enable verbose output. Can be used up to 3 times to increase verbosity
+    #[clap(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Do not print warnings (errors are still printed)
+    #[clap(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+}
+
+fn init_logger(verbose: u8, quiet: bool) {
+    let level = if quiet {
+        LevelFilter::Error
+    } else {
+        match verbose {
+            0 => LevelFilter::Warn,
+            1 => LevelFilter::Info,
+            2 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        }
+    };
+    env_logger::Builder::new()
+        .filter_module("rfl_tool", level)
+        .format(|buf, record| match record.level() {
+            Level::Error => writeln!(buf, "Error: {}", record.args()),
+            Level::Warn => writeln!(buf, "Warning! {}", record.args()),
+            _ => writeln!(buf, "{}", record.args()),
+        })
+        .init();
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    init_logger(args.verbose, args.quiet);
+    info!("rfl-tool {}", env!("CARGO_PKG_VERSION"));
+
+    match &args.command {
+        Command::Info { rfl_files } => {
+            for input_file in rfl_files {
+                println!("Processing {}...", input_file.display());
+                let rfl = load_rfl(input_file)?;
+                info::print_level_info(&rfl, args.verbose > 0);
+                println!();
+            }
+        }
+        Command::Lightmaps {
+            rfl_files,
+            output_dir,
+            stitch,
+        } => {
+            for input_file in rfl_files {
+                info!("Processing {}...", input_file.display());
+                let rfl = load_rfl(input_file)?;
+                info!("Level name: {}", rfl.header.level_name);
+                let prefix = output_dir.join(input_file.file_stem().unwrap());
+                lightmaps::export_lightmaps(&rfl, &prefix, *stitch)?;
+            }
+        }
+    }
+
+    Ok(())
+}
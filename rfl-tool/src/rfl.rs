@@ -0,0 +1,606 @@
+use byteorder::{LittleEndian, ReadBytesExt};
+use std::io::{Error, ErrorKind, Read, Result};
+
+pub const RFL_SIGNATURE: u32 = 0xD4BA_DA55;
+// Oldest version supported by RF 1.2
+pub const RFL_MIN_VERSION: u32 = 0xB4;
+
+// Section types
+pub const END_SECTION: u32 = 0x0000_0000;
+pub const STATIC_GEOMETRY_SECTION: u32 = 0x0000_0100;
+pub const GEO_REGIONS_SECTION: u32 = 0x0000_0200;
+pub const LIGHTS_SECTION: u32 = 0x0000_0300;
+pub const CUTSCENE_CAMERAS_SECTION: u32 = 0x0000_0400;
+pub const AMBIENT_SOUNDS_SECTION: u32 = 0x0000_0500;
+pub const EVENTS_SECTION: u32 = 0x0000_0600;
+pub const MP_RESPAWNS_SECTION: u32 = 0x0000_0700;
+pub const LEVEL_PROPERTIES_SECTION: u32 = 0x0000_0900;
+pub const PARTICLE_EMITTERS_SECTION: u32 = 0x0000_0A00;
+pub const GAS_REGIONS_SECTION: u32 = 0x0000_0B00;
+pub const ROOM_EFFECTS_SECTION: u32 = 0x0000_0C00;
+pub const BOLT_EMITTERS_SECTION: u32 = 0x0000_0E00;
+pub const TARGETS_SECTION: u32 = 0x0000_0F00;
+pub const DECALS_SECTION: u32 = 0x0000_1000;
+pub const PUSH_REGIONS_SECTION: u32 = 0x0000_1100;
+pub const LIGHTMAPS_SECTION: u32 = 0x0000_1200;
+pub const MOVERS_SECTION: u32 = 0x0000_2000;
+pub const MOVING_GROUPS_SECTION: u32 = 0x0000_3000;
+pub const CUTSCENE_PATH_NODES_SECTION: u32 = 0x0000_5000;
+pub const TGA_FILES_SECTION: u32 = 0x0000_7000;
+pub const VCM_FILES_SECTION: u32 = 0x0000_7001;
+pub const MVF_FILES_SECTION: u32 = 0x0000_7002;
+pub const V3D_FILES_SECTION: u32 = 0x0000_7003;
+pub const VFX_FILES_SECTION: u32 = 0x0000_7004;
+pub const EAX_EFFECTS_SECTION: u32 = 0x0000_8000;
+pub const NAV_POINTS_SECTION: u32 = 0x0002_0000;
+pub const ENTITIES_SECTION: u32 = 0x0003_0000;
+pub const ITEMS_SECTION: u32 = 0x0004_0000;
+pub const CLUTTERS_SECTION: u32 = 0x0005_0000;
+pub const TRIGGERS_SECTION: u32 = 0x0006_0000;
+pub const PLAYER_START_SECTION: u32 = 0x0007_0000;
+pub const LEVEL_INFO_SECTION: u32 = 0x0100_0000;
+pub const BRUSHES_SECTION: u32 = 0x0200_0000;
+pub const GROUPS_SECTION: u32 = 0x0300_0000;
+
+pub fn get_section_name(section_type: u32) -> &'static str {
+    match section_type {
+        STATIC_GEOMETRY_SECTION => "static geometry",
+        GEO_REGIONS_SECTION => "geo regions",
+        LIGHTS_SECTION => "lights",
+        CUTSCENE_CAMERAS_SECTION => "cutscene cameras",
+        AMBIENT_SOUNDS_SECTION => "ambient sounds",
+        EVENTS_SECTION => "events",
+        MP_RESPAWNS_SECTION => "multiplayer respawn points",
+        LEVEL_PROPERTIES_SECTION => "level properties",
+        PARTICLE_EMITTERS_SECTION => "particle emitters",
+        GAS_REGIONS_SECTION => "gas regions",
+        ROOM_EFFECTS_SECTION => "room effects",
+        BOLT_EMITTERS_SECTION => "bolt emitters",
+        TARGETS_SECTION => "targets",
+        DECALS_SECTION => "decals",
+        PUSH_REGIONS_SECTION => "push regions",
+        LIGHTMAPS_SECTION => "lightmaps",
+        MOVERS_SECTION => "movers",
+        MOVING_GROUPS_SECTION => "moving groups",
+        CUTSCENE_PATH_NODES_SECTION => "cutscene path nodes",
+        TGA_FILES_SECTION => "TGA files",
+        VCM_FILES_SECTION => "VCM files",
+        MVF_FILES_SECTION => "MVF files",
+        V3D_FILES_SECTION => "V3D files",
+        VFX_FILES_SECTION => "VFX files",
+        EAX_EFFECTS_SECTION => "EAX effects",
+        NAV_POINTS_SECTION => "navigation points",
+        ENTITIES_SECTION => "entities",
+        ITEMS_SECTION => "items",
+        CLUTTERS_SECTION => "clutters",
+        TRIGGERS_SECTION => "triggers",
+        PLAYER_START_SECTION => "player start",
+        LEVEL_INFO_SECTION => "level info",
+        BRUSHES_SECTION => "brushes",
+        GROUPS_SECTION => "groups",
+        _ => "unknown",
+    }
+}
+
+pub(crate) trait ReadExt: Read {
+    fn read_vstr(&mut self) -> Result<String> {
+        let len = self.read_u16::<LittleEndian>()?;
+        let mut buf = vec![0_u8; usize::from(len)];
+        self.read_exact(&mut buf)?;
+        Ok(String::from_utf8_lossy(&buf).into_owned())
+    }
+
+    fn read_f32_array<const N: usize>(&mut self) -> Result<[f32; N]> {
+        let mut arr = [0_f32; N];
+        self.read_f32_into::<LittleEndian>(&mut arr)?;
+        Ok(arr)
+    }
+
+    fn read_bool(&mut self) -> Result<bool> {
+        Ok(self.read_u8()? != 0)
+    }
+
+    fn read_links(&mut self) -> Result<Vec<i32>> {
+        let num_links = self.read_u32::<LittleEndian>()?;
+        (0..num_links)
+            .map(|_| self.read_i32::<LittleEndian>())
+            .collect()
+    }
+}
+
+impl<T: Read> ReadExt for T {}
+
+#[allow(unused)]
+pub struct FileHeader {
+    pub version: u32,
+    pub timestamp: u32,
+    pub player_start_offset: u32,
+    pub level_info_offset: u32,
+    pub sections_count: u32,
+    pub sections_size: u32,
+    pub level_name: String,
+    pub mod_name: String,
+}
+
+impl FileHeader {
+    pub fn read<R: Read>(rdr: &mut R) -> Result<Self> {
+        let signature = rdr.read_u32::<LittleEndian>()?;
+        if signature != RFL_SIGNATURE {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid file signature {:x}", signature),
+            ));
+        }
+        let version = rdr.read_u32::<LittleEndian>()?;
+        if version < RFL_MIN_VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("unsupported version {:x}", version),
+            ));
+        }
+        Ok(Self {
+            version,
+            timestamp: rdr.read_u32::<LittleEndian>()?,
+            player_start_offset: rdr.read_u32::<LittleEndian>()?,
+            level_info_offset: rdr.read_u32::<LittleEndian>()?,
+            sections_count: rdr.read_u32::<LittleEndian>()?,
+            sections_size: rdr.read_u32::<LittleEndian>()?,
+            level_name: rdr.read_vstr()?,
+            mod_name: rdr.read_vstr()?,
+        })
+    }
+}
+
+pub struct Section {
+    pub section_type: u32,
+    pub data: Vec<u8>,
+}
+
+pub struct RflFile {
+    pub header: FileHeader,
+    pub sections: Vec<Section>,
+}
+
+impl RflFile {
+    pub fn read<R: Read>(rdr: &mut R) -> Result<Self> {
+        let header = FileHeader::read(rdr)?;
+        let mut sections = Vec::new();
+        loop {
+            let section_type = rdr.read_u32::<LittleEndian>()?;
+            let size = rdr.read_u32::<LittleEndian>()?;
+            if section_type == END_SECTION {
+                break;
+            }
+            // size comes from the file so data is not pre-allocated
+            let mut data = Vec::new();
+            rdr.take(u64::from(size)).read_to_end(&mut data)?;
+            if data.len() != size as usize {
+                return Err(Error::new(
+                    ErrorKind::UnexpectedEof,
+                    format!("section {:x} is truncated", section_type),
+                ));
+            }
+            sections.push(Section { section_type, data });
+        }
+        Ok(Self { header, sections })
+    }
+
+    pub fn find_section(&self, section_type: u32) -> Option<&Section> {
+        self.sections
+            .iter()
+            .find(|s| s.section_type == section_type)
+    }
+}
+
+// Protects from huge allocations caused by corrupted files - lightmaps created by the level editor are much smaller
+const MAX_LIGHTMAP_PIXELS: u32 = 2048 * 2048;
+
+pub struct Lightmap {
+    pub width: u32,
+    pub height: u32,
+    // RGB pixels, 3 bytes per pixel
+    pub pixels: Vec<u8>,
+}
+
+impl Lightmap {
+    pub fn read<R: Read>(rdr: &mut R) -> Result<Self> {
+        let width = rdr.read_u32::<LittleEndian>()?;
+        let height = rdr.read_u32::<LittleEndian>()?;
+        let num_pixels = width
+            .checked_mul(height)
+            .filter(|&n| n <= MAX_LIGHTMAP_PIXELS)
+            .ok_or_else(|| {
+                Error::new(
+                    ErrorKind::InvalidData,
+                    format!("invalid lightmap size {}x{}", width, height),
+                )
+            })?;
+        let mut pixels = vec![0_u8; num_pixels as usize * 3];
+        rdr.read_exact(&mut pixels)?;
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+}
+
+fn read_section_records<'a, T, F: FnMut(&mut &'a [u8]) -> Result<T>>(
+    rfl: &'a RflFile,
+    section_type: u32,
+    mut fun: F,
+) -> Result<Vec<T>> {
+    let Some(section) = rfl.find_section(section_type) else {
+        return Ok(Vec::new());
+    };
+    let mut rdr = section.data.as_slice();
+    let num_records = rdr.read_u32::<LittleEndian>()?;
+    // number of records comes from the file so the vector is not pre-allocated
+    let mut records = Vec::new();
+    for _ in 0..num_records {
+        records.push(fun(&mut rdr)?);
+    }
+    Ok(records)
+}
+
+#[allow(unused)]
+pub struct LevelInfo {
+    pub level_name: String,
+    pub author: String,
+    pub date: String,
+    pub has_moving_geometry: bool,
+    pub multiplayer_level: bool,
+}
+
+pub fn read_level_info(rfl: &RflFile) -> Result<Option<LevelInfo>> {
+    let Some(section) = rfl.find_section(LEVEL_INFO_SECTION) else {
+        return Ok(None);
+    };
+    let mut rdr = section.data.as_slice();
+    let _unknown = rdr.read_u32::<LittleEndian>()?;
+    Ok(Some(LevelInfo {
+        level_name: rdr.read_vstr()?,
+        author: rdr.read_vstr()?,
+        date: rdr.read_vstr()?,
+        has_moving_geometry: rdr.read_bool()?,
+        multiplayer_level: rdr.read_bool()?,
+    }))
+}
+
+// Common part of all objects placed in the level (entities, items, clutters, etc.)
+#[allow(unused)]
+pub struct Object {
+    pub uid: i32,
+    pub class_name: String,
+    pub pos: [f32; 3],
+    pub orient: [f32; 9],
+    pub script_name: String,
+    pub hidden_in_editor: bool,
+}
+
+impl Object {
+    pub fn read<R: Read>(rdr: &mut R) -> Result<Self> {
+        Ok(Self {
+            uid: rdr.read_i32::<LittleEndian>()?,
+            class_name: rdr.read_vstr()?,
+            pos: rdr.read_f32_array()?,
+            orient: rdr.read_f32_array()?,
+            script_name: rdr.read_vstr()?,
+            hidden_in_editor: rdr.read_bool()?,
+        })
+    }
+}
+
+#[allow(unused)]
+pub struct Entity {
+    pub object: Object,
+    pub team_id: u32,
+    pub default_primary_weapon: String,
+    pub default_secondary_weapon: String,
+    pub item_drop: String,
+    pub state_anim: String,
+    pub corpse_pose: String,
+    pub skin: String,
+    pub death_anim: String,
+    pub left_hand_holding: String,
+    pub right_hand_holding: String,
+}
+
+impl Entity {
+    pub fn read<R: Read>(rdr: &mut R) -> Result<Self> {
+        let object = Object::read(rdr)?;
+        let _cooperation = rdr.read_u32::<LittleEndian>()?;
+        let _friendliness = rdr.read_u32::<LittleEndian>()?;
+        let team_id = rdr.read_u32::<LittleEndian>()?;
+        let _waypoint_list = rdr.read_vstr()?;
+        let _waypoint_method = rdr.read_vstr()?;
+        // unknown, boarded, ready_to_fire_state, only_attack_player, weapon_is_holstered, deaf
+        let mut flags = [0_u8; 6];
+        rdr.read_exact(&mut flags)?;
+        let _sweep_angles = rdr.read_f32_array::<2>()?;
+        // ignore_terrain_when_firing, unknown, start_crouched
+        let mut flags = [0_u8; 3];
+        rdr.read_exact(&mut flags)?;
+        let _life_and_armor = rdr.read_f32_array::<2>()?;
+        let _fov = rdr.read_u32::<LittleEndian>()?;
+        let default_primary_weapon = rdr.read_vstr()?;
+        let default_secondary_weapon = rdr.read_vstr()?;
+        let item_drop = rdr.read_vstr()?;
+        let state_anim = rdr.read_vstr()?;
+        let corpse_pose = rdr.read_vstr()?;
+        let skin = rdr.read_vstr()?;
+        let death_anim = rdr.read_vstr()?;
+        let _ai_mode = rdr.read_u8()?;
+        let _ai_attack_style = rdr.read_u8()?;
+        // unknown, turret_uid, alert_camera_uid, alarm_event_uid
+        let mut uids = [0_i32; 4];
+        rdr.read_i32_into::<LittleEndian>(&mut uids)?;
+        // run, start_hidden, wear_helmet, end_game_if_killed, cower_from_weapon, question_unarmed_player,
+        // dont_hum, no_shadow, always_simulate, perfect_aim, permanent_corpse, never_fly, never_leave,
+        // no_persona_messages, fade_corpse_immediately, never_collide_with_player, use_custom_attack_range
+        let mut flags = [0_u8; 17];
+        rdr.read_exact(&mut flags)?;
+        if flags[16] != 0 {
+            let _custom_attack_range = rdr.read_f32::<LittleEndian>()?;
+        }
+        let left_hand_holding = rdr.read_vstr()?;
+        let right_hand_holding = rdr.read_vstr()?;
+        Ok(Self {
+            object,
+            team_id,
+            default_primary_weapon,
+            default_secondary_weapon,
+            item_drop,
+            state_anim,
+            corpse_pose,
+            skin,
+            death_anim,
+            left_hand_holding,
+            right_hand_holding,
+        })
+    }
+}
+
+#[allow(unused)]
+pub struct Item {
+    pub object: Object,
+    pub count: i32,
+    pub respawn_time: i32,
+    pub team_id: i32,
+}
+
+impl Item {
+    pub fn read<R: Read>(rdr: &mut R) -> Result<Self> {
+        Ok(Self {
+            object: Object::read(rdr)?,
+            count: rdr.read_i32::<LittleEndian>()?,
+            respawn_time: rdr.read_i32::<LittleEndian>()?,
+            team_id: rdr.read_i32::<LittleEndian>()?,
+        })
+    }
+}
+
+#[allow(unused)]
+pub struct Clutter {
+    pub object: Object,
+    pub skin: String,
+    pub links: Vec<i32>,
+}
+
+impl Clutter {
+    pub fn read<R: Read>(rdr: &mut R) -> Result<Self> {
+        Ok(Self {
+            object: Object::read(rdr)?,
+            skin: rdr.read_vstr()?,
+            links: rdr.read_links()?,
+        })
+    }
+}
+
+#[allow(unused)]
+pub struct Event {
+    pub uid: i32,
+    pub class_name: String,
+    pub pos: [f32; 3],
+    pub script_name: String,
+    pub delay: f32,
+    pub str1: String,
+    pub str2: String,
+    pub links: Vec<i32>,
+}
+
+impl Event {
+    // Events that have orientation stored in the level file
+    const ORIENTED_CLASSES: [&'static str; 4] =
+        ["Alarm", "Teleport", "Teleport_Player", "Play_Vclip"];
+
+    pub fn read<R: Read>(rdr: &mut R) -> Result<Self> {
+        let uid = rdr.read_i32::<LittleEndian>()?;
+        let class_name = rdr.read_vstr()?;
+        let pos = rdr.read_f32_array()?;
+        let script_name = rdr.read_vstr()?;
+        let _unknown = rdr.read_u8()?;
+        let delay = rdr.read_f32::<LittleEndian>()?;
+        // bool1, bool2
+        let mut bools = [0_u8; 2];
+        rdr.read_exact(&mut bools)?;
+        // int1, int2
+        let mut ints = [0_i32; 2];
+        rdr.read_i32_into::<LittleEndian>(&mut ints)?;
+        // float1, float2
+        let _floats = rdr.read_f32_array::<2>()?;
+        let str1 = rdr.read_vstr()?;
+        let str2 = rdr.read_vstr()?;
+        let links = rdr.read_links()?;
+        if Self::ORIENTED_CLASSES.contains(&class_name.as_str()) {
+            let _orient = rdr.read_f32_array::<9>()?;
+        }
+        let _color = rdr.read_u32::<LittleEndian>()?;
+        Ok(Self {
+            uid,
+            class_name,
+            pos,
+            script_name,
+            delay,
+            str1,
+            str2,
+            links,
+        })
+    }
+}
+
+pub fn read_lightmaps(rfl: &RflFile) -> Result<Vec<Lightmap>> {
+    read_section_records(rfl, LIGHTMAPS_SECTION, Lightmap::read)
+}
+
+fn skip_bytes(rdr: &mut &[u8], num_bytes: usize) -> Result<()> {
+    if rdr.len() < num_bytes {
+        return Err(Error::from(ErrorKind::UnexpectedEof));
+    }
+    *rdr = &rdr[num_bytes..];
+    Ok(())
+}
+
+fn skip_room(rdr: &mut &[u8]) -> Result<()> {
+    // id, bounding box
+    skip_bytes(rdr, 4 + 2 * 12)?;
+    // is_skyroom, is_cold, is_outside, is_airlock, is_liquid_room, has_ambient_light, is_subroom, has_alpha
+    let mut flags = [0_u8; 8];
+    rdr.read_exact(&mut flags)?;
+    let _life = rdr.read_f32::<LittleEndian>()?;
+    let _eax_effect = rdr.read_vstr()?;
+    if flags[4] != 0 {
+        // depth, color
+        skip_bytes(rdr, 4 + 4)?;
+        let _surface_texture = rdr.read_vstr()?;
+        // visibility, type, alpha, waveform, scroll_u, scroll_v
+        skip_bytes(rdr, 6 * 4)?;
+    }
+    if flags[5] != 0 {
+        let _ambient_color = rdr.read_u32::<LittleEndian>()?;
+    }
+    Ok(())
+}
+
+/// Returns index of the room using every lightmap (None for lightmaps not used by any face). Lightmaps are
+/// referenced by lightmap surfaces of static geometry faces. The whole section must be parsed successfully so a
+/// layout mismatch is reported as an error instead of producing wrong rooms
+pub fn read_lightmap_rooms(rfl: &RflFile, num_lightmaps: usize) -> Result<Vec<Option<u32>>> {
+    let mut rooms = vec![None; num_lightmaps];
+    let Some(section) = rfl.find_section(STATIC_GEOMETRY_SECTION) else {
+        return Ok(rooms);
+    };
+    let rdr = &mut section.data.as_slice();
+    // unknown, modifiability, name
+    skip_bytes(rdr, 10)?;
+    let num_textures = rdr.read_u32::<LittleEndian>()?;
+    for _ in 0..num_textures {
+        let _texture = rdr.read_vstr()?;
+    }
+    let num_face_scroll_data = rdr.read_u32::<LittleEndian>()?;
+    for _ in 0..num_face_scroll_data {
+        // face_id, uv_velocity
+        skip_bytes(rdr, 4 + 8)?;
+    }
+    let num_rooms = rdr.read_u32::<LittleEndian>()?;
+    for _ in 0..num_rooms {
+        skip_room(rdr)?;
+    }
+    let num_subroom_lists = rdr.read_u32::<LittleEndian>()?;
+    for _ in 0..num_subroom_lists {
+        let _room_index = rdr.read_u32::<LittleEndian>()?;
+        let _subrooms = rdr.read_links()?;
+    }
+    let num_portals = rdr.read_u32::<LittleEndian>()?;
+    for _ in 0..num_portals {
+        // room indices, two points
+        skip_bytes(rdr, 2 * 4 + 2 * 12)?;
+    }
+    let num_vertices = rdr.read_u32::<LittleEndian>()?;
+    for _ in 0..num_vertices {
+        skip_bytes(rdr, 12)?;
+    }
+    let num_faces = rdr.read_u32::<LittleEndian>()?;
+    let mut face_surfaces = Vec::new();
+    for _ in 0..num_faces {
+        // plane, texture
+        skip_bytes(rdr, 16 + 4)?;
+        let surface_index = rdr.read_i32::<LittleEndian>()?;
+        // face_id, reserved1, portal_index_plus_2, flags, reserved2, smoothing_groups
+        skip_bytes(rdr, 4 + 8 + 4 + 2 + 2 + 4)?;
+        let room_index = rdr.read_u32::<LittleEndian>()?;
+        if room_index >= num_rooms {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid face room index {}", room_index),
+            ));
+        }
+        let num_face_vertices = rdr.read_u32::<LittleEndian>()?;
+        // vertex index, texture coordinates and lightmap coordinates if face has a lightmap surface
+        let vertex_size = if surface_index == -1 { 12 } else { 20 };
+        for _ in 0..num_face_vertices {
+            skip_bytes(rdr, vertex_size)?;
+        }
+        if let Ok(surface_index) = usize::try_from(surface_index) {
+            face_surfaces.push((surface_index, room_index));
+        }
+    }
+    let num_surfaces = rdr.read_u32::<LittleEndian>()?;
+    let mut surface_lightmaps = Vec::new();
+    for _ in 0..num_surfaces {
+        surface_lightmaps.push(rdr.read_u32::<LittleEndian>()? as usize);
+        skip_bytes(rdr, 88)?;
+    }
+    if !rdr.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "unexpected data at the end of static geometry section",
+        ));
+    }
+    for (surface_index, room_index) in face_surfaces {
+        let lightmap_index = surface_lightmaps.get(surface_index).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid face surface index {}", surface_index),
+            )
+        })?;
+        let room = rooms.get_mut(*lightmap_index).ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidData,
+                format!("invalid surface lightmap index {}", lightmap_index),
+            )
+        })?;
+        // lightmaps shared by multiple rooms are assigned to the room of the first face using them
+        room.get_or_insert(room_index);
+    }
+    Ok(rooms)
+}
+
+pub fn read_entities(rfl: &RflFile) -> Result<Vec<Entity>> {
+    read_section_records(rfl, ENTITIES_SECTION, Entity::read)
+}
+
+pub fn read_items(rfl: &RflFile) -> Result<Vec<Item>> {
+    read_section_records(rfl, ITEMS_SECTION, Item::read)
+}
+
+pub fn read_clutters(rfl: &RflFile) -> Result<Vec<Clutter>> {
+    read_section_records(rfl, CLUTTERS_SECTION, Clutter::read)
+}
+
+pub fn read_events(rfl: &RflFile) -> Result<Vec<Event>> {
+    read_section_records(rfl, EVENTS_SECTION, Event::read)
+}
+
+pub fn count_movers(rfl: &RflFile) -> Result<u32> {
+    // Movers section contains brushes - only their number is needed here
+    let Some(section) = rfl.find_section(MOVERS_SECTION) else {
+        return Ok(0);
+    };
+    section.data.as_slice().read_u32::<LittleEndian>()
+}
+
+// Lists of files used by the level. Every list contains file names followed by reference counts.
+pub fn read_file_list(rfl: &RflFile, section_type: u32) -> Result<Vec<String>> {
+    read_section_records(rfl, section_type, |rdr| rdr.read_vstr())
+}
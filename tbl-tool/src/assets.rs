@@ -0,0 +1,49 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Set of asset file names available to the game. Names are compared case-insensitively like in RF.
+#[derive(Default)]
+pub struct AssetIndex {
+    names: HashSet<String>,
+}
+
+impl AssetIndex {
+    pub fn add_path(&mut self, path: &Path) -> io::Result<()> {
+        if path.is_dir() {
+            self.add_dir(path)
+        } else {
+            self.add_vpp(path)
+        }
+    }
+
+    fn add_dir(&mut self, dir: &Path) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                self.add_dir(&path)?;
+            } else if let Some(name) = path.file_name() {
+                self.names.insert(name.to_string_lossy().to_lowercase());
+            }
+        }
+        Ok(())
+    }
+
+    fn add_vpp(&mut self, vpp_path: &Path) -> io::Result<()> {
+        let names = vpp::read_vpp_file_names(&vpp_path.to_string_lossy())
+            .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", vpp_path.display(), e)))?;
+        for name in names {
+            self.names.insert(name.to_lowercase());
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.names.contains(&name.to_lowercase())
+    }
+
+    pub fn num_files(&self) -> usize {
+        self.names.len()
+    }
+}
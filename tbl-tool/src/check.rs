@@ -0,0 +1,70 @@
+use crate::assets::AssetIndex;
+use std::path::Path;
+use tbl::TblFile;
+
+// Extensions of files that can be referenced by tables
+const ASSET_EXTENSIONS: [&str; 10] = [
+    "v3m", "v3c", "v3d", "vfx", "tga", "vbm", "wav", "rfa", "mvf", "vcm",
+];
+
+fn get_extension(name: &str) -> Option<String> {
+    Path::new(name)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+}
+
+fn alternative_names(name: &str) -> Vec<String> {
+    let path = Path::new(name);
+    let alt_exts: &[&str] = match get_extension(name).as_deref() {
+        // game loads VBM when TGA is not found
+        Some("tga") => &["vbm"],
+        Some("v3d") => &["v3m", "v3c"],
+        _ => &[],
+    };
+    alt_exts
+        .iter()
+        .map(|ext| path.with_extension(ext).to_string_lossy().into_owned())
+        .collect()
+}
+
+fn asset_exists(name: &str, assets: &AssetIndex) -> bool {
+    assets.contains(name) || alternative_names(name).iter().any(|n| assets.contains(n))
+}
+
+/// Checks if all files referenced by the table exist. Returns number of missing files.
+pub fn check_table(tbl_path: &Path, tbl: &TblFile, assets: &AssetIndex) -> usize {
+    let mut num_missing = 0;
+    for record in tbl.records() {
+        let record_name = tbl.record_name(&record).unwrap_or("<unnamed>");
+        for field in tbl.fields(&record) {
+            let Some(value) = field.value.as_str() else {
+                continue;
+            };
+            let is_asset =
+                get_extension(value).is_some_and(|ext| ASSET_EXTENSIONS.contains(&ext.as_str()));
+            if !is_asset {
+                continue;
+            }
+            if asset_exists(value, assets) {
+                info!(
+                    "{}: {} '{}': found {}",
+                    tbl_path.display(),
+                    record.section,
+                    record_name,
+                    value
+                );
+            } else {
+                error!(
+                    "{}: {} '{}': field '{}' references missing file {}",
+                    tbl_path.display(),
+                    record.section,
+                    record_name,
+                    field.name,
+                    value
+                );
+                num_missing += 1;
+            }
+        }
+    }
+    num_missing
+}
@@ -0,0 +1,175 @@
+//! Conversion of tables to JSON and back.
+//!
+//! JSON document contains every line of the table in order. Sections, section ends and fields are stored in
+//! structured form and every line also keeps its original text. When converting back, the original text is used if
+//! the structured data still matches it, so unmodified lines (including comments and formatting) are restored exactly.
+//! Modified fields keep their original spacing and trailing comment.
+//!
+//! Example line: `{"field": "Name", "sub": false, "value": "Shotgun", "text": "$Name: \"Shotgun\""}`
+
+use serde_json::{json, Map, Value as JsonValue};
+use tbl::encoding::Encoding;
+use tbl::{Field, Line, TblFile, Value};
+
+fn split_line_ending(text: &str) -> (&str, &str) {
+    let content = text.trim_end_matches(['\r', '\n']);
+    text.split_at(content.len())
+}
+
+fn value_to_json(value: &Value) -> JsonValue {
+    match value {
+        Value::String(s) => json!(s),
+        Value::Integer(i) => json!(i),
+        Value::Float(f) if f.is_finite() => json!(f),
+        // JSON has no representation of NaN and infinity
+        Value::Float(f) => json!({ "raw": f.to_string() }),
+        Value::Bool(b) => json!(b),
+        Value::Raw(s) => json!({ "raw": s }),
+    }
+}
+
+fn value_from_json(value: &JsonValue) -> Result<Value, String> {
+    match value {
+        JsonValue::String(s) => Ok(Value::String(s.clone())),
+        JsonValue::Number(n) if n.is_f64() => Ok(Value::Float(n.as_f64().unwrap())),
+        JsonValue::Number(n) => n
+            .as_i64()
+            .map(Value::Integer)
+            .ok_or_else(|| format!("integer out of range: {}", n)),
+        JsonValue::Bool(b) => Ok(Value::Bool(*b)),
+        JsonValue::Object(map) => map
+            .get("raw")
+            .and_then(JsonValue::as_str)
+            .map(|s| Value::Raw(s.to_owned()))
+            .ok_or_else(|| format!("expected object with 'raw' string: {}", value)),
+        _ => Err(format!("unsupported value: {}", value)),
+    }
+}
+
+pub fn tbl_to_json(tbl: &TblFile, encoding: Encoding) -> JsonValue {
+    let default_line_ending = tbl.line_ending();
+    let lines: Vec<JsonValue> = tbl
+        .raw_lines()
+        .map(|(line, raw)| {
+            let mut obj = Map::new();
+            match line {
+                Line::Section(name) => {
+                    obj.insert("section".into(), json!(name));
+                }
+                Line::End => {
+                    obj.insert("end".into(), json!(true));
+                }
+                Line::Field(field) => {
+                    obj.insert("field".into(), json!(field.name));
+                    obj.insert("sub".into(), json!(field.is_sub_field));
+                    obj.insert("value".into(), value_to_json(&field.value));
+                }
+                Line::Other => {}
+            }
+            if let Some(raw) = raw {
+                let (text, line_ending) = split_line_ending(raw);
+                obj.insert("text".into(), json!(text));
+                if line_ending != default_line_ending {
+                    obj.insert("line_ending".into(), json!(line_ending));
+                }
+            }
+            JsonValue::Object(obj)
+        })
+        .collect();
+    json!({
+        "encoding": encoding.name(),
+        "line_ending": default_line_ending,
+        "lines": lines,
+    })
+}
+
+fn same_field(a: &Field, b: &Field) -> bool {
+    // values are also compared as text because NaN is not equal to itself and it is stored as raw value
+    a.name == b.name
+        && a.is_sub_field == b.is_sub_field
+        && (a.value == b.value || a.value.to_string() == b.value.to_string())
+}
+
+/// Parses original text of a line (without line ending)
+fn parse_text(text: &str) -> Option<Line> {
+    TblFile::parse(text).ok()?.lines().next().cloned()
+}
+
+fn line_from_json(obj: &Map<String, JsonValue>) -> Result<Option<Line>, String> {
+    if let Some(name) = obj.get("section") {
+        let name = name.as_str().ok_or("section name should be a string")?;
+        return Ok(Some(Line::Section(name.to_owned())));
+    }
+    if obj.contains_key("end") {
+        return Ok(Some(Line::End));
+    }
+    let Some(name) = obj.get("field") else {
+        return Ok(None);
+    };
+    let name = name.as_str().ok_or("field name should be a string")?;
+    let is_sub_field = obj.get("sub").and_then(JsonValue::as_bool).unwrap_or(false);
+    let value = value_from_json(obj.get("value").ok_or("field has no value")?)?;
+    let text = obj.get("text").and_then(JsonValue::as_str);
+    // keep spacing and trailing comment of the original line
+    let mut field = match text.and_then(parse_text) {
+        Some(Line::Field(f)) => f,
+        _ => Field::new(name, value.clone()),
+    };
+    field.name = name.to_owned();
+    field.is_sub_field = is_sub_field;
+    field.value = value;
+    Ok(Some(Line::Field(field)))
+}
+
+fn format_line(line: &Line) -> String {
+    match line {
+        Line::Section(name) => format!("#{}", name),
+        Line::End => "#End".to_owned(),
+        Line::Field(field) => field.to_string(),
+        Line::Other => String::new(),
+    }
+}
+
+/// Returns encoding of the original table (Windows-1252 if not known)
+pub fn get_encoding(doc: &JsonValue) -> Encoding {
+    doc.get("encoding")
+        .and_then(JsonValue::as_str)
+        .and_then(Encoding::from_name)
+        .unwrap_or(Encoding::Windows1252)
+}
+
+pub fn json_to_tbl(doc: &JsonValue) -> Result<String, String> {
+    let default_line_ending = doc
+        .get("line_ending")
+        .and_then(JsonValue::as_str)
+        .unwrap_or("\r\n");
+    let lines = doc
+        .get("lines")
+        .and_then(JsonValue::as_array)
+        .ok_or("expected 'lines' array")?;
+    let mut output = String::new();
+    for (i, line_json) in lines.iter().enumerate() {
+        let obj = line_json
+            .as_object()
+            .ok_or_else(|| format!("line {}: expected an object", i + 1))?;
+        let line = line_from_json(obj).map_err(|e| format!("line {}: {}", i + 1, e))?;
+        let text = obj.get("text").and_then(JsonValue::as_str);
+        let unchanged = match (&line, text.and_then(parse_text)) {
+            (None, _) => true,
+            (Some(Line::Section(a)), Some(Line::Section(b))) => a == &b,
+            (Some(Line::End), Some(Line::End)) => true,
+            (Some(Line::Field(a)), Some(Line::Field(b))) => same_field(a, &b),
+            _ => false,
+        };
+        match (unchanged, text) {
+            (true, Some(text)) => output.push_str(text),
+            _ => output.push_str(&line.as_ref().map(format_line).unwrap_or_default()),
+        }
+        let line_ending = obj
+            .get("line_ending")
+            .and_then(JsonValue::as_str)
+            .unwrap_or(default_line_ending);
+        output.push_str(line_ending);
+    }
+    Ok(output)
+}
@@ -0,0 +1,175 @@
+mod assets;
+mod check;
+mod json;
+
+use assets::AssetIndex;
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use log::{Level, LevelFilter};
+use std::io::Write;
+use std::{error::Error, fs, path::Path, path::PathBuf};
+use tbl::encoding::{self, Encoding};
+use tbl::TblFile;
+
+#[macro_use]
+extern crate log;
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum EncodingArg {
+    /// UTF-8 for files that are valid UTF-8 with non-ASCII characters, Windows-1252 otherwise
+    Auto,
+    /// Encoding used by RF
+    Windows1252,
+    Utf8,
+}
+
+impl EncodingArg {
+    fn to_encoding(self) -> Option<Encoding> {
+        match self {
+            EncodingArg::Auto => None,
+            EncodingArg::Windows1252 => Some(Encoding::Windows1252),
+            EncodingArg::Utf8 => Some(Encoding::Utf8),
+        }
+    }
+}
+
+fn load_tbl(path: &Path, encoding: EncodingArg) -> Result<(TblFile, Encoding)> {
+    let bytes = fs::read(path)?;
+    let (text, encoding) = encoding::decode(&bytes, encoding.to_encoding());
+    let tbl = TblFile::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok((tbl, encoding))
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check if files referenced by tables (meshes, textures, sounds, etc.) exist
+    Check {
+        /// Input TBL files, e.g. items.tbl, weapons.tbl
+        #[clap(required = true)]
+        tbl_files: Vec<PathBuf>,
+
+        /// Directory or packfile (VPP) containing game assets. Can be used many times
+        #[clap(short, long = "assets", required = true)]
+        assets: Vec<PathBuf>,
+    },
+    /// Convert table to JSON (lines are stored in order together with their original text so the conversion back
+    /// is lossless)
+    ToJson {
+        /// Input TBL file
+        tbl_file: PathBuf,
+
+        /// Output JSON file. By default input filename with .json extension is used
+        #[clap(short, long = "output")]
+        output_file: Option<PathBuf>,
+    },
+    /// Convert JSON created by to-json command back to a table
+    FromJson {
+        /// Input JSON file
+        json_file: PathBuf,
+
+        /// Output TBL file. By default input filename with .tbl extension is used
+        #[clap(short, long = "output")]
+        output_file: Option<PathBuf>,
+    },
+}
+
+#[derive(Parser, Debug)]
+#[clap(author, version, about, about = "TBL (game data table) tool")]
+pub struct Args {
+    #[clap(subcommand)]
+    command: Command,
+
+    /// Enable verbose output. Can be used up to 3 times to increase verbosity
+    #[clap(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Do not print warnings (errors are still printed)
+    #[clap(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Text encoding of TBL files. When converting from JSON, auto uses the encoding of the original table
+    #[clap(long, value_enum, global = true, default_value_t = EncodingArg::Auto)]
+    encoding: EncodingArg,
+
+    /// Write '?' instead of characters that cannot be represented in Windows-1252 (by default it is an error)
+    #[clap(long, global = true)]
+    replace_unmappable: bool,
+}
+
+fn init_logger(verbose: u8, quiet: bool) {
+    let level = if quiet {
+        LevelFilter::Error
+    } else {
+        match verbose {
+            0 => LevelFilter::Warn,
+            1 => LevelFilter::Info,
+            2 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        }
+    };
+    env_logger::Builder::new()
+        .filter_module("tbl_tool", level)
+        .format(|buf, record| match record.level() {
+            Level::Error => writeln!(buf, "Error: {}", record.args()),
+            Level::Warn => writeln!(buf, "Warning! {}", record.args()),
+            _ => writeln!(buf, "{}", record.args()),
+        })
+        .init();
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    init_logger(args.verbose, args.quiet);
+    info!("tbl-tool {}", env!("CARGO_PKG_VERSION"));
+
+    match &args.command {
+        Command::Check { tbl_files, assets } => {
+            let mut asset_index = AssetIndex::default();
+            for path in assets {
+                asset_index.add_path(path)?;
+            }
+            info!("Found {} asset files", asset_index.num_files());
+            let mut num_missing = 0;
+            for tbl_path in tbl_files {
+                let (tbl, _) = load_tbl(tbl_path, args.encoding)?;
+                num_missing += check::check_table(tbl_path, &tbl, &asset_index);
+            }
+            if num_missing > 0 {
+                error!("Found {} references to missing files", num_missing);
+                std::process::exit(1);
+            }
+        }
+        Command::ToJson {
+            tbl_file,
+            output_file,
+        } => {
+            let (tbl, encoding) = load_tbl(tbl_file, args.encoding)?;
+            let output_file = output_file
+                .clone()
+                .unwrap_or_else(|| tbl_file.with_extension("json"));
+            let json = serde_json::to_string_pretty(&json::tbl_to_json(&tbl, encoding))?;
+            fs::write(&output_file, json)?;
+        }
+        Command::FromJson {
+            json_file,
+            output_file,
+        } => {
+            let doc: serde_json::Value = serde_json::from_slice(&fs::read(json_file)?)?;
+            let text =
+                json::json_to_tbl(&doc).map_err(|e| format!("{}: {}", json_file.display(), e))?;
+            let encoding = args
+                .encoding
+                .to_encoding()
+                .unwrap_or_else(|| json::get_encoding(&doc));
+            let bytes = encoding::encode(&text, encoding, args.replace_unmappable)
+                .map_err(|e| format!("{}: {}", json_file.display(), e))?;
+            let output_file = output_file
+                .clone()
+                .unwrap_or_else(|| json_file.with_extension("tbl"));
+            fs::write(&output_file, bytes)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,380 @@
+//! Parser and writer of RF table files (`*.tbl`), e.g. `items.tbl`, `weapons.tbl` or `strings.tbl`.
+//!
+//! Table is kept as a list of lines so it can be written back without any change in formatting of
+//! lines that were not modified.
+
+pub use rf_encoding as encoding;
+
+use std::error::Error;
+use std::fmt::{self, Display};
+use std::ops::Range;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    // Values that are not recognized, e.g. lists
+    Raw(String),
+}
+
+impl Value {
+    fn parse(text: &str) -> Self {
+        if let Some(s) = text.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+            if !s.contains('"') {
+                return Value::String(s.to_owned());
+            }
+        }
+        match text.to_lowercase().as_str() {
+            "true" => return Value::Bool(true),
+            "false" => return Value::Bool(false),
+            _ => {}
+        }
+        if let Ok(i) = text.parse::<i64>() {
+            Value::Integer(i)
+        } else if let Ok(f) = text.parse::<f64>() {
+            Value::Float(f)
+        } else {
+            Value::Raw(text.to_owned())
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) | Value::Raw(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Integer(i) => Some(*i as f64),
+            Value::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::String(s) => write!(f, "\"{}\"", s),
+            Value::Integer(i) => write!(f, "{}", i),
+            // make sure float is not written as an integer
+            Value::Float(v) if v.fract() == 0.0 => write!(f, "{:.1}", v),
+            Value::Float(v) => write!(f, "{}", v),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Raw(s) => f.write_str(s),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Field {
+    pub name: String,
+    // true for '+' prefixed fields (sub-fields), false for '$' prefixed fields
+    pub is_sub_field: bool,
+    pub value: Value,
+    // whitespace between colon and value
+    separator: String,
+    // text after value, e.g. a comment
+    trailing: String,
+}
+
+impl Field {
+    pub fn new(name: &str, value: Value) -> Self {
+        Self {
+            name: name.to_owned(),
+            is_sub_field: false,
+            value,
+            separator: " ".to_owned(),
+            trailing: String::new(),
+        }
+    }
+}
+
+impl Display for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prefix = if self.is_sub_field { '+' } else { '$' };
+        write!(
+            f,
+            "{}{}:{}{}{}",
+            prefix, self.name, self.separator, self.value, self.trailing
+        )
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Line {
+    // Section start, e.g. "#Items"
+    Section(String),
+    // Section end ("#End")
+    End,
+    Field(Field),
+    // Comments, empty lines and anything else that is not interpreted
+    Other,
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    line: Line,
+    // Original text including line ending. It is cleared when the line is modified.
+    raw: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for ParseError {}
+
+/// Group of fields starting with the first field of a section entry, e.g. `$Class Name`.
+#[derive(Clone, Debug)]
+pub struct Record {
+    pub section: String,
+    entries: Range<usize>,
+}
+
+#[derive(Clone, Debug)]
+pub struct TblFile {
+    entries: Vec<Entry>,
+    // used for lines that were added or modified
+    line_ending: &'static str,
+}
+
+fn strip_comment(text: &str) -> (&str, &str) {
+    let pos = text.find("//").unwrap_or(text.len());
+    let value_end = text[..pos].trim_end().len();
+    text.split_at(value_end)
+}
+
+fn parse_field(text: &str, line_num: usize) -> Result<Field, ParseError> {
+    let is_sub_field = text.starts_with('+');
+    let Some((name, rest)) = text[1..].split_once(':') else {
+        return Err(ParseError {
+            line: line_num,
+            message: format!("expected ':' in field definition: {}", text.trim_end()),
+        });
+    };
+    // only spaces and tabs - line ending of a field with an empty value is not a part of the separator
+    let value_start = rest.len() - rest.trim_start_matches([' ', '\t']).len();
+    let (separator, value_and_trailing) = rest.split_at(value_start);
+    let (value_text, trailing) = if let Some(quoted) = value_and_trailing.strip_prefix('"') {
+        // quoted strings can contain comment sequence and span multiple lines
+        let end = quoted.find('"').map(|p| p + 2).ok_or_else(|| ParseError {
+            line: line_num,
+            message: "unterminated string".to_owned(),
+        })?;
+        value_and_trailing.split_at(end)
+    } else {
+        strip_comment(value_and_trailing)
+    };
+    Ok(Field {
+        name: name.trim().to_owned(),
+        is_sub_field,
+        value: Value::parse(value_text),
+        separator: separator.to_owned(),
+        trailing: trailing.trim_end_matches(['\r', '\n']).to_owned(),
+    })
+}
+
+fn parse_line(text: &str, line_num: usize) -> Result<Line, ParseError> {
+    let trimmed = text.trim();
+    if trimmed.starts_with('$') || trimmed.starts_with('+') {
+        Ok(Line::Field(parse_field(text.trim_start(), line_num)?))
+    } else if let Some(name) = trimmed.strip_prefix('#') {
+        let (name, _) = strip_comment(name);
+        if name.eq_ignore_ascii_case("end") {
+            Ok(Line::End)
+        } else {
+            Ok(Line::Section(name.to_owned()))
+        }
+    } else {
+        Ok(Line::Other)
+    }
+}
+
+impl TblFile {
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut entries = Vec::new();
+        let line_ending = if text.contains("\r\n") || !text.contains('\n') {
+            "\r\n"
+        } else {
+            "\n"
+        };
+        let mut lines = text.split_inclusive('\n').enumerate();
+        while let Some((i, line)) = lines.next() {
+            let mut raw = line.to_owned();
+            // join lines of a multi-line quoted string
+            while is_field_line(&raw) && has_unterminated_quote(&raw) {
+                let Some((_, next_line)) = lines.next() else {
+                    break;
+                };
+                raw.push_str(next_line);
+            }
+            let line = parse_line(&raw, i + 1)?;
+            entries.push(Entry {
+                line,
+                raw: Some(raw),
+            });
+        }
+        Ok(Self {
+            entries,
+            line_ending,
+        })
+    }
+
+    pub fn lines(&self) -> impl Iterator<Item = &Line> {
+        self.entries.iter().map(|e| &e.line)
+    }
+
+    /// Returns lines together with their original text (including line ending). Text is `None` for lines that were
+    /// added or modified.
+    pub fn raw_lines(&self) -> impl Iterator<Item = (&Line, Option<&str>)> {
+        self.entries.iter().map(|e| (&e.line, e.raw.as_deref()))
+    }
+
+    /// Line ending used for lines that were added or modified (CRLF unless the parsed text uses only LF)
+    pub fn line_ending(&self) -> &'static str {
+        self.line_ending
+    }
+
+    /// Returns all records in the file. Records have to be obtained again after a new field is added
+    /// by [`TblFile::set`].
+    pub fn records(&self) -> Vec<Record> {
+        let mut records = Vec::new();
+        let mut section = String::new();
+        let mut record_key: Option<String> = None;
+        let mut current: Option<Record> = None;
+        for (i, entry) in self.entries.iter().enumerate() {
+            match &entry.line {
+                Line::Section(name) => {
+                    records.extend(current.take());
+                    section = name.clone();
+                    record_key = None;
+                }
+                Line::End => {
+                    records.extend(current.take());
+                    record_key = None;
+                }
+                Line::Field(field) => {
+                    let key = record_key.get_or_insert_with(|| field.name.clone());
+                    if !field.is_sub_field && key.eq_ignore_ascii_case(&field.name) {
+                        records.extend(current.take());
+                        current = Some(Record {
+                            section: section.clone(),
+                            entries: i..i + 1,
+                        });
+                    } else if let Some(record) = current.as_mut() {
+                        record.entries.end = i + 1;
+                    }
+                }
+                Line::Other => {}
+            }
+        }
+        records.extend(current);
+        records
+    }
+
+    pub fn records_in_section(&self, section: &str) -> Vec<Record> {
+        self.records()
+            .into_iter()
+            .filter(|r| r.section.eq_ignore_ascii_case(section))
+            .collect()
+    }
+
+    pub fn fields<'a>(&'a self, record: &Record) -> impl Iterator<Item = &'a Field> {
+        self.entries[record.entries.clone()]
+            .iter()
+            .filter_map(|e| match &e.line {
+                Line::Field(f) => Some(f),
+                _ => None,
+            })
+    }
+
+    pub fn get<'a>(&'a self, record: &Record, name: &str) -> Option<&'a Value> {
+        self.fields(record)
+            .find(|f| f.name.eq_ignore_ascii_case(name))
+            .map(|f| &f.value)
+    }
+
+    /// Returns value of the first field that identifies the record, e.g. `$Name`
+    pub fn record_name<'a>(&'a self, record: &Record) -> Option<&'a str> {
+        self.fields(record).next().and_then(|f| f.value.as_str())
+    }
+
+    /// Changes value of an existing field or appends a new field at the end of the record.
+    pub fn set(&mut self, record: &Record, name: &str, value: Value) {
+        let range = record.entries.clone();
+        let existing = self.entries[range.clone()]
+            .iter_mut()
+            .find(|e| matches!(&e.line, Line::Field(f) if f.name.eq_ignore_ascii_case(name)));
+        if let Some(entry) = existing {
+            if let Line::Field(field) = &mut entry.line {
+                field.value = value;
+            }
+            entry.raw = None;
+        } else {
+            self.entries.insert(
+                range.end,
+                Entry {
+                    line: Line::Field(Field::new(name, value)),
+                    raw: None,
+                },
+            );
+        }
+    }
+}
+
+fn is_field_line(text: &str) -> bool {
+    let trimmed = text.trim_start();
+    trimmed.starts_with('$') || trimmed.starts_with('+')
+}
+
+fn has_unterminated_quote(text: &str) -> bool {
+    let (_, value) = text.split_once(':').unwrap_or_default();
+    let value = value.trim_start();
+    value.starts_with('"') && value.matches('"').count() % 2 == 1
+}
+
+impl Display for TblFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entry in &self.entries {
+            if let Some(raw) = &entry.raw {
+                f.write_str(raw)?;
+                continue;
+            }
+            match &entry.line {
+                Line::Section(name) => write!(f, "#{}", name)?,
+                Line::End => write!(f, "#End")?,
+                Line::Field(field) => write!(f, "{}", field)?,
+                Line::Other => {}
+            }
+            f.write_str(self.line_ending)?;
+        }
+        Ok(())
+    }
+}
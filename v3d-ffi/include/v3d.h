@@ -0,0 +1,59 @@
+/* C interface of the v3d library (GLTF to V3M/V3C conversion) */
+#ifndef V3D_H
+#define V3D_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Return codes (the same values are used as vmesh exit codes) */
+#define V3D_OK 0
+#define V3D_ERROR_INTERNAL 1
+#define V3D_ERROR_USAGE 2
+#define V3D_ERROR_INPUT 3
+#define V3D_ERROR_VALIDATION 4
+#define V3D_ERROR_IO 5
+
+/* Conversion flags */
+#define V3D_CONVERT_DASH_FACTION_LIMITS 0x1
+
+typedef struct v3d_mesh_info {
+    int32_t is_character;
+    uint32_t num_lod_meshes;
+    uint32_t num_materials;
+    uint32_t num_cspheres;
+    uint32_t num_bones;
+    uint32_t num_vertices;
+    uint32_t num_triangles;
+    float bbox_min[3];
+    float bbox_max[3];
+} v3d_mesh_info;
+
+/* Converts GLB (or GLTF with embedded buffers) into V3M (or V3C if the model is skinned).
+   Output buffer must be released with v3d_free_buffer. */
+int v3d_convert_glb(const uint8_t *glb, size_t glb_len, uint32_t flags, uint8_t **out_data, size_t *out_len);
+
+/* Receives conversion stage name and its completion in percent (0-100) */
+typedef void (*v3d_progress_callback)(const char *stage, float percentage, void *user_data);
+
+/* Same as v3d_convert_glb but calls progress (if not NULL) from the calling thread when conversion progresses */
+int v3d_convert_glb_with_progress(const uint8_t *glb, size_t glb_len, uint32_t flags, v3d_progress_callback progress,
+    void *user_data, uint8_t **out_data, size_t *out_len);
+
+/* Releases buffer returned by v3d_convert_glb or v3d_convert_glb_with_progress */
+void v3d_free_buffer(uint8_t *data, size_t len);
+
+/* Parses V3M/V3C file and returns its basic information */
+int v3d_parse_v3m(const uint8_t *data, size_t len, v3d_mesh_info *info);
+
+/* Returns message of the last error in the calling thread or NULL */
+const char *v3d_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* V3D_H */
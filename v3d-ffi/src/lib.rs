@@ -0,0 +1,240 @@
+//! C interface of the v3d library. Functions are declared in `include/v3d.h`.
+//!
+//! All functions return `V3D_OK` (0) on success or the error category exit code used by vmesh on failure. Error
+//! message of the last failed call can be retrieved using `v3d_last_error`.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, c_void, CString};
+use std::io::Cursor;
+use std::{ptr, slice};
+use v3d::errors::ErrorCategory;
+use v3d::limits::Limits;
+use v3d::progress::ProgressCallback;
+use v3d::{v3mc, v3mc_convert, ConvertOptions};
+
+pub const V3D_OK: i32 = 0;
+
+/// Validate geometry against Dash Faction limits instead of stock game limits
+pub const V3D_CONVERT_DASH_FACTION_LIMITS: u32 = 0x1;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn fail<E: ToString>(category: ErrorCategory, err: E) -> i32 {
+    let message = err.to_string().replace('\0', " ");
+    let message = CString::new(message).expect("message should not contain null characters");
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+    category.exit_code()
+}
+
+unsafe fn input_slice<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    if data.is_null() {
+        None
+    } else {
+        Some(slice::from_raw_parts(data, len))
+    }
+}
+
+/// Basic information about V3M/V3C file
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct V3dMeshInfo {
+    /// Non-zero for character meshes (V3C)
+    pub is_character: i32,
+    pub num_lod_meshes: u32,
+    pub num_materials: u32,
+    pub num_cspheres: u32,
+    pub num_bones: u32,
+    /// Number of vertices in the most detailed LOD level of all submeshes
+    pub num_vertices: u32,
+    /// Number of triangles in the most detailed LOD level of all submeshes
+    pub num_triangles: u32,
+    pub bbox_min: [f32; 3],
+    pub bbox_max: [f32; 3],
+}
+
+fn to_u32(count: usize) -> u32 {
+    count.try_into().unwrap_or(u32::MAX)
+}
+
+fn get_mesh_info(file: &v3mc::File) -> V3dMeshInfo {
+    let mut info = V3dMeshInfo {
+        is_character: i32::from(file.header.signature == v3mc::V3C_SIGNATURE),
+        num_lod_meshes: to_u32(file.lod_meshes.len()),
+        num_cspheres: to_u32(file.cspheres.len()),
+        num_bones: to_u32(file.bones.len()),
+        ..V3dMeshInfo::default()
+    };
+    if !file.lod_meshes.is_empty() {
+        info.bbox_min = [f32::MAX; 3];
+        info.bbox_max = [f32::MIN; 3];
+    }
+    for lod_mesh in &file.lod_meshes {
+        // counts come from the input file so their sums are saturated instead of overflowing
+        info.num_materials = info
+            .num_materials
+            .saturating_add(to_u32(lod_mesh.materials.len()));
+        if let Some(mesh) = lod_mesh.meshes.first() {
+            for chunk in &mesh.chunks {
+                info.num_vertices = info.num_vertices.saturating_add(u32::from(chunk.num_vecs));
+                info.num_triangles = info
+                    .num_triangles
+                    .saturating_add(u32::from(chunk.num_faces));
+            }
+        }
+        // submesh bounding box is relative to the submesh origin
+        #[allow(clippy::needless_range_loop)]
+        for axis in 0..3 {
+            let min = lod_mesh.offset[axis] + lod_mesh.bbox_min[axis];
+            let max = lod_mesh.offset[axis] + lod_mesh.bbox_max[axis];
+            info.bbox_min[axis] = info.bbox_min[axis].min(min);
+            info.bbox_max[axis] = info.bbox_max[axis].max(max);
+        }
+    }
+    info
+}
+
+/// Progress callback receiving stage name, completion of the stage in percent and user data pointer
+pub type V3dProgressCallback =
+    Option<unsafe extern "C" fn(stage: *const c_char, percentage: f32, user_data: *mut c_void)>;
+
+/// User data of progress callback. Conversion context must be thread-safe but the callback is only called from the
+/// thread that started the conversion so passing the pointer around is fine.
+struct UserData(*mut c_void);
+
+unsafe impl Send for UserData {}
+unsafe impl Sync for UserData {}
+
+impl UserData {
+    fn get(&self) -> *mut c_void {
+        self.0
+    }
+}
+
+fn convert_options(flags: u32) -> ConvertOptions {
+    let limits = if flags & V3D_CONVERT_DASH_FACTION_LIMITS != 0 {
+        Limits::DASH_FACTION
+    } else {
+        Limits::STOCK
+    };
+    ConvertOptions {
+        limits,
+        ..ConvertOptions::default()
+    }
+}
+
+unsafe fn write_output(data: Vec<u8>, out_data: *mut *mut u8, out_len: *mut usize) {
+    let data = data.into_boxed_slice();
+    out_len.write(data.len());
+    out_data.write(Box::into_raw(data).cast::<u8>());
+}
+
+/// Converts GLB (or GLTF with embedded buffers) into V3M file (V3C if the model is skinned).
+///
+/// On success `*out_data` points to the output file that must be released with `v3d_free_buffer`.
+///
+/// # Safety
+///
+/// `glb` must point to `glb_len` readable bytes. `out_data` and `out_len` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn v3d_convert_glb(
+    glb: *const u8,
+    glb_len: usize,
+    flags: u32,
+    out_data: *mut *mut u8,
+    out_len: *mut usize,
+) -> i32 {
+    let Some(glb) = input_slice(glb, glb_len) else {
+        return fail(ErrorCategory::Usage, "input buffer is null");
+    };
+    if out_data.is_null() || out_len.is_null() {
+        return fail(ErrorCategory::Usage, "output pointer is null");
+    }
+    match v3mc_convert::convert_glb_to_v3mc(glb, convert_options(flags)) {
+        Ok(data) => {
+            write_output(data, out_data, out_len);
+            V3D_OK
+        }
+        Err(e) => fail(e.category(), e),
+    }
+}
+
+/// Same as `v3d_convert_glb` but calls `progress` (if not NULL) from the calling thread every time conversion
+/// progress changes.
+///
+/// # Safety
+///
+/// Same requirements as `v3d_convert_glb`. `progress` must be safe to call with `user_data`.
+#[no_mangle]
+pub unsafe extern "C" fn v3d_convert_glb_with_progress(
+    glb: *const u8,
+    glb_len: usize,
+    flags: u32,
+    progress: V3dProgressCallback,
+    user_data: *mut c_void,
+    out_data: *mut *mut u8,
+    out_len: *mut usize,
+) -> i32 {
+    let Some(glb) = input_slice(glb, glb_len) else {
+        return fail(ErrorCategory::Usage, "input buffer is null");
+    };
+    if out_data.is_null() || out_len.is_null() {
+        return fail(ErrorCategory::Usage, "output pointer is null");
+    }
+    let user_data = UserData(user_data);
+    let listener = ProgressCallback::new(move |update| {
+        if let Some(progress) = progress {
+            let stage = CString::new(update.stage.replace('\0', " ")).unwrap_or_default();
+            progress(stage.as_ptr(), update.percentage(), user_data.get());
+        }
+    });
+    match v3mc_convert::convert_glb_to_v3mc_with_progress(glb, convert_options(flags), listener) {
+        Ok(data) => {
+            write_output(data, out_data, out_len);
+            V3D_OK
+        }
+        Err(e) => fail(e.category(), e),
+    }
+}
+
+/// Releases buffer returned by `v3d_convert_glb` or `v3d_convert_glb_with_progress`.
+///
+/// # Safety
+///
+/// `data` and `len` must come from a successful `v3d_convert_glb` call and the buffer cannot be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn v3d_free_buffer(data: *mut u8, len: usize) {
+    if !data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(data, len)));
+    }
+}
+
+/// Parses V3M/V3C file and fills `info` with its basic information.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes. `info` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn v3d_parse_v3m(data: *const u8, len: usize, info: *mut V3dMeshInfo) -> i32 {
+    let Some(data) = input_slice(data, len) else {
+        return fail(ErrorCategory::Usage, "input buffer is null");
+    };
+    if info.is_null() {
+        return fail(ErrorCategory::Usage, "output pointer is null");
+    }
+    match v3mc::File::read(&mut Cursor::new(data)) {
+        Ok(file) => {
+            info.write(get_mesh_info(&file));
+            V3D_OK
+        }
+        Err(e) => fail(ErrorCategory::Input, e),
+    }
+}
+
+/// Returns message of the last error that occurred in the calling thread or NULL if there was none. The string is
+/// valid until the next failed call in the same thread.
+#[no_mangle]
+pub extern "C" fn v3d_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |s| s.as_ptr()))
+}
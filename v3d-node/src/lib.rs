@@ -0,0 +1,93 @@
+//! Node.js bindings of the v3d library
+
+use napi::bindgen_prelude::Buffer;
+use napi::{Error, Result};
+use napi_derive::napi;
+use std::collections::HashMap;
+use std::io::Cursor;
+use v3d::limits::Limits;
+use v3d::report::NodeReport;
+use v3d::{v3mc, v3mc_convert, ConvertOptions};
+
+#[napi(object)]
+#[derive(Default)]
+pub struct ConvertGlbOptions {
+    /// Validate geometry against Dash Faction limits instead of stock game limits
+    pub dash_faction_limits: Option<bool>,
+    /// Override engine limits, e.g. "vertices=8000,textures=10"
+    pub limits: Option<String>,
+    /// Texture names mapping (source image name to RF texture name)
+    pub texture_map: Option<HashMap<String, String>>,
+}
+
+/// Summary of a single submesh of V3M/V3C file
+#[napi(object)]
+pub struct SubmeshInfo {
+    pub name: String,
+    pub lods: u32,
+    /// Vertex count of the most detailed LOD
+    pub vertices: u32,
+    /// Triangle count of the most detailed LOD
+    pub triangles: u32,
+    /// Batch (chunk) count of the most detailed LOD
+    pub batches: u32,
+    pub textures: Vec<String>,
+    pub radius: f64,
+}
+
+impl From<NodeReport> for SubmeshInfo {
+    fn from(report: NodeReport) -> Self {
+        let to_u32 = |count: usize| count.try_into().unwrap_or(u32::MAX);
+        Self {
+            name: report.name,
+            lods: to_u32(report.lods),
+            vertices: to_u32(report.vertices),
+            triangles: to_u32(report.triangles),
+            batches: to_u32(report.batches),
+            textures: report.textures,
+            radius: f64::from(report.radius),
+        }
+    }
+}
+
+fn to_napi_error<E: ToString>(err: E) -> Error {
+    Error::from_reason(err.to_string())
+}
+
+/// Converts GLB file (or GLTF with embedded buffers) into V3M file (V3C if the model is skinned)
+#[napi]
+pub fn convert_glb(data: Buffer, options: Option<ConvertGlbOptions>) -> Result<Buffer> {
+    let options = options.unwrap_or_default();
+    let mut limits = if options.dash_faction_limits.unwrap_or(false) {
+        Limits::DASH_FACTION
+    } else {
+        Limits::STOCK
+    };
+    if let Some(overrides) = &options.limits {
+        limits.apply_overrides(overrides).map_err(to_napi_error)?;
+    }
+    let convert_options = ConvertOptions {
+        limits,
+        texture_map: options
+            .texture_map
+            .unwrap_or_default()
+            .into_iter()
+            .collect(),
+        ..ConvertOptions::default()
+    };
+    let output =
+        v3mc_convert::convert_glb_to_v3mc(&data, convert_options).map_err(to_napi_error)?;
+    Ok(output.into())
+}
+
+/// Parses V3M/V3C file and returns a summary of every submesh
+#[napi]
+pub fn inspect_v3m(data: Buffer) -> Result<Vec<SubmeshInfo>> {
+    let file = v3mc::File::read(&mut Cursor::new(&*data)).map_err(to_napi_error)?;
+    Ok(file
+        .lod_meshes
+        .iter()
+        .map(NodeReport::from_lod_mesh)
+        .map(SubmeshInfo::from)
+        .collect())
+}
@@ -0,0 +1,33 @@
+//! WebAssembly bindings of the v3d library. All data is passed in memory so they can be used in a browser.
+
+use std::io::Cursor;
+use v3d::limits::Limits;
+use v3d::report::NodeReport;
+use v3d::{v3mc, v3mc_convert, ConvertOptions};
+use wasm_bindgen::prelude::*;
+
+/// Converts GLB file (or GLTF with embedded buffers) into V3M file (V3C if the model is skinned)
+#[wasm_bindgen(js_name = convertGlb)]
+pub fn convert_glb(data: &[u8], dash_faction_limits: bool) -> Result<Vec<u8>, JsError> {
+    let options = ConvertOptions {
+        limits: if dash_faction_limits {
+            Limits::DASH_FACTION
+        } else {
+            Limits::STOCK
+        },
+        ..ConvertOptions::default()
+    };
+    Ok(v3mc_convert::convert_glb_to_v3mc(data, options)?)
+}
+
+/// Parses V3M/V3C file and returns an array with a summary of every submesh (the same as in vmesh JSON report)
+#[wasm_bindgen(js_name = inspectV3m)]
+pub fn inspect_v3m(data: &[u8]) -> Result<JsValue, JsError> {
+    let file = v3mc::File::read(&mut Cursor::new(data))?;
+    let nodes: Vec<_> = file
+        .lod_meshes
+        .iter()
+        .map(NodeReport::from_lod_mesh)
+        .collect();
+    serde_wasm_bindgen::to_value(&nodes).map_err(|e| JsError::new(&e.to_string()))
+}
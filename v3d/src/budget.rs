@@ -0,0 +1,109 @@
+//! Pre-flight analysis of a GLTF document against engine limits.
+//!
+//! The analysis only reads accessor metadata so it is cheap enough to be run before conversion starts. It gives an
+//! overview of all submeshes at once instead of failing on the first problem.
+
+use crate::diagnostics::{describe_node, describe_primitive};
+use crate::limits::Limits;
+use crate::{
+    get_mesh_materials, get_primitive_vertex_count, get_rendered_primitives, get_submesh_nodes,
+};
+
+// Sizes of V3M data stored for every vertex and triangle (see v3mc::MeshChunkData). Per-file and per-chunk headers
+// are ignored because they are small compared to the geometry.
+const VERTEX_SIZE: usize = 12 + 12 + 8;
+const SKINNED_VERTEX_EXTRA_SIZE: usize = 8;
+const TRIANGLE_SIZE: usize = 8 + 16;
+
+/// Geometry of a single primitive
+#[derive(Clone, Debug)]
+pub struct PrimitiveBudget {
+    pub location: String,
+    pub vertices: usize,
+    pub indices: usize,
+}
+
+/// Usage of engine limits by a single mesh node (a submesh or one of its LOD meshes)
+#[derive(Clone, Debug)]
+pub struct MeshBudget {
+    pub name: String,
+    pub textures: usize,
+    pub primitives: Vec<PrimitiveBudget>,
+    /// Estimated size of the mesh data in the output file in bytes
+    pub estimated_size: usize,
+}
+
+impl MeshBudget {
+    pub fn passed(&self, limits: &Limits) -> bool {
+        self.textures <= limits.max_textures
+            && self
+                .primitives
+                .iter()
+                .all(|p| p.vertices <= limits.max_vertices && p.indices <= limits.max_indices)
+    }
+}
+
+/// Result of the pre-flight analysis
+#[derive(Clone, Debug)]
+pub struct Budget {
+    pub limits: Limits,
+    pub meshes: Vec<MeshBudget>,
+    /// Number of bones in the skeleton (0 if the model is not skinned)
+    pub bones: usize,
+}
+
+impl Budget {
+    pub fn passed(&self) -> bool {
+        self.bones <= self.limits.max_bones && self.meshes.iter().all(|m| m.passed(&self.limits))
+    }
+
+    /// Estimated size of the output file in bytes
+    pub fn estimated_size(&self) -> usize {
+        self.meshes.iter().map(|m| m.estimated_size).sum()
+    }
+}
+
+fn analyze_mesh(node: &gltf::Node, is_skinned: bool) -> MeshBudget {
+    let mesh = node.mesh().unwrap();
+    let vertex_size = if is_skinned {
+        VERTEX_SIZE + SKINNED_VERTEX_EXTRA_SIZE
+    } else {
+        VERTEX_SIZE
+    };
+    let primitives: Vec<_> = get_rendered_primitives(&mesh)
+        .into_iter()
+        .map(|(i, prim)| PrimitiveBudget {
+            location: describe_primitive(node, i, &prim),
+            vertices: get_primitive_vertex_count(&prim),
+            indices: prim.indices().map_or(0, |a| a.count()),
+        })
+        .collect();
+    let estimated_size = primitives
+        .iter()
+        .map(|p| p.vertices * vertex_size + p.indices / 3 * TRIANGLE_SIZE)
+        .sum();
+    MeshBudget {
+        name: describe_node(node),
+        textures: get_mesh_materials(&mesh).len(),
+        primitives,
+        estimated_size,
+    }
+}
+
+/// Analyzes all submeshes (including LOD meshes) and the skeleton of the document against given limits
+pub fn analyze_budget(doc: &gltf::Document, limits: &Limits) -> Budget {
+    let skin = doc.skins().next();
+    let is_skinned = skin.is_some();
+    let mut meshes = Vec::new();
+    for node in get_submesh_nodes(doc) {
+        meshes.push(analyze_mesh(&node, is_skinned));
+        for child in node.children().filter(|n| n.mesh().is_some()) {
+            meshes.push(analyze_mesh(&child, is_skinned));
+        }
+    }
+    Budget {
+        limits: *limits,
+        meshes,
+        bones: skin.map_or(0, |s| s.joints().count()),
+    }
+}
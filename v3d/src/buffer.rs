@@ -0,0 +1,94 @@
+//! Loading of GLTF buffers. Large external buffer files are memory-mapped instead of being read into memory so
+//! converting multi-hundred-MB models does not keep two copies of the geometry around.
+
+use crate::ConvertResult;
+use memmap2::Mmap;
+use std::fs::File;
+use std::ops::Deref;
+use std::path::Path;
+
+/// External buffer files smaller than this are simply read into memory
+const MMAP_MIN_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Data of a single GLTF buffer
+pub enum BufferData {
+    /// Buffer embedded in GLB/data URI or a small external file
+    Owned(gltf::buffer::Data),
+    /// Memory-mapped external buffer file
+    Mapped(Mmap),
+}
+
+impl Deref for BufferData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            Self::Owned(data) => data,
+            Self::Mapped(mmap) => mmap,
+        }
+    }
+}
+
+impl From<gltf::buffer::Data> for BufferData {
+    fn from(data: gltf::buffer::Data) -> Self {
+        Self::Owned(data)
+    }
+}
+
+/// Returns path of an external buffer file if the URI is a plain relative path. URIs using a scheme or percent
+/// encoding are left for the gltf crate to handle.
+fn get_external_file_path(uri: &str, base_dir: &Path) -> Option<std::path::PathBuf> {
+    if uri.contains(':') || uri.contains('%') {
+        None
+    } else {
+        Some(base_dir.join(uri))
+    }
+}
+
+fn map_external_file(buffer: &gltf::Buffer, path: &Path) -> ConvertResult<Option<BufferData>> {
+    let file = File::open(path)?;
+    let file_len = file.metadata()?.len();
+    if file_len < MMAP_MIN_SIZE {
+        return Ok(None);
+    }
+    if file_len < buffer.length() as u64 {
+        return Err(gltf::Error::BufferLength {
+            buffer: buffer.index(),
+            expected: buffer.length(),
+            actual: file_len as usize,
+        }
+        .into());
+    }
+    debug!(
+        "Memory-mapping buffer #{}: {} ({} bytes)",
+        buffer.index(),
+        path.display(),
+        file_len
+    );
+    // Safety: the file is only read and it is expected not to be modified by other processes during conversion
+    let mmap = unsafe { Mmap::map(&file)? };
+    Ok(Some(BufferData::Mapped(mmap)))
+}
+
+/// Loads all buffers of a GLTF document. External files are loaded relative to `base_dir` (not supported if it is
+/// `None`).
+pub fn import_buffers(
+    document: &gltf::Document,
+    base_dir: Option<&Path>,
+    mut blob: Option<Vec<u8>>,
+) -> ConvertResult<Vec<BufferData>> {
+    let mut buffers = Vec::with_capacity(document.buffers().len());
+    for buffer in document.buffers() {
+        if let (gltf::buffer::Source::Uri(uri), Some(base_dir)) = (buffer.source(), base_dir) {
+            if let Some(path) = get_external_file_path(uri, base_dir) {
+                if let Some(data) = map_external_file(&buffer, &path)? {
+                    buffers.push(data);
+                    continue;
+                }
+            }
+        }
+        let data = gltf::buffer::Data::from_source_and_blob(buffer.source(), base_dir, &mut blob)?;
+        buffers.push(data.into());
+    }
+    Ok(buffers)
+}
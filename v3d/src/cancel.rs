@@ -0,0 +1,26 @@
+//! Cancellation of running conversions
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Token used to abort a conversion from another thread (e.g. when user presses a Cancel button).
+///
+/// Converters check the token between submeshes and primitives and fail with
+/// [`crate::errors::ConvertError::Cancelled`] once it is cancelled. Clones share the same state.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Requests cancellation of all conversions using this token
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
@@ -1,14 +1,17 @@
-use crate::io_utils::new_custom_error;
+use crate::diagnostics::describe_node;
+use crate::errors::{ConvertError, Location};
+use crate::names;
 use crate::v3mc_convert::get_node_extras;
-use crate::{gltf_to_rf_quat, gltf_to_rf_vec, rfa, v3mc, Context};
+use crate::warnings::WarningKind;
+use crate::{
+    get_node_local_transform, gltf_to_rf_quat, gltf_to_rf_vec, rfa, v3mc, Context, ConvertResult,
+};
 use gltf::animation::util::{ReadInputs, ReadOutputs};
 use gltf::animation::Interpolation;
 use serde_derive::Deserialize;
 use serde_json::Value;
 use std::collections::BTreeMap;
 use std::f32;
-use std::fs::File;
-use std::io::BufWriter;
 use std::vec::Vec;
 
 #[derive(Deserialize, Debug, Default)]
@@ -35,11 +38,57 @@ impl JointExtras {
     }
 }
 
-fn gltf_time_to_rfa_time(time_sec: f32) -> i32 {
-    (time_sec * 30.0_f32 * 160.0_f32) as i32
+/// Inverse bind matrices deviating from orthonormal matrices by more than this are orthonormalized
+const BIND_MATRIX_TOLERANCE: f32 = 0.0001_f32;
+/// Inverse bind matrices deviating from orthonormal matrices by more than this are scaled on purpose and cannot be
+/// fixed without changing the mesh
+const MAX_BIND_MATRIX_DEVIATION: f32 = 0.01_f32;
+
+/// Part of the bone length used as radius of generated collision spheres (0.5 makes the sphere cover the whole bone)
+const AUTO_CSPHERE_RADIUS_FACTOR: f32 = 0.5_f32;
+/// Generated collision spheres smaller than this are skipped (e.g. spheres of helper bones)
+const MIN_AUTO_CSPHERE_RADIUS: f32 = 0.01_f32;
+
+/// RF-specific extras (custom properties) of animations
+#[derive(Deserialize, Debug, Default)]
+struct AnimExtras {
+    #[serde(rename = "RF_loop")]
+    looping: Option<bool>,
+    #[serde(rename = "RF_ramp_in_time")]
+    ramp_in_time: Option<f32>,
+    #[serde(rename = "RF_ramp_out_time")]
+    ramp_out_time: Option<f32>,
+}
+
+fn get_anim_extras(anim: &gltf::Animation) -> AnimExtras {
+    anim.extras()
+        .as_ref()
+        .and_then(|raw| serde_json::from_str::<AnimExtras>(raw.get()).ok())
+        .unwrap_or_default()
+}
+
+/// Suffix of names of looping animations (e.g. `walk_loop`)
+const LOOP_ANIM_NAME_SUFFIX: &str = "_loop";
+
+/// Returns `Some(true)` for looping animations and `Some(false)` for one-shot animations based on `RF_loop` extras
+/// property or `_loop` name suffix. RFA files have no looping flag - the game loops state animations and plays
+/// actions once - so this only determines how the animation should be listed in game tables. Returns `None` if not
+/// specified.
+pub fn get_anim_looping(anim: &gltf::Animation) -> Option<bool> {
+    get_anim_extras(anim).looping.or_else(|| {
+        anim.name()
+            .unwrap_or_default()
+            .to_lowercase()
+            .ends_with(LOOP_ANIM_NAME_SUFFIX)
+            .then_some(true)
+    })
 }
 
-fn make_short_quat(quat: [f32; 4]) -> [i16; 4] {
+pub(crate) fn gltf_time_to_rfa_time(time_sec: f32) -> i32 {
+    (time_sec * rfa::RFA_TIME_UNITS_PER_SEC) as i32
+}
+
+pub(crate) fn make_short_quat(quat: [f32; 4]) -> [i16; 4] {
     quat.map(|x| (x * 16383.0_f32) as i16)
 }
 
@@ -164,12 +213,14 @@ fn check_for_scale_channels(n: &gltf::Node, anim: &gltf::Animation, ctx: &Contex
         let reader = channel.reader(|buffer| ctx.get_buffer_data(buffer));
         if let Some(ReadOutputs::Scales(scales)) = reader.read_outputs() {
             if scales.flatten().any(|s| (s - 1.0_f32).abs() > 0.01_f32) {
-                eprintln!(
-                    "Warning! Animation #{} '{}' is using unsupported scale channel on node #{} '{}'!",
-                    anim.index(),
-                    anim.name().unwrap_or_default(),
-                    n.index(),
-                    n.name().unwrap_or_default(),
+                ctx.warn(
+                    WarningKind::AnimChannel,
+                    &format!(
+                        "Animation #{} '{}' is using unsupported scale channel on {}!",
+                        anim.index(),
+                        anim.name().unwrap_or_default(),
+                        describe_node(n),
+                    ),
                 );
             }
         }
@@ -206,14 +257,19 @@ fn determine_anim_weight(
     }
 }
 
-fn convert_bone_anim(node: &gltf::Node, anim: &gltf::Animation, ctx: &Context) -> rfa::Bone {
-    let rotation_keys = convert_rotation_keys(node, anim, ctx);
-    let translation_keys = convert_translation_keys(node, anim, ctx);
-    check_for_scale_channels(node, anim, ctx);
+/// Creates animation of a bone from converted keys. Weight is taken from joint extras or options or determined from
+/// the keys.
+pub(crate) fn make_bone_anim(
+    node: &gltf::Node,
+    anim: &gltf::Animation,
+    rotation_keys: Vec<rfa::RotationKey>,
+    translation_keys: Vec<rfa::TranslationKey>,
+    ctx: &Context,
+) -> rfa::Bone {
     let extras = get_node_extras::<JointExtras>(node);
     let weight = extras
         .get_anim_weight(anim.name().unwrap_or_default())
-        .or(ctx.args.anim_weight)
+        .or(ctx.options.anim_weight)
         .unwrap_or_else(|| determine_anim_weight(&rotation_keys, &translation_keys));
     rfa::Bone {
         weight,
@@ -222,6 +278,13 @@ fn convert_bone_anim(node: &gltf::Node, anim: &gltf::Animation, ctx: &Context) -
     }
 }
 
+fn convert_bone_anim(node: &gltf::Node, anim: &gltf::Animation, ctx: &Context) -> rfa::Bone {
+    let rotation_keys = convert_rotation_keys(node, anim, ctx);
+    let translation_keys = convert_translation_keys(node, anim, ctx);
+    check_for_scale_channels(node, anim, ctx);
+    make_bone_anim(node, anim, rotation_keys, translation_keys, ctx)
+}
+
 fn get_default_ramp_in_time(anim: &gltf::Animation) -> i32 {
     if is_death_anim(anim) {
         800
@@ -245,9 +308,10 @@ fn determine_ramp_in_time(
     ctx: &Context,
 ) -> i32 {
     let anim_name = anim.name().unwrap_or_default();
-    root_joint_extras
-        .get_ramp_in_time(anim_name)
-        .or(ctx.args.ramp_in_time)
+    get_anim_extras(anim)
+        .ramp_in_time
+        .or_else(|| root_joint_extras.get_ramp_in_time(anim_name))
+        .or(ctx.options.ramp_in_time)
         .map(gltf_time_to_rfa_time)
         .unwrap_or_else(|| get_default_ramp_in_time(anim).min(duration / 2))
 }
@@ -259,9 +323,10 @@ fn determine_ramp_out_time(
     ctx: &Context,
 ) -> i32 {
     let anim_name = anim.name().unwrap_or_default();
-    root_joint_extras
-        .get_ramp_out_time(anim_name)
-        .or(ctx.args.ramp_out_time)
+    get_anim_extras(anim)
+        .ramp_out_time
+        .or_else(|| root_joint_extras.get_ramp_out_time(anim_name))
+        .or(ctx.options.ramp_out_time)
         .map(gltf_time_to_rfa_time)
         .unwrap_or_else(|| get_default_ramp_out_time(anim).min(duration / 2))
 }
@@ -288,17 +353,26 @@ fn make_rfa(anim: &gltf::Animation, skin: &gltf::Skin, ctx: &Context) -> rfa::Fi
     for joint in skin.joints() {
         bones.push(convert_bone_anim(&joint, anim, ctx));
     }
+    make_rfa_from_bones(anim, skin, bones, ctx)
+}
+
+/// Creates RFA file from animations of all bones of the skin. Ramp times are determined from `anim` and root joint
+/// of the skin
+pub(crate) fn make_rfa_from_bones(
+    anim: &gltf::Animation,
+    skin: &gltf::Skin,
+    bones: Vec<rfa::Bone>,
+    ctx: &Context,
+) -> rfa::File {
     let (start_time, end_time) = determine_anim_time_range(&bones);
     let duration = end_time - start_time;
     let root_joint_extras = get_root_joint_extras(skin);
     let ramp_in_time = determine_ramp_in_time(anim, &root_joint_extras, duration, ctx);
     let ramp_out_time = determine_ramp_out_time(anim, &root_joint_extras, duration, ctx);
-    if ctx.args.verbose >= 2 {
-        println!("Start time: {}", start_time);
-        println!("End time: {}", end_time);
-        println!("Ramp In time: {}", ramp_in_time);
-        println!("Ramp Out time: {}", ramp_out_time);
-    }
+    debug!("Start time: {}", start_time);
+    debug!("End time: {}", end_time);
+    debug!("Ramp In time: {}", ramp_in_time);
+    debug!("Ramp Out time: {}", ramp_out_time);
     let header = rfa::FileHeader {
         num_bones: bones.len() as i32,
         start_time,
@@ -312,23 +386,21 @@ fn make_rfa(anim: &gltf::Animation, skin: &gltf::Skin, ctx: &Context) -> rfa::Fi
     rfa::File { header, bones }
 }
 
-pub(crate) fn convert_animation_to_rfa(
+/// Converts animation into RFA file. Returns animation name (used as output file stem) and the converted file
+pub fn convert_animation_to_rfa(
     anim: &gltf::Animation,
     index: usize,
     skin: &gltf::Skin,
     ctx: &Context,
-) -> std::io::Result<()> {
+) -> ConvertResult<(String, rfa::File)> {
     let name = anim
         .name()
         .map_or_else(|| format!("anim_{}", index), str::to_owned);
-    let file_name = ctx.output_dir.join(format!("{}.rfa", name));
-    if ctx.args.verbose >= 1 {
-        println!("Exporting animation: {} -> {}", name, file_name.display());
-    }
-    let mut wrt = BufWriter::new(File::create(&file_name)?);
+    ctx.check_cancelled()?;
+    info!("Converting animation: {}", name);
     let rfa = make_rfa(anim, skin, ctx);
-    rfa.write(&mut wrt)?;
-    Ok(())
+    ctx.check_errors()?;
+    Ok((name, rfa))
 }
 
 fn get_joint_index(node: &gltf::Node, skin: &gltf::Skin) -> usize {
@@ -345,68 +417,146 @@ fn get_joint_parent<'a>(node: &gltf::Node, skin: &gltf::Skin<'a>) -> Option<gltf
         .find(|n| n.children().any(|c| c.index() == node.index()))
 }
 
+/// Returns how much the matrix deviates from an orthonormal matrix: the biggest difference of axis length from 1 or
+/// cosine of angle between axes from 0
+fn get_orthonormal_deviation(m: &glam::Mat3) -> f32 {
+    let axes = [m.x_axis, m.y_axis, m.z_axis];
+    let length_deviation = axes
+        .iter()
+        .map(|axis| (axis.length() - 1.0_f32).abs())
+        .fold(0.0_f32, f32::max);
+    let skew = [(0, 1), (0, 2), (1, 2)]
+        .iter()
+        .map(|&(i, j)| {
+            axes[i]
+                .normalize_or_zero()
+                .dot(axes[j].normalize_or_zero())
+                .abs()
+        })
+        .fold(0.0_f32, f32::max);
+    length_deviation.max(skew)
+}
+
+/// Orthonormalizes the matrix using Gram-Schmidt process (X axis direction is kept)
+fn orthonormalize(m: &glam::Mat3) -> glam::Mat3 {
+    let x = m.x_axis.normalize();
+    let y = (m.y_axis - x * x.dot(m.y_axis)).normalize();
+    glam::Mat3::from_cols(x, y, x.cross(y))
+}
+
+/// Validates the inverse bind matrix of a joint and returns it with slightly scaled or skewed rotation part
+/// orthonormalized
+fn fix_inverse_bind_matrix(
+    n: &gltf::Node,
+    matrix: glam::Mat4,
+    ctx: &Context,
+) -> ConvertResult<glam::Mat4> {
+    let rotation_part = glam::Mat3::from_mat4(matrix);
+    if rotation_part.determinant() <= 0.0_f32 {
+        return Err(ConvertError::InvalidInput(format!(
+            "{}: inverse bind matrix is mirrored or degenerate",
+            describe_node(n)
+        )));
+    }
+    let deviation = get_orthonormal_deviation(&rotation_part);
+    debug!(
+        "{}: inverse bind matrix deviation {}",
+        describe_node(n),
+        deviation
+    );
+    if deviation >= MAX_BIND_MATRIX_DEVIATION {
+        return Err(ConvertError::ScaledBone(Location::node(n)));
+    }
+    if deviation <= BIND_MATRIX_TOLERANCE {
+        return Ok(matrix);
+    }
+    ctx.warn(
+        WarningKind::BindMatrix,
+        &format!(
+            "{}: inverse bind matrix is not orthonormal (deviation {:.5}) - orthonormalized",
+            describe_node(n),
+            deviation
+        ),
+    );
+    let fixed = orthonormalize(&rotation_part);
+    Ok(glam::Mat4::from_cols(
+        fixed.x_axis.extend(0.0_f32),
+        fixed.y_axis.extend(0.0_f32),
+        fixed.z_axis.extend(0.0_f32),
+        matrix.w_axis,
+    ))
+}
+
+/// Converts joint into a bone. RF stores inverted bind pose of every bone (transformation from model space to bone
+/// space) which is what glTF inverse bind matrix describes, so only the coordinate system is changed.
 fn convert_bone(
     n: &gltf::Node,
+    name: String,
     inverse_bind_matrix: &[[f32; 4]; 4],
-    index: usize,
     skin: &gltf::Skin,
-) -> v3mc::Bone {
-    let name = n
-        .name()
-        .map_or_else(|| format!("bone_{}", index), str::to_owned);
+    ctx: &Context,
+) -> ConvertResult<v3mc::Bone> {
     let parent_node_opt = get_joint_parent(n, skin);
     let parent_index = parent_node_opt.map_or(-1, |pn| get_joint_index(&pn, skin) as i32);
-    let inv_transform = glam::Mat4::from_cols_array_2d(inverse_bind_matrix);
-    let (gltf_scale, gltf_rotation, gltf_translation) =
+    let inv_transform =
+        fix_inverse_bind_matrix(n, glam::Mat4::from_cols_array_2d(inverse_bind_matrix), ctx)?;
+    let (_gltf_scale, gltf_rotation, gltf_translation) =
         inv_transform.to_scale_rotation_translation();
-    assert!(
-        (gltf_scale - glam::Vec3::ONE).max_element() < 0.01_f32,
-        "scale is not supported: {}",
-        gltf_scale
-    );
     let base_rotation = gltf_to_rf_quat(gltf_rotation.into());
     let base_translation = gltf_to_rf_vec(gltf_translation.into());
-    v3mc::Bone {
+    Ok(v3mc::Bone {
         name,
         base_rotation,
         base_translation,
         parent_index,
-    }
+    })
 }
 
-pub(crate) fn convert_bones(skin: &gltf::Skin, ctx: &Context) -> std::io::Result<Vec<v3mc::Bone>> {
+pub(crate) fn check_bones_limit(skin: &gltf::Skin, ctx: &Context) {
     let num_joints = skin.joints().count();
-    if ctx.args.verbose >= 2 {
-        println!("Bones (joints): {}/{}", num_joints, v3mc::MAX_BONES);
-    }
-    if num_joints > v3mc::MAX_BONES {
-        let err_msg = format!(
-            "too many bones: found {} but only {} are supported",
+    debug!(
+        "Bones (joints): {}/{}",
+        num_joints, ctx.options.limits.max_bones
+    );
+    if num_joints > ctx.options.limits.max_bones {
+        ctx.report_error(format!(
+            "skin '{}': too many bones: found {} but only {} are supported",
+            skin.name().unwrap_or("<unnamed>"),
             num_joints,
-            v3mc::MAX_BONES
-        );
-        return Err(new_custom_error(err_msg));
+            ctx.options.limits.max_bones
+        ));
     }
+}
 
+pub(crate) fn convert_bones(skin: &gltf::Skin, ctx: &Context) -> ConvertResult<Vec<v3mc::Bone>> {
+    let skin_error = |message: String| ConvertError::InvalidSkin {
+        skin: skin.name().unwrap_or("<unnamed>").to_owned(),
+        message,
+    };
+    let num_joints = skin.joints().count();
+    // glTF specification says missing inverse bind matrices are identity matrices
     let inverse_bind_matrices: Vec<_> = skin
         .reader(|buffer| ctx.get_buffer_data(buffer))
         .read_inverse_bind_matrices()
-        .expect("expected inverse bind matrices")
-        .collect();
+        .map_or_else(
+            || vec![glam::Mat4::IDENTITY.to_cols_array_2d(); num_joints],
+            Iterator::collect,
+        );
 
     if inverse_bind_matrices.len() != num_joints {
-        let err_msg = format!(
+        return Err(skin_error(format!(
             "invalid number of inverse bind matrices: expected {}, got {}",
             num_joints,
             inverse_bind_matrices.len()
-        );
-        return Err(new_custom_error(err_msg));
+        )));
     }
 
+    let bone_names = names::assign_bone_names(skin, ctx);
     let mut bones = Vec::with_capacity(num_joints);
-    for (i, n) in skin.joints().enumerate() {
-        let bone = convert_bone(&n, &inverse_bind_matrices[i], i, skin);
-        bones.push(bone);
+    for ((n, name), inverse_bind_matrix) in
+        skin.joints().zip(bone_names).zip(&inverse_bind_matrices)
+    {
+        bones.push(convert_bone(&n, name, inverse_bind_matrix, skin, ctx)?);
     }
     Ok(bones)
 }
@@ -425,3 +575,54 @@ pub(crate) fn get_nodes_parented_to_bones<'a>(
         })
         .filter(move |(node, _)| !is_joint(node, skin))
 }
+
+/// Generates collision spheres from the skeleton. Every bone with child joints gets a sphere centered between the bone
+/// origin and its children covering the bone length. Leaf bones (e.g. head or hands) get a sphere of the parent bone
+/// size centered at the bone origin. `bones` are the converted bones of the skin (used for names).
+pub(crate) fn generate_cspheres(skin: &gltf::Skin, bones: &[v3mc::Bone]) -> Vec<v3mc::ColSphere> {
+    let joints: Vec<_> = skin.joints().collect();
+    let mut spheres: Vec<Option<(glam::Vec3, f32)>> = joints
+        .iter()
+        .map(|joint| {
+            let child_offsets: Vec<_> = joint
+                .children()
+                .filter(|child| is_joint(child, skin))
+                .map(|child| get_node_local_transform(&child).w_axis.truncate())
+                .collect();
+            if child_offsets.is_empty() {
+                return None;
+            }
+            let length = child_offsets
+                .iter()
+                .map(|offset| offset.length())
+                .fold(0.0_f32, f32::max);
+            let center = child_offsets.iter().sum::<glam::Vec3>() / child_offsets.len() as f32;
+            Some((center, length * AUTO_CSPHERE_RADIUS_FACTOR))
+        })
+        .collect();
+    for (i, joint) in joints.iter().enumerate() {
+        if spheres[i].is_none() {
+            let parent_radius = get_joint_parent(joint, skin)
+                .and_then(|parent| spheres[get_joint_index(&parent, skin)])
+                .map(|(_center, radius)| radius);
+            spheres[i] = parent_radius.map(|radius| (glam::Vec3::ZERO, radius));
+        }
+    }
+    spheres
+        .into_iter()
+        .zip(bones)
+        .enumerate()
+        .filter_map(|(i, (sphere, bone))| {
+            let (center, radius) =
+                sphere.filter(|(_, radius)| *radius >= MIN_AUTO_CSPHERE_RADIUS)?;
+            let name = format!("csphere_{}", bone.name);
+            debug!("Generated csphere '{}' with radius {}", name, radius);
+            Some(v3mc::ColSphere {
+                name: names::truncate_name(&name, names::MAX_NAME_LEN).to_owned(),
+                parent_index: i as i32,
+                pos: gltf_to_rf_vec(center.into()),
+                radius,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,332 @@
+//! Conversion context and GLTF helpers shared by all converters
+
+use crate::buffer::{self, BufferData};
+use crate::cancel::CancellationToken;
+use crate::diagnostics::{DiagnosticsSink, LogDiagnostics};
+use crate::errors::ConvertError;
+use crate::math_utils::{Matrix3, Matrix4, Vector3};
+use crate::report::NodeReport;
+use crate::warnings::{self, WarningKind};
+use crate::{ConvertOptions, ConvertResult, ProgressListener};
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Loads buffers of already parsed GLTF file. External files are loaded relative to `base_dir` (large files are
+/// memory-mapped)
+pub fn import_gltf(
+    gltf: gltf::Gltf,
+    base_dir: &Path,
+) -> ConvertResult<(gltf::Document, Vec<BufferData>)> {
+    let gltf::Gltf { document, blob } = gltf;
+    debug!("Importing GLTF buffers");
+    let buffers = buffer::import_buffers(&document, Some(base_dir), blob)?;
+    Ok((document, buffers))
+}
+
+/// Parses GLB file (or GLTF with embedded buffers) from memory. References to external files are not supported so
+/// filesystem is never accessed (e.g. when running in a browser)
+pub fn import_gltf_slice(data: &[u8]) -> ConvertResult<(gltf::Document, Vec<BufferData>)> {
+    let gltf::Gltf { document, blob } = gltf::Gltf::from_slice(data)?;
+    debug!("Importing GLTF buffers");
+    let buffers = buffer::import_buffers(&document, None, blob)?;
+    Ok((document, buffers))
+}
+
+// glTF defines -X as right, RF defines +X as right
+// Both glTF and RF defines +Y as up, +Z as forward
+
+pub(crate) fn gltf_to_rf_vec(vec: [f32; 3]) -> [f32; 3] {
+    // in GLTF negative X is right, in RF positive X is right
+    [-vec[0], vec[1], vec[2]]
+}
+
+pub(crate) fn gltf_to_rf_quat(quat: [f32; 4]) -> [f32; 4] {
+    // convert to RF coordinate system
+    // it seems RF expects inverted quaternions...
+    [-quat[0], quat[1], quat[2], quat[3]]
+}
+
+pub(crate) fn gltf_to_rf_face<T: Copy>(vindices: [T; 3]) -> [T; 3] {
+    // because we convert from right-handed to left-handed order of vertices must be flipped to
+    // fix backface culling
+    [vindices[0], vindices[2], vindices[1]]
+}
+
+fn build_child_nodes_indices(doc: &gltf::Document) -> Vec<usize> {
+    let mut child_indices: Vec<usize> = doc
+        .nodes()
+        .flat_map(|n| n.children().map(|n| n.index()))
+        .collect();
+    child_indices.dedup();
+    child_indices
+}
+
+/// Returns top-level mesh nodes. Each of them is converted into a separate submesh
+pub fn get_submesh_nodes(doc: &gltf::Document) -> Vec<gltf::Node<'_>> {
+    let child_indices = build_child_nodes_indices(doc);
+    doc.nodes()
+        .filter(|n| n.mesh().is_some() && !child_indices.contains(&n.index()))
+        .collect()
+}
+
+/// Prefix of names of materials used by collision-only (invisible) geometry
+const COLLISION_MATERIAL_PREFIX: &str = "col_";
+
+/// Checks if primitive is collision-only geometry that is not rendered. Such primitives use a material with `col_`
+/// name prefix or with `RF_collision_only` extras property set to `true`.
+pub fn is_collision_only(prim: &gltf::Primitive) -> bool {
+    let material = prim.material();
+    let has_prefix = material.name().is_some_and(|name| {
+        name.get(..COLLISION_MATERIAL_PREFIX.len())
+            .is_some_and(|p| p.eq_ignore_ascii_case(COLLISION_MATERIAL_PREFIX))
+    });
+    has_prefix
+        || material
+            .extras()
+            .as_ref()
+            .and_then(|raw| serde_json::from_str::<serde_json::Value>(raw.get()).ok())
+            .and_then(|extras| extras.get("RF_collision_only")?.as_bool())
+            .unwrap_or(false)
+}
+
+/// Returns primitives of the mesh that are rendered (see [`is_collision_only`]) together with their indices
+pub fn get_rendered_primitives<'a>(mesh: &gltf::Mesh<'a>) -> Vec<(usize, gltf::Primitive<'a>)> {
+    mesh.primitives()
+        .enumerate()
+        .filter(|(_, prim)| !is_collision_only(prim))
+        .collect()
+}
+
+/// Returns materials of rendered primitives of the mesh
+pub fn get_mesh_materials<'a>(mesh: &gltf::Mesh<'a>) -> Vec<gltf::Material<'a>> {
+    let mut materials = get_rendered_primitives(mesh)
+        .into_iter()
+        .map(|(_, prim)| prim.material())
+        .collect::<Vec<_>>();
+    materials.dedup_by_key(|m| m.index());
+    materials
+}
+
+pub fn get_primitive_vertex_count(prim: &gltf::Primitive) -> usize {
+    prim.attributes()
+        .find(|p| p.0 == gltf::mesh::Semantic::Positions)
+        .map_or(0, |a| a.1.count())
+}
+
+pub(crate) fn extract_translation_from_matrix(transform: &Matrix4) -> (Vector3, Matrix3) {
+    let mut translation = [0_f32; 3];
+    translation.copy_from_slice(&transform[3][0..3]);
+    let mut rot_scale_mat = [[0_f32; 3]; 3];
+    rot_scale_mat[0].copy_from_slice(&transform[0][0..3]);
+    rot_scale_mat[1].copy_from_slice(&transform[1][0..3]);
+    rot_scale_mat[2].copy_from_slice(&transform[2][0..3]);
+    (translation, rot_scale_mat)
+}
+
+pub(crate) fn get_node_local_transform(node: &gltf::Node) -> glam::Mat4 {
+    glam::Mat4::from_cols_array_2d(&node.transform().matrix())
+}
+
+/// Solid color texture generated for a material without base color texture. The converter only references it - the
+/// caller is responsible for writing the image file
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GeneratedTexture {
+    /// Texture file name (TGA)
+    pub name: String,
+    /// sRGB color with alpha
+    pub color: [u8; 4],
+}
+
+/// Source image of a texture used for baking
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TextureImage {
+    /// URI relative to the GLTF file
+    Uri(String),
+    /// Encoded image (e.g. PNG or JPEG) embedded in a GLTF buffer
+    Data(Vec<u8>),
+}
+
+/// Base color texture with ambient occlusion baked in (see [`ConvertOptions::bake_occlusion`]). The converter only
+/// references it - the caller is responsible for creating the image file
+#[derive(Clone, Debug, PartialEq)]
+pub struct BakedTexture {
+    /// Texture file name (TGA)
+    pub name: String,
+    /// Base color image (sRGB)
+    pub base_color: TextureImage,
+    /// Occlusion image - occlusion is stored in the red channel (linear)
+    pub occlusion: TextureImage,
+    /// Occlusion strength from the material (0 - no occlusion, 1 - full occlusion)
+    pub strength: f32,
+}
+
+pub(crate) struct NoProgress;
+
+impl ProgressListener for NoProgress {}
+
+/// Buffer data of imported GLTF file. Can be passed to parallel tasks without the rest of [`Context`]
+#[derive(Clone, Copy)]
+pub(crate) struct Buffers<'a>(&'a [BufferData]);
+
+impl<'a> Buffers<'a> {
+    pub(crate) fn get(self, buffer: gltf::Buffer) -> Option<&'a [u8]> {
+        Some(&*self.0[buffer.index()])
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    // state is always left consistent so it can be used even if another thread panicked
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Conversion state shared by all converters.
+///
+/// Context is `Send + Sync` so conversions can run on worker threads. Every conversion needs its own context
+/// because it owns the imported buffers and collects errors and reports of a single input file, but the same
+/// [`ConvertOptions`] can be cloned into any number of contexts.
+pub struct Context {
+    buffers: Vec<BufferData>,
+    pub(crate) is_character: bool,
+    pub(crate) options: ConvertOptions,
+    errors: Mutex<Vec<String>>,
+    pub(crate) progress: Box<dyn ProgressListener>,
+    diagnostics: Box<dyn DiagnosticsSink>,
+    cancellation: Option<CancellationToken>,
+    fixes: Mutex<Vec<String>>,
+    node_reports: Mutex<Vec<NodeReport>>,
+    generated_textures: Mutex<Vec<GeneratedTexture>>,
+    baked_textures: Mutex<Vec<BakedTexture>>,
+}
+
+impl Context {
+    pub fn new(buffers: Vec<BufferData>, is_character: bool, options: ConvertOptions) -> Self {
+        Self {
+            buffers,
+            is_character,
+            options,
+            errors: Mutex::new(Vec::new()),
+            progress: Box::new(NoProgress),
+            diagnostics: Box::new(LogDiagnostics),
+            cancellation: None,
+            fixes: Mutex::new(Vec::new()),
+            node_reports: Mutex::new(Vec::new()),
+            generated_textures: Mutex::new(Vec::new()),
+            baked_textures: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn with_progress<P: ProgressListener + 'static>(mut self, progress: P) -> Self {
+        self.progress = Box::new(progress);
+        self
+    }
+
+    /// Sets the sink receiving conversion warnings (by default they are written to the `log` crate)
+    pub fn with_diagnostics<D: DiagnosticsSink + 'static>(mut self, diagnostics: D) -> Self {
+        self.diagnostics = Box::new(diagnostics);
+        self
+    }
+
+    /// Sets the token checked during conversion. Once it is cancelled converters fail with
+    /// [`ConvertError::Cancelled`]
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    pub fn options(&self) -> &ConvertOptions {
+        &self.options
+    }
+
+    /// Returns report entries of all nodes converted so far
+    pub fn take_node_reports(&self) -> Vec<NodeReport> {
+        std::mem::take(&mut lock(&self.node_reports))
+    }
+
+    /// Returns solid color textures referenced by the converted meshes (see [`ConvertOptions::color_textures`])
+    pub fn take_generated_textures(&self) -> Vec<GeneratedTexture> {
+        std::mem::take(&mut lock(&self.generated_textures))
+    }
+
+    pub(crate) fn add_generated_texture(&self, texture: GeneratedTexture) {
+        let mut textures = lock(&self.generated_textures);
+        if !textures.contains(&texture) {
+            textures.push(texture);
+        }
+    }
+
+    /// Returns textures with baked ambient occlusion referenced by the converted meshes (see
+    /// [`ConvertOptions::bake_occlusion`])
+    pub fn take_baked_textures(&self) -> Vec<BakedTexture> {
+        std::mem::take(&mut lock(&self.baked_textures))
+    }
+
+    pub(crate) fn add_baked_texture(&self, texture: BakedTexture) {
+        let mut textures = lock(&self.baked_textures);
+        if !textures.contains(&texture) {
+            textures.push(texture);
+        }
+    }
+
+    pub(crate) fn get_buffer_data(&self, buffer: gltf::Buffer) -> Option<&[u8]> {
+        self.buffers().get(buffer)
+    }
+
+    pub(crate) fn buffers(&self) -> Buffers<'_> {
+        Buffers(&self.buffers)
+    }
+
+    pub(crate) fn warn(&self, kind: WarningKind, message: &str) {
+        if warnings::is_strict(&self.options.strict, kind) {
+            self.report_error(format!("{} (strict mode)", message));
+        } else {
+            self.diagnostics.warning(kind, message);
+        }
+    }
+
+    /// Records a problem that makes the output invalid but does not prevent further analysis of the input
+    pub(crate) fn report_error(&self, message: String) {
+        lock(&self.errors).push(message);
+    }
+
+    /// Records an automatic correction applied to the currently converted node
+    pub(crate) fn record_fix(&self, message: String) {
+        debug!("Applied fix: {}", message);
+        let mut fixes = lock(&self.fixes);
+        if !fixes.contains(&message) {
+            fixes.push(message);
+        }
+    }
+
+    /// Adds a converted node to the conversion report together with fixes recorded since the previous node
+    pub(crate) fn add_node_report(&self, mut node_report: NodeReport) {
+        node_report.fixes = std::mem::take(&mut lock(&self.fixes));
+        lock(&self.node_reports).push(node_report);
+    }
+
+    /// Fails if conversion was cancelled by the user
+    pub(crate) fn check_cancelled(&self) -> ConvertResult<()> {
+        if self
+            .cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            return Err(ConvertError::Cancelled);
+        }
+        Ok(())
+    }
+
+    /// Fails with a report of all problems found so far
+    pub fn check_errors(&self) -> ConvertResult<()> {
+        let errors = lock(&self.errors);
+        if errors.is_empty() {
+            return Ok(());
+        }
+        Err(ConvertError::Validation(errors.clone()))
+    }
+}
+
+// Fails to compile if Context stops being usable from multiple threads
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<Context>();
+};
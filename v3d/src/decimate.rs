@@ -0,0 +1,331 @@
+//! Mesh simplification used for automatic LOD generation.
+//!
+//! Implements quadric error metric decimation (Garland-Heckbert) using half-edge collapses: a vertex is always merged
+//! into one of its neighbours so vertex attributes (normals, UVs, weights) never have to be interpolated. Vertices on
+//! open borders and UV/normal seams (different vertices sharing a position) are never removed so the silhouette and
+//! texture mapping stay intact.
+
+use crate::math_utils::{compute_triangle_plane, Vector3};
+use crate::v3mc::{MeshChunkData, MeshFace};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+
+/// Symmetric 4x4 matrix stored as upper triangle
+#[derive(Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    fn from_plane(a: f64, b: f64, c: f64, d: f64, weight: f64) -> Self {
+        let q = [
+            a * a,
+            a * b,
+            a * c,
+            a * d,
+            b * b,
+            b * c,
+            b * d,
+            c * c,
+            c * d,
+            d * d,
+        ];
+        Self(q.map(|v| v * weight))
+    }
+
+    fn add(&mut self, other: &Self) {
+        for (a, b) in self.0.iter_mut().zip(other.0) {
+            *a += b;
+        }
+    }
+
+    fn error(&self, p: &Vector3) -> f64 {
+        let q = &self.0;
+        let (x, y, z) = (f64::from(p[0]), f64::from(p[1]), f64::from(p[2]));
+        q[0] * x * x
+            + 2.0 * q[1] * x * y
+            + 2.0 * q[2] * x * z
+            + 2.0 * q[3] * x
+            + q[4] * y * y
+            + 2.0 * q[5] * y * z
+            + 2.0 * q[6] * y
+            + q[7] * z * z
+            + 2.0 * q[8] * z
+            + q[9]
+    }
+}
+
+/// Collapse of vertex `from` into vertex `to`. Versions are used to detect outdated heap entries
+struct Collapse {
+    cost: f64,
+    from: usize,
+    to: usize,
+    from_version: u32,
+    to_version: u32,
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cost.total_cmp(&other.cost)
+    }
+}
+
+fn triangle_cross(p0: &Vector3, p1: &Vector3, p2: &Vector3) -> [f64; 3] {
+    let e0 = [0, 1, 2].map(|i| f64::from(p1[i] - p0[i]));
+    let e1 = [0, 1, 2].map(|i| f64::from(p2[i] - p0[i]));
+    [
+        e0[1] * e1[2] - e0[2] * e1[1],
+        e0[2] * e1[0] - e0[0] * e1[2],
+        e0[0] * e1[1] - e0[1] * e1[0],
+    ]
+}
+
+fn dot(a: &[f64; 3], b: &[f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+struct Decimator<'a> {
+    vecs: &'a [Vector3],
+    faces: Vec<[usize; 3]>,
+    face_alive: Vec<bool>,
+    vertex_faces: Vec<Vec<usize>>,
+    quadrics: Vec<Quadric>,
+    locked: Vec<bool>,
+    removed: Vec<bool>,
+    versions: Vec<u32>,
+    heap: BinaryHeap<Reverse<Collapse>>,
+}
+
+impl<'a> Decimator<'a> {
+    fn new(chunk: &'a MeshChunkData) -> Self {
+        let vecs = &chunk.vecs;
+        let num_vecs = vecs.len();
+        let faces: Vec<[usize; 3]> = chunk
+            .faces
+            .iter()
+            .map(|f| f.vindices.map(usize::from))
+            .collect();
+
+        let mut vertex_faces = vec![Vec::new(); num_vecs];
+        let mut quadrics = vec![Quadric::default(); num_vecs];
+        let mut edge_use_count: HashMap<(usize, usize), u32> = HashMap::new();
+        for (face_index, face) in faces.iter().enumerate() {
+            let cross = triangle_cross(&vecs[face[0]], &vecs[face[1]], &vecs[face[2]]);
+            let len = dot(&cross, &cross).sqrt();
+            if len > 0.0 {
+                let [a, b, c] = cross.map(|v| v / len);
+                let p0 = &vecs[face[0]];
+                let d = -(a * f64::from(p0[0]) + b * f64::from(p0[1]) + c * f64::from(p0[2]));
+                // weight by triangle area so small triangles do not dominate the error
+                let quadric = Quadric::from_plane(a, b, c, d, len / 2.0);
+                for &v in face {
+                    quadrics[v].add(&quadric);
+                }
+            }
+            for (i, &v) in face.iter().enumerate() {
+                vertex_faces[v].push(face_index);
+                let w = face[(i + 1) % 3];
+                *edge_use_count.entry((v.min(w), v.max(w))).or_default() += 1;
+            }
+        }
+
+        let mut locked = vec![false; num_vecs];
+        // open borders
+        for ((v, w), count) in edge_use_count {
+            if count == 1 {
+                locked[v] = true;
+                locked[w] = true;
+            }
+        }
+        // seams - vertices duplicated because of different normals or texture coordinates
+        let mut position_users: HashMap<[u32; 3], Vec<usize>> = HashMap::new();
+        for (i, pos) in vecs.iter().enumerate() {
+            position_users
+                .entry(pos.map(f32::to_bits))
+                .or_default()
+                .push(i);
+        }
+        for users in position_users.values().filter(|u| u.len() > 1) {
+            for &v in users {
+                locked[v] = true;
+            }
+        }
+
+        let face_alive = vec![true; faces.len()];
+        let mut decimator = Self {
+            vecs,
+            faces,
+            face_alive,
+            vertex_faces,
+            quadrics,
+            locked,
+            removed: vec![false; num_vecs],
+            versions: vec![0; num_vecs],
+            heap: BinaryHeap::new(),
+        };
+        for v in 0..num_vecs {
+            decimator.push_vertex_collapses(v);
+        }
+        decimator
+    }
+
+    fn push_collapse(&mut self, from: usize, to: usize) {
+        if self.locked[from] || from == to {
+            return;
+        }
+        let mut quadric = self.quadrics[from];
+        quadric.add(&self.quadrics[to]);
+        self.heap.push(Reverse(Collapse {
+            cost: quadric.error(&self.vecs[to]),
+            from,
+            to,
+            from_version: self.versions[from],
+            to_version: self.versions[to],
+        }));
+    }
+
+    /// Pushes collapses of all edges adjacent to the vertex in both directions
+    fn push_vertex_collapses(&mut self, v: usize) {
+        let mut neighbours = Vec::new();
+        for &f in &self.vertex_faces[v] {
+            if self.face_alive[f] {
+                neighbours.extend(self.faces[f].iter().copied().filter(|&w| w != v));
+            }
+        }
+        neighbours.sort_unstable();
+        neighbours.dedup();
+        for w in neighbours {
+            self.push_collapse(v, w);
+            self.push_collapse(w, v);
+        }
+    }
+
+    /// Checks if moving `from` onto `to` does not flip or degenerate any of the remaining triangles
+    fn is_collapse_valid(&self, from: usize, to: usize) -> bool {
+        for &f in &self.vertex_faces[from] {
+            let face = &self.faces[f];
+            if !self.face_alive[f] || face.contains(&to) {
+                continue;
+            }
+            let old_cross = triangle_cross(
+                &self.vecs[face[0]],
+                &self.vecs[face[1]],
+                &self.vecs[face[2]],
+            );
+            let new_face = face.map(|v| if v == from { to } else { v });
+            let new_cross = triangle_cross(
+                &self.vecs[new_face[0]],
+                &self.vecs[new_face[1]],
+                &self.vecs[new_face[2]],
+            );
+            if dot(&old_cross, &new_cross) <= 0.0 {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Performs the collapse and returns number of removed faces
+    fn collapse(&mut self, from: usize, to: usize) -> usize {
+        let mut num_removed = 0;
+        let from_faces = std::mem::take(&mut self.vertex_faces[from]);
+        for f in from_faces {
+            if !self.face_alive[f] {
+                continue;
+            }
+            if self.faces[f].contains(&to) {
+                self.face_alive[f] = false;
+                num_removed += 1;
+            } else {
+                for v in &mut self.faces[f] {
+                    if *v == from {
+                        *v = to;
+                    }
+                }
+                self.vertex_faces[to].push(f);
+            }
+        }
+        self.removed[from] = true;
+        let from_quadric = self.quadrics[from];
+        self.quadrics[to].add(&from_quadric);
+        self.versions[to] += 1;
+        self.push_vertex_collapses(to);
+        num_removed
+    }
+
+    fn run(&mut self, target_faces: usize) {
+        let mut num_faces = self.faces.len();
+        while num_faces > target_faces {
+            let Some(Reverse(c)) = self.heap.pop() else {
+                break;
+            };
+            let outdated = self.removed[c.from]
+                || self.removed[c.to]
+                || self.versions[c.from] != c.from_version
+                || self.versions[c.to] != c.to_version;
+            if outdated || !self.is_collapse_valid(c.from, c.to) {
+                continue;
+            }
+            num_faces -= self.collapse(c.from, c.to);
+        }
+    }
+}
+
+/// Returns simplified copy of the chunk with approximately `ratio` of the original triangles. Face planes are only
+/// computed if the source chunk has them.
+pub(crate) fn decimate_chunk(chunk: &MeshChunkData, ratio: f32) -> MeshChunkData {
+    let target_faces = (chunk.faces.len() as f32 * ratio).ceil() as usize;
+    let mut decimator = Decimator::new(chunk);
+    decimator.run(target_faces);
+
+    // compact vertices that are still in use keeping their original order
+    let mut vertex_map = vec![None; chunk.vecs.len()];
+    let mut faces = Vec::new();
+    let mut used_vertices = Vec::new();
+    for (f, face) in decimator.faces.iter().enumerate() {
+        if !decimator.face_alive[f] {
+            continue;
+        }
+        let vindices = face.map(|v| {
+            *vertex_map[v].get_or_insert_with(|| {
+                used_vertices.push(v);
+                (used_vertices.len() - 1) as u16
+            })
+        });
+        faces.push(MeshFace {
+            vindices,
+            flags: chunk.faces[f].flags,
+        });
+    }
+    let vecs: Vec<_> = used_vertices.iter().map(|&v| chunk.vecs[v]).collect();
+    let face_planes = if chunk.face_planes.is_empty() {
+        Vec::new()
+    } else {
+        faces
+            .iter()
+            .map(|face| face.vindices.map(usize::from))
+            .map(|[i, j, k]| compute_triangle_plane(&vecs[i], &vecs[j], &vecs[k]))
+            .collect()
+    };
+    MeshChunkData {
+        norms: used_vertices.iter().map(|&v| chunk.norms[v]).collect(),
+        uvs: used_vertices.iter().map(|&v| chunk.uvs[v]).collect(),
+        wi: used_vertices.iter().map(|&v| chunk.wi[v]).collect(),
+        same_pos_vertex_offsets: vec![0; vecs.len()],
+        vecs,
+        faces,
+        face_planes,
+    }
+}
@@ -0,0 +1,41 @@
+use crate::errors::{Location, MaterialRef, NodeRef};
+use crate::warnings::WarningKind;
+
+/// Receives warnings found during conversion (warnings turned into errors by strict mode are not passed here, they
+/// are returned as [`crate::errors::ConvertError::Validation`] instead).
+///
+/// Any thread-safe `Fn(WarningKind, &str)` closure can be used as a sink.
+pub trait DiagnosticsSink: Send + Sync {
+    fn warning(&self, kind: WarningKind, message: &str);
+}
+
+impl<F: Fn(WarningKind, &str) + Send + Sync> DiagnosticsSink for F {
+    fn warning(&self, kind: WarningKind, message: &str) {
+        self(kind, message)
+    }
+}
+
+/// Default sink that writes warnings to the `log` crate
+pub struct LogDiagnostics;
+
+impl DiagnosticsSink for LogDiagnostics {
+    fn warning(&self, _kind: WarningKind, message: &str) {
+        warn!("{}", message);
+    }
+}
+
+/// Describes a node for diagnostic messages, e.g. `node 'Cube'` or `node #3` if it has no name
+pub fn describe_node(node: &gltf::Node) -> String {
+    NodeRef::from(node).to_string()
+}
+
+/// Describes a material for diagnostic messages, e.g. `material 'Wood'`, `material #2` or `default material`
+pub fn describe_material(material: &gltf::Material) -> String {
+    MaterialRef::from(material).to_string()
+}
+
+/// Describes a mesh primitive together with its node and material, e.g.
+/// `node 'Cube', primitive #1 (material 'Wood')`
+pub fn describe_primitive(node: &gltf::Node, index: usize, prim: &gltf::Primitive) -> String {
+    Location::primitive(Some(node), index, prim).to_string()
+}
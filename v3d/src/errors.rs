@@ -0,0 +1,226 @@
+use std::error::Error;
+use std::fmt;
+
+/// Category of failure used to select the process exit code
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ErrorCategory {
+    /// Unexpected failure (e.g. panic)
+    Internal,
+    /// Invalid command line
+    Usage,
+    /// Input file cannot be parsed or contains unsupported data
+    Input,
+    /// Output would exceed engine limits (or violates strict mode)
+    Validation,
+    /// Reading or writing files failed
+    Io,
+}
+
+impl ErrorCategory {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Self::Internal => 1,
+            // the same exit code is used by clap
+            Self::Usage => 2,
+            Self::Input => 3,
+            Self::Validation => 4,
+            Self::Io => 5,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CategorizedError {
+    pub category: ErrorCategory,
+    message: String,
+}
+
+impl CategorizedError {
+    pub fn new<S: Into<String>>(category: ErrorCategory, message: S) -> Self {
+        Self {
+            category,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for CategorizedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl Error for CategorizedError {}
+
+/// Reference to a GLTF node used in error messages
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NodeRef {
+    pub index: usize,
+    pub name: Option<String>,
+}
+
+#[cfg(feature = "gltf")]
+impl From<&gltf::Node<'_>> for NodeRef {
+    fn from(node: &gltf::Node) -> Self {
+        Self {
+            index: node.index(),
+            name: node.name().map(str::to_owned),
+        }
+    }
+}
+
+impl fmt::Display for NodeRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.name {
+            Some(name) => write!(f, "node '{}'", name),
+            None => write!(f, "node #{}", self.index),
+        }
+    }
+}
+
+/// Reference to a GLTF material used in error messages. Index is `None` for the default material
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MaterialRef {
+    pub index: Option<usize>,
+    pub name: Option<String>,
+}
+
+#[cfg(feature = "gltf")]
+impl From<&gltf::Material<'_>> for MaterialRef {
+    fn from(material: &gltf::Material) -> Self {
+        Self {
+            index: material.index(),
+            name: material.name().map(str::to_owned),
+        }
+    }
+}
+
+impl fmt::Display for MaterialRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.name, self.index) {
+            (Some(name), _) => write!(f, "material '{}'", name),
+            (None, Some(index)) => write!(f, "material #{}", index),
+            (None, None) => f.write_str("default material"),
+        }
+    }
+}
+
+/// Place in the input file where a problem was found
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Location {
+    pub node: Option<NodeRef>,
+    /// Index of primitive in the node mesh
+    pub primitive: Option<usize>,
+    pub material: Option<MaterialRef>,
+}
+
+#[cfg(feature = "gltf")]
+impl Location {
+    pub fn node(node: &gltf::Node) -> Self {
+        Self {
+            node: Some(node.into()),
+            ..Self::default()
+        }
+    }
+
+    pub fn material(material: &gltf::Material) -> Self {
+        Self {
+            material: Some(material.into()),
+            ..Self::default()
+        }
+    }
+
+    pub fn primitive(node: Option<&gltf::Node>, index: usize, prim: &gltf::Primitive) -> Self {
+        Self {
+            node: node.map(NodeRef::from),
+            primitive: Some(index),
+            material: Some((&prim.material()).into()),
+        }
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(node) = &self.node {
+            parts.push(node.to_string());
+        }
+        match (self.primitive, &self.material) {
+            (Some(index), Some(material)) => {
+                parts.push(format!("primitive #{} ({})", index, material))
+            }
+            (Some(index), None) => parts.push(format!("primitive #{}", index)),
+            (None, Some(material)) => parts.push(material.to_string()),
+            (None, None) => {}
+        }
+        if parts.is_empty() {
+            f.write_str("<unknown location>")
+        } else {
+            f.write_str(&parts.join(", "))
+        }
+    }
+}
+
+/// Error returned by the converters
+#[derive(Debug, thiserror::Error)]
+pub enum ConvertError {
+    #[error("{0}: only triangle list primitives are supported")]
+    UnsupportedPrimitiveMode(Location),
+    #[error("{0}: not indexed geometry is not supported")]
+    NotIndexed(Location),
+    #[error("{location}: mesh has no {attribute}")]
+    MissingAttribute {
+        location: Location,
+        attribute: &'static str,
+    },
+    #[error("{location}: number of {attribute} ({count}) does not match number of positions ({expected})")]
+    AttributeCountMismatch {
+        location: Location,
+        attribute: &'static str,
+        count: usize,
+        expected: usize,
+    },
+    #[error("{0}: vertex index does not fit in 16 bits")]
+    VertexIndexOverflow(Location),
+    #[error("{location}: vertex index {index} is out of range (vertex count {vertex_count})")]
+    VertexIndexOutOfRange {
+        location: Location,
+        index: u16,
+        vertex_count: usize,
+    },
+    #[error("{0}: primitive is too big to be stored in a single mesh chunk")]
+    ChunkTooBig(Location),
+    #[error("{0}: joint index does not fit in 8 bits")]
+    JointIndexOverflow(Location),
+    #[error("{0}: name is missing")]
+    MissingName(Location),
+    #[error("{0}: scaled bones are not supported")]
+    ScaledBone(Location),
+    #[error("skin '{skin}': {message}")]
+    InvalidSkin { skin: String, message: String },
+    #[error("{0}")]
+    InvalidInput(String),
+    #[error("conversion was cancelled")]
+    Cancelled,
+    /// Output would exceed engine limits or violate strict mode. Contains all problems that were found
+    #[error("{}", crate::format_error_report(.0))]
+    Validation(Vec<String>),
+    #[cfg(feature = "gltf")]
+    #[error(transparent)]
+    Gltf(#[from] gltf::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+impl ConvertError {
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Self::Validation(_) => ErrorCategory::Validation,
+            Self::Cancelled => ErrorCategory::Usage,
+            #[cfg(feature = "gltf")]
+            Self::Gltf(gltf::Error::Io(_)) => ErrorCategory::Io,
+            Self::Io(_) => ErrorCategory::Io,
+            _ => ErrorCategory::Input,
+        }
+    }
+}
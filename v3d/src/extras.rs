@@ -0,0 +1,251 @@
+//! Validation of RF-specific extras (custom properties) against the keys understood by the converter. Unknown keys
+//! are usually typos (e.g. `LOD_Distance`) that would otherwise be silently ignored.
+
+use crate::diagnostics::{describe_material, describe_node};
+use crate::get_submesh_nodes;
+use crate::warnings::WarningKind;
+use crate::{Context, MaterialPreset};
+use serde_json::{Map, Value};
+
+#[derive(Clone, Copy)]
+enum ValueType {
+    Number,
+    Bool,
+    String,
+    /// Array of two numbers
+    Vector2,
+    /// Name of a material preset
+    MaterialPreset,
+    /// Object with string values
+    StringMap,
+    /// Array of objects with string `name` and number `value`
+    RecordList,
+}
+
+impl ValueType {
+    fn matches(self, value: &Value) -> bool {
+        match self {
+            Self::Number => value.is_number(),
+            Self::Bool => value.is_boolean(),
+            Self::String => value.is_string(),
+            Self::Vector2 => value
+                .as_array()
+                .is_some_and(|items| items.len() == 2 && items.iter().all(Value::is_number)),
+            Self::MaterialPreset => value.as_str().and_then(MaterialPreset::from_name).is_some(),
+            Self::StringMap => value
+                .as_object()
+                .is_some_and(|map| map.values().all(Value::is_string)),
+            Self::RecordList => value.as_array().is_some_and(|records| {
+                records.iter().all(|r| {
+                    r.as_object().is_some_and(|map| {
+                        map.len() == 2
+                            && map.get("name").is_some_and(Value::is_string)
+                            && map.get("value").is_some_and(Value::is_number)
+                    })
+                })
+            }),
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Self::Number => "a number",
+            Self::Bool => "a boolean",
+            Self::String => "a string",
+            Self::Vector2 => "an array of two numbers",
+            Self::MaterialPreset => {
+                "a material preset name (default, glass, chrome, additive_glow, foliage_cutout, water)"
+            }
+            Self::StringMap => "an object with string values",
+            Self::RecordList => "an array of objects with name (string) and value (number)",
+        }
+    }
+}
+
+/// Keys allowed in extras of mesh nodes (LOD groups and LOD meshes)
+const MESH_NODE_KEYS: [(&str, ValueType); 3] = [
+    ("LOD_distance", ValueType::Number),
+    ("LOD_textures", ValueType::StringMap),
+    ("V3M_unknown1", ValueType::RecordList),
+];
+
+/// Keys allowed in extras of materials
+const MATERIAL_KEYS: [(&str, ValueType); 7] = [
+    ("RF_preset", ValueType::MaterialPreset),
+    ("rf_preset", ValueType::MaterialPreset),
+    ("RF_reflection_map", ValueType::String),
+    ("RF_uv_scroll", ValueType::Vector2),
+    ("RF_collision_only", ValueType::Bool),
+    ("RF_show_sky", ValueType::Bool),
+    ("RF_portal", ValueType::Bool),
+];
+
+/// Keys allowed in extras of animations
+const ANIMATION_KEYS: [(&str, ValueType); 3] = [
+    ("RF_loop", ValueType::Bool),
+    ("RF_ramp_in_time", ValueType::Number),
+    ("RF_ramp_out_time", ValueType::Number),
+];
+
+/// Prefixes of keys allowed in extras of joints. Full key has form `<prefix>.<animation name>`
+const JOINT_KEY_PREFIXES: [&str; 3] = ["weight", "ramp_in_time", "ramp_out_time"];
+
+/// Returns Levenshtein distance between two strings ignoring letter case
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+/// Returns the most similar candidate if it is close enough to be a likely typo
+fn suggest<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    const MAX_DISTANCE: usize = 2;
+    candidates
+        .into_iter()
+        .map(|c| (edit_distance(name, c), c))
+        .filter(|(d, _)| *d <= MAX_DISTANCE)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, c)| c)
+}
+
+fn format_suggestion(suggestion: Option<&str>) -> String {
+    suggestion.map_or_else(String::new, |s| format!(" - did you mean '{}'?", s))
+}
+
+fn get_extras_map(
+    extras: &gltf::json::extras::Extras,
+    location: &str,
+    ctx: &Context,
+) -> Option<Map<String, Value>> {
+    let raw = extras.as_ref()?;
+    match serde_json::from_str::<Value>(raw.get()) {
+        Ok(Value::Object(map)) => Some(map),
+        _ => {
+            ctx.warn(
+                WarningKind::InvalidExtras,
+                &format!("{}: extras are not an object", location),
+            );
+            None
+        }
+    }
+}
+
+fn validate_keys(
+    extras: &gltf::json::extras::Extras,
+    keys: &[(&str, ValueType)],
+    location: &str,
+    ctx: &Context,
+) {
+    let Some(map) = get_extras_map(extras, location, ctx) else {
+        return;
+    };
+    for (key, value) in &map {
+        match keys.iter().find(|(k, _)| *k == key.as_str()) {
+            Some((_, value_type)) if !value_type.matches(value) => ctx.warn(
+                WarningKind::InvalidExtras,
+                &format!(
+                    "{}: extras property '{}' should be {}",
+                    location,
+                    key,
+                    value_type.describe()
+                ),
+            ),
+            Some(_) => {}
+            None => ctx.warn(
+                WarningKind::InvalidExtras,
+                &format!(
+                    "{}: unknown extras property '{}'{}",
+                    location,
+                    key,
+                    format_suggestion(suggest(key, keys.iter().map(|(k, _)| *k)))
+                ),
+            ),
+        }
+    }
+}
+
+fn validate_mesh_node_extras(node: &gltf::Node, ctx: &Context) {
+    validate_keys(node.extras(), &MESH_NODE_KEYS, &describe_node(node), ctx);
+}
+
+fn validate_joint_extras(node: &gltf::Node, anim_names: &[&str], ctx: &Context) {
+    let Some(map) = get_extras_map(node.extras(), &describe_node(node), ctx) else {
+        return;
+    };
+    for (key, value) in &map {
+        let (prefix, anim_name) = key.split_once('.').unwrap_or((key.as_str(), ""));
+        let problem = if !JOINT_KEY_PREFIXES.contains(&prefix) {
+            let suggestion = suggest(prefix, JOINT_KEY_PREFIXES)
+                .map(|s| format!("{}.{}", s, anim_name))
+                .filter(|_| !anim_name.is_empty());
+            Some(format!(
+                "unknown extras property '{}'{}",
+                key,
+                format_suggestion(suggestion.as_deref())
+            ))
+        } else if !anim_names.contains(&anim_name) {
+            let suggestion =
+                suggest(anim_name, anim_names.iter().copied()).map(|s| format!("{}.{}", prefix, s));
+            Some(format!(
+                "extras property '{}' refers to unknown animation '{}'{}",
+                key,
+                anim_name,
+                format_suggestion(suggestion.as_deref())
+            ))
+        } else if !value.is_number() {
+            Some(format!("extras property '{}' should be a number", key))
+        } else {
+            None
+        };
+        if let Some(problem) = problem {
+            ctx.warn(
+                WarningKind::InvalidExtras,
+                &format!("{}: {}", describe_node(node), problem),
+            );
+        }
+    }
+}
+
+/// Checks extras of mesh nodes, joints, animations and materials. Extras of other nodes are not used by the converter so they are
+/// ignored.
+pub(crate) fn validate_extras(doc: &gltf::Document, ctx: &Context) {
+    for node in get_submesh_nodes(doc) {
+        validate_mesh_node_extras(&node, ctx);
+        for child in node.children().filter(|n| n.mesh().is_some()) {
+            validate_mesh_node_extras(&child, ctx);
+        }
+    }
+    if let Some(skin) = doc.skins().next() {
+        let anim_names: Vec<_> = doc
+            .animations()
+            .map(|a| a.name().unwrap_or_default())
+            .collect();
+        for joint in skin.joints() {
+            validate_joint_extras(&joint, &anim_names, ctx);
+        }
+        for (i, anim) in doc.animations().enumerate() {
+            let location = anim.name().map_or_else(
+                || format!("animation #{}", i),
+                |name| format!("animation '{}'", name),
+            );
+            validate_keys(anim.extras(), &ANIMATION_KEYS, &location, ctx);
+        }
+    }
+    for material in doc.materials() {
+        validate_keys(
+            material.extras(),
+            &MATERIAL_KEYS,
+            &describe_material(&material),
+            ctx,
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use binrw::{BinResult, Endian};
+use byteorder::{ByteOrder, LittleEndian, ReadBytesExt, WriteBytesExt};
+use rf_encoding::{self as encoding, Encoding};
+use std::{
+    convert::TryInto,
+    io::{Read, Seek, Write},
+};
+
+/// Encodes string stored in RF files. RF uses Windows-1252 for names and other texts
+pub(crate) fn encode_string(string: &str) -> std::io::Result<Vec<u8>> {
+    encoding::encode(string, Encoding::Windows1252, false).map_err(|e| {
+        new_custom_error(format!(
+            "string value {} contains character '{}' that cannot be represented in Windows-1252",
+            string, e.character
+        ))
+    })
+}
+
+/// Decodes string stored in RF files (see [`encode_string`])
+pub(crate) fn decode_string(bytes: &[u8]) -> String {
+    encoding::decode(bytes, Some(Encoding::Windows1252)).0
+}
+
+pub(crate) trait WriteExt: Write {
+    fn write_f32_slice<T: ByteOrder>(&mut self, slice: &[f32]) -> std::io::Result<()> {
+        for val in slice {
+            self.write_f32::<T>(*val)?;
+        }
+        Ok(())
+    }
+
+    fn write_i16_slice<T: ByteOrder>(&mut self, slice: &[i16]) -> std::io::Result<()> {
+        for val in slice {
+            self.write_i16::<T>(*val)?;
+        }
+        Ok(())
+    }
+
+    fn write_char_array(&mut self, string: &str, size: usize) -> std::io::Result<()> {
+        let bytes = encode_string(string)?;
+        if bytes.len() >= size {
+            return Err(new_custom_error(format!(
+                "string value {} is too long (max {})",
+                string,
+                size - 1
+            )));
+        }
+        self.write_all(&bytes)?;
+        let padding = vec![0_u8; size - bytes.len()];
+        self.write_all(&padding)?;
+        Ok(())
+    }
+
+    fn write_vstr(&mut self, string: &str) -> std::io::Result<()> {
+        let bytes = encode_string(string)?;
+        let len = bytes
+            .len()
+            .try_into()
+            .map_err(|_| new_custom_error(format!("string value {} is too long", string)))?;
+        self.write_u16::<LittleEndian>(len)?;
+        self.write_all(&bytes)?;
+        Ok(())
+    }
+}
+
+impl<T: Write> WriteExt for T {}
+
+pub(crate) trait ReadExt: Read {
+    fn read_f32_array<const N: usize>(&mut self) -> std::io::Result<[f32; N]> {
+        let mut arr = [0_f32; N];
+        self.read_f32_into::<LittleEndian>(&mut arr)?;
+        Ok(arr)
+    }
+
+    fn read_vstr(&mut self) -> std::io::Result<String> {
+        let len = self.read_u16::<LittleEndian>()?;
+        let mut buf = vec![0_u8; usize::from(len)];
+        self.read_exact(&mut buf)?;
+        Ok(decode_string(&buf))
+    }
+
+    fn read_char_array(&mut self, size: usize) -> std::io::Result<String> {
+        let mut buf = vec![0_u8; size];
+        self.read_exact(&mut buf)?;
+        let len = buf.iter().position(|&b| b == 0).unwrap_or(size);
+        Ok(decode_string(&buf[..len]))
+    }
+}
+
+impl<T: Read> ReadExt for T {}
+
+pub(crate) fn new_custom_error<S: Into<String>>(msg: S) -> std::io::Error {
+    std::io::Error::other(msg.into())
+}
+
+pub(crate) fn binrw_to_io_error(err: binrw::Error) -> std::io::Error {
+    match err {
+        binrw::Error::Io(err) => err,
+        err => std::io::Error::new(std::io::ErrorKind::InvalidData, err.to_string()),
+    }
+}
+
+/// Reads fixed size null-padded string (for use with binrw `parse_with`)
+pub(crate) fn parse_char_array<R: Read + Seek>(
+    rdr: &mut R,
+    _endian: Endian,
+    size: usize,
+) -> BinResult<String> {
+    Ok(rdr.read_char_array(size)?)
+}
+
+/// Writes fixed size null-padded string (for use with binrw `write_with`)
+#[allow(clippy::ptr_arg)] // signature is dictated by binrw
+pub(crate) fn write_char_array_field<W: Write + Seek>(
+    value: &String,
+    wrt: &mut W,
+    _endian: Endian,
+    size: usize,
+) -> BinResult<()> {
+    Ok(wrt.write_char_array(value, size)?)
+}
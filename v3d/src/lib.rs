@@ -0,0 +1,287 @@
+//! Conversion of GLTF files into Red Faction formats: static meshes (V3M), character meshes (V3C), animations
+//! (RFA), groups (RFG) and levels (RFL).
+//!
+//! Typical usage: import a GLTF file with [`import_gltf`], create a [`Context`] and call one of the converters, e.g.
+//! [`v3mc_convert::convert_gltf_to_v3mc`]. Converted files can be serialized with their `write` methods.
+//!
+//! Warnings are written to the `log` crate by default. Use [`Context::with_diagnostics`] to capture them instead.
+//!
+//! Embedders can follow conversion progress by passing a [`ProgressListener`] to [`Context::with_progress`] and abort
+//! it using [`cancel::CancellationToken`] passed to [`Context::with_cancellation`].
+//!
+//! Meshes generated by code can be created with [`v3m_builder::V3mBuilder`].
+//!
+//! GLTF import and all converters are enabled by the default `gltf` feature. Applications that only read or write
+//! Red Faction files (V3M/V3C, RFA, RFG, RFL) can disable default features to avoid gltf, glam, rayon and memmap2
+//! dependencies.
+//!
+//! Conversion failures are reported as [`errors::ConvertError`] which identifies the node, primitive and material
+//! the problem was found in.
+
+#[cfg(feature = "gltf")]
+pub mod budget;
+#[cfg(feature = "gltf")]
+pub mod buffer;
+pub mod cancel;
+#[cfg(feature = "gltf")]
+pub mod char_anim;
+#[cfg(feature = "gltf")]
+mod context;
+#[cfg(feature = "gltf")]
+mod decimate;
+#[cfg(feature = "gltf")]
+pub mod diagnostics;
+pub mod errors;
+#[cfg(feature = "gltf")]
+mod extras;
+mod io_utils;
+pub mod limits;
+#[cfg(feature = "gltf")]
+mod material;
+#[cfg(feature = "gltf")]
+mod math_utils;
+#[cfg(feature = "gltf")]
+mod names;
+pub mod progress;
+pub mod report;
+#[cfg(feature = "gltf")]
+pub mod retarget;
+pub mod rfa;
+pub mod rfg;
+#[cfg(feature = "gltf")]
+pub mod rfg_convert;
+pub mod rfl;
+#[cfg(feature = "gltf")]
+pub mod rfl_brush_convert;
+#[cfg(feature = "gltf")]
+pub mod rfl_convert;
+#[cfg(feature = "gltf")]
+mod smoothing;
+#[cfg(feature = "gltf")]
+pub mod v3m_builder;
+pub mod v3mc;
+#[cfg(feature = "gltf")]
+pub mod v3mc_convert;
+pub mod warnings;
+
+#[cfg(feature = "gltf")]
+pub(crate) use context::{
+    extract_translation_from_matrix, get_node_local_transform, gltf_to_rf_face, gltf_to_rf_quat,
+    gltf_to_rf_vec, NoProgress,
+};
+#[cfg(feature = "gltf")]
+pub use context::{
+    get_mesh_materials, get_primitive_vertex_count, get_rendered_primitives, get_submesh_nodes,
+    import_gltf, import_gltf_slice, is_collision_only, BakedTexture, Context, GeneratedTexture,
+    TextureImage,
+};
+use errors::ConvertError;
+use limits::Limits;
+use std::collections::BTreeMap;
+use std::error::Error;
+use warnings::WarningKind;
+
+// logging is used only by the GLTF converter
+#[cfg(feature = "gltf")]
+#[macro_use]
+extern crate log;
+
+pub type BoxResult<T> = Result<T, Box<dyn Error>>;
+pub type ConvertResult<T> = Result<T, ConvertError>;
+
+pub fn format_error_report(errors: &[String]) -> String {
+    let mut report = format!("found {} problem(s):", errors.len());
+    for error in errors {
+        report += "\n  - ";
+        report += error;
+    }
+    report
+}
+
+/// Local axis of a GLTF node that prop points (e.g. weapon muzzles) point along. RF prop points point along Z axis
+/// but empties created by some DCC tools point along a different axis.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, serde_derive::Deserialize)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum PropAxis {
+    X,
+    NegX,
+    Y,
+    NegY,
+    #[default]
+    Z,
+    NegZ,
+}
+
+/// Handling of texture coordinates outside of 0-1 range in primitives using a clamped (ClampToEdge) texture
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, serde_derive::Deserialize)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum ClampedUvFix {
+    /// Only report a warning
+    #[default]
+    Warn,
+    /// Clamp texture coordinates to 0-1 range
+    Clamp,
+    /// Use wrapping texture mode instead
+    Wrap,
+}
+
+/// Named set of material settings for a common kind of surface. Every preset defines complete render state so the
+/// result does not depend on the glTF material. Settings from the materials override file take precedence over the
+/// preset.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, serde_derive::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaterialPreset {
+    /// Opaque surface
+    Default,
+    /// Alpha blended window glass with a weak reflection that does not write the Z-buffer
+    Glass,
+    /// Environment mapped chrome or polished metal
+    Chrome,
+    /// Self-illuminated additive surface (e.g. lamp glows, energy effects, holograms) that is not fogged
+    AdditiveGlow,
+    /// Two-sided alpha tested surface (e.g. leaves, grass, fences)
+    FoliageCutout,
+    /// Water surface: alpha blended, two-sided, does not write the Z-buffer and is fogged
+    Water,
+}
+
+impl MaterialPreset {
+    pub const ALL: [MaterialPreset; 6] = [
+        MaterialPreset::Default,
+        MaterialPreset::Glass,
+        MaterialPreset::Chrome,
+        MaterialPreset::AdditiveGlow,
+        MaterialPreset::FoliageCutout,
+        MaterialPreset::Water,
+    ];
+
+    /// Name used in material extras and the materials override file
+    pub fn name(self) -> &'static str {
+        match self {
+            MaterialPreset::Default => "default",
+            MaterialPreset::Glass => "glass",
+            MaterialPreset::Chrome => "chrome",
+            MaterialPreset::AdditiveGlow => "additive_glow",
+            MaterialPreset::FoliageCutout => "foliage_cutout",
+            MaterialPreset::Water => "water",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|p| p.name() == name)
+    }
+}
+
+/// RF material settings replacing values derived from a GLTF material. Fields that are not set keep converted values.
+#[derive(Clone, Debug, Default, PartialEq, serde_derive::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MaterialOverride {
+    /// Preset providing defaults of the other fields
+    pub preset: Option<MaterialPreset>,
+    /// Diffuse texture name
+    pub texture: Option<String>,
+    pub tex_src: Option<v3mc::TextureSource>,
+    pub color_op: Option<v3mc::ColorOp>,
+    pub alpha_op: Option<v3mc::AlphaOp>,
+    pub alpha_blend: Option<v3mc::AlphaBlend>,
+    pub zbuffer_type: Option<v3mc::ZbufferType>,
+    pub fog: Option<v3mc::FogType>,
+    pub self_illumination: Option<f32>,
+    pub specular_level: Option<f32>,
+    pub glossiness: Option<f32>,
+    pub reflection_amount: Option<f32>,
+    /// Environment (reflection) map texture name
+    pub refl_tex_name: Option<String>,
+    pub flags: Option<u32>,
+    /// Render faces from both sides
+    pub double_sided: Option<bool>,
+}
+
+/// Options controlling the conversion
+#[derive(Clone, Debug)]
+pub struct ConvertOptions {
+    /// Engine limits the output is validated against
+    pub limits: Limits,
+    /// Warning classes treated as errors. Empty list means all classes
+    pub strict: Option<Vec<WarningKind>>,
+    /// Texture names mapping (source image name to RF texture name)
+    pub texture_map: BTreeMap<String, String>,
+    /// Default animation weight used when it is not defined in bone extras
+    pub anim_weight: Option<f32>,
+    /// Default ramp in time in seconds used when it is not defined in bone extras
+    pub ramp_in_time: Option<f32>,
+    /// Default ramp out time in seconds used when it is not defined in bone extras
+    pub ramp_out_time: Option<f32>,
+    /// Triangle count ratios of LOD meshes generated for submeshes without LODs, e.g. `[0.5, 0.25]`
+    pub auto_lod: Vec<f32>,
+    /// Multiplier of automatically computed LOD distances (used for generated LODs and LOD meshes without
+    /// `LOD_distance` property)
+    pub lod_distance_scale: f32,
+    /// Suffix added to texture names used by less detailed LODs, e.g. `"_lod{lod}"` maps `wall.tga` to
+    /// `wall_lod1.tga` in LOD1. `{lod}` is replaced by the LOD index. `LOD_textures` node property takes precedence
+    pub lod_texture_suffix: Option<String>,
+    /// Node axis prop points point along
+    pub prop_axis: PropAxis,
+    /// Fix applied to texture coordinates outside of 0-1 range in primitives using a clamped texture
+    pub clamped_uv_fix: ClampedUvFix,
+    /// Generate solid color textures from base color factor for materials without base color texture instead of
+    /// using the default texture
+    pub color_textures: bool,
+    /// Bake occlusion texture into the base color texture. V3M has no vertex colors and no occlusion maps so ambient
+    /// occlusion can only be stored in the base color texture
+    pub bake_occlusion: bool,
+    /// Material settings overrides. Keys are GLTF material names or converted texture names (material name is checked
+    /// first)
+    pub material_overrides: BTreeMap<String, MaterialOverride>,
+    /// Store triangle planes in static meshes. Omitting them makes files smaller but the planes are needed by some
+    /// clients (for backface culling)
+    pub face_planes: bool,
+    /// Recompute normals from geometry: faces meeting at an angle bigger than this (in degrees) get hard edges,
+    /// smaller angles are smoothed. Normals from the input file are kept if not set
+    pub crease_angle: Option<f32>,
+    /// Texture used by all materials instead of their own textures (batches stay separate). Useful for testing
+    /// greybox models in game
+    pub force_texture: Option<String>,
+    /// Generate collision spheres from bone positions and lengths for characters without hand-made collision spheres
+    pub auto_cspheres: bool,
+}
+
+impl Default for ConvertOptions {
+    fn default() -> Self {
+        Self {
+            limits: Limits::STOCK,
+            strict: None,
+            texture_map: BTreeMap::new(),
+            anim_weight: None,
+            ramp_in_time: None,
+            ramp_out_time: None,
+            auto_lod: Vec::new(),
+            lod_distance_scale: 1.0,
+            lod_texture_suffix: None,
+            prop_axis: PropAxis::default(),
+            clamped_uv_fix: ClampedUvFix::default(),
+            color_textures: false,
+            bake_occlusion: false,
+            material_overrides: BTreeMap::new(),
+            face_planes: true,
+            crease_angle: None,
+            force_texture: None,
+            auto_cspheres: false,
+        }
+    }
+}
+
+/// Receives conversion progress notifications. All methods do nothing by default.
+///
+/// Conversion is split into stages: each stage is started with `start`, reports processed items with `advance` and
+/// ends with `finish`. See [`progress::ProgressCallback`] for a closure based implementation.
+///
+/// Listeners must be thread-safe because the conversion context owning them can be moved to and shared between
+/// threads.
+pub trait ProgressListener: Send + Sync {
+    fn start(&self, _label: &str, _total: usize) {}
+    fn advance(&self, _count: usize, _item: &str) {}
+    fn finish(&self) {}
+}
@@ -0,0 +1,69 @@
+use serde_derive::Deserialize;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    /// Limits of unmodified RF 1.2
+    Stock,
+    /// Limits of Dash Faction patched client
+    Dashfaction,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Limits {
+    pub max_vertices: usize,
+    pub max_indices: usize,
+    pub max_textures: usize,
+    pub max_bones: usize,
+}
+
+impl Limits {
+    pub const STOCK: Self = Self {
+        max_vertices: 6000 - 768,
+        max_indices: 10000 - 768,
+        max_textures: 7,
+        max_bones: 50,
+    };
+
+    // Dash Faction does not use fixed size vertex buffers so geometry is only limited by the file format:
+    // chunk allocation sizes are 16 bit so 12 bytes per vertex and 8 bytes per triangle must fit in u16
+    pub const DASH_FACTION: Self = Self {
+        max_vertices: 0xFFFF / 12,
+        max_indices: 0xFFFF / 8 * 3,
+        max_textures: 7,
+        max_bones: 50,
+    };
+
+    pub fn for_profile(profile: Profile) -> Self {
+        match profile {
+            Profile::Stock => Self::STOCK,
+            Profile::Dashfaction => Self::DASH_FACTION,
+        }
+    }
+
+    /// Applies comma separated overrides, e.g. "vertices=8000,textures=10"
+    pub fn apply_overrides(&mut self, overrides: &str) -> Result<(), String> {
+        for item in overrides
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+        {
+            let (key, value) = item
+                .split_once('=')
+                .ok_or_else(|| format!("expected key=value in limits: {}", item))?;
+            let value: usize = value
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid limit value: {}", item))?;
+            match key.trim() {
+                "vertices" => self.max_vertices = value,
+                "indices" => self.max_indices = value,
+                "textures" => self.max_textures = value,
+                "bones" => self.max_bones = value,
+                _ => return Err(format!("unknown limit: {}", key)),
+            }
+        }
+        Ok(())
+    }
+}
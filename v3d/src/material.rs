@@ -0,0 +1,753 @@
+use crate::diagnostics::describe_material;
+use crate::errors::ConvertError;
+use crate::math_utils::linear_to_srgb;
+use crate::names::to_ascii_name;
+use crate::rfg;
+use crate::v3mc;
+use crate::warnings::WarningKind;
+use crate::{
+    BakedTexture, ClampedUvFix, Context, ConvertResult, GeneratedTexture, MaterialOverride,
+    MaterialPreset, TextureImage,
+};
+use serde_derive::Deserialize;
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::f32;
+use std::path::Path;
+
+/// Allowed distance of clamped texture coordinates from 0-1 range (UV unwrapping is not perfectly exact)
+const CLAMPED_UV_TOLERANCE: f32 = 0.001;
+
+/// Checks texture coordinates of a primitive using a clamped texture. Coordinates outside of 0-1 range result in
+/// streaked texture edges in game. Returns texture source used for the primitive after applying the configured fix.
+fn check_clamped_uvs(location: &str, uvs: &mut [[f32; 2]], ctx: &Context) -> v3mc::TextureSource {
+    let range = -CLAMPED_UV_TOLERANCE..=1.0 + CLAMPED_UV_TOLERANCE;
+    let num_outside = uvs
+        .iter()
+        .filter(|uv| !uv.iter().all(|v| range.contains(v)))
+        .count();
+    if num_outside == 0 {
+        return v3mc::TextureSource::Clamp;
+    }
+    match ctx.options.clamped_uv_fix {
+        ClampedUvFix::Warn => {
+            ctx.warn(
+                WarningKind::ClampedUvRange,
+                &format!(
+                    "{}: {} texture coordinates are outside of 0-1 range but texture is clamped (ClampToEdge) - \
+                    texture edges will be streaked",
+                    location, num_outside
+                ),
+            );
+            v3mc::TextureSource::Clamp
+        }
+        ClampedUvFix::Clamp => {
+            for v in uvs.iter_mut().flatten() {
+                *v = v.clamp(0.0, 1.0);
+            }
+            ctx.record_fix(format!(
+                "{}: clamped {} texture coordinates to 0-1 range",
+                location, num_outside
+            ));
+            v3mc::TextureSource::Clamp
+        }
+        ClampedUvFix::Wrap => {
+            ctx.record_fix(format!(
+                "{}: used wrapping texture mode because texture coordinates are outside of 0-1 range",
+                location
+            ));
+            v3mc::TextureSource::Wrap
+        }
+    }
+}
+
+/// Checks if material is transmissive (KHR_materials_transmission), e.g. window glass. Such materials are converted to
+/// an alpha blended setup that does not write the Z-buffer so objects behind them stay visible.
+fn is_glass_material(material: &gltf::Material) -> bool {
+    material
+        .transmission()
+        .is_some_and(|t| t.transmission_factor() > 0.0 || t.transmission_texture().is_some())
+}
+
+/// Computes render mode of a primitive. Texture coordinates of the primitive are checked (and fixed if requested) when
+/// the texture is clamped.
+pub(crate) fn compute_render_mode_for_material(
+    material: &gltf::material::Material,
+    location: &str,
+    uvs: &mut [[f32; 2]],
+    ctx: &Context,
+) -> u32 {
+    // for example 0x400C41 (sofa1.v3m):
+    //   tex_src = 1, color_op = 2, alpha_op = 3, alpha_blend = 0, zbuffer_type = 5, fog = 0
+    // for example 0x518C41 (paper1.v3m, per1.v3m, ...):
+    //   tex_src = 1, color_op = 2, alpha_op = 3, alpha_blend = 3, zbuffer_type = 5, fog = 0
+    let overrides = get_material_settings(material, ctx).unwrap_or_default();
+    let mut tex_src = v3mc::TextureSource::Wrap;
+    if let Some(override_tex_src) = overrides.tex_src {
+        // sampler settings are not used so there is nothing to check
+        tex_src = override_tex_src;
+    } else if let Some(tex_info) = material.pbr_metallic_roughness().base_color_texture() {
+        use gltf::texture::WrappingMode;
+        let sampler = tex_info.texture().sampler();
+        if sampler.wrap_t() != sampler.wrap_s() {
+            ctx.warn(
+                WarningKind::WrapMode,
+                &format!(
+                    "{}: ignoring wrapT - wrapping mode must be the same for T and S",
+                    describe_material(material)
+                ),
+            );
+        }
+        if sampler.wrap_s() == WrappingMode::MirroredRepeat {
+            ctx.warn(
+                WarningKind::WrapMode,
+                &format!(
+                    "{}: MirroredRepeat wrapping mode is not supported",
+                    describe_material(material)
+                ),
+            );
+            ctx.record_fix(format!(
+                "{}: replaced MirroredRepeat wrapping mode with Repeat",
+                describe_material(material)
+            ));
+        }
+
+        tex_src = if sampler.wrap_s() == WrappingMode::ClampToEdge {
+            check_clamped_uvs(location, uvs, ctx)
+        } else {
+            v3mc::TextureSource::Wrap
+        };
+    }
+
+    let color_op = overrides.color_op.unwrap_or(v3mc::ColorOp::Mul);
+    let alpha_op = overrides.alpha_op.unwrap_or(v3mc::AlphaOp::Mul);
+
+    use gltf::material::AlphaMode;
+    let is_glass = is_glass_material(material);
+    let alpha_blend = overrides
+        .alpha_blend
+        .unwrap_or(match material.alpha_mode() {
+            _ if is_glass => v3mc::AlphaBlend::AlphaBlendAlpha,
+            AlphaMode::Blend => v3mc::AlphaBlend::AlphaBlendAlpha,
+            _ => v3mc::AlphaBlend::None,
+        });
+    let zbuffer_type = overrides
+        .zbuffer_type
+        .unwrap_or(match material.alpha_mode() {
+            _ if is_glass => v3mc::ZbufferType::Read,
+            AlphaMode::Opaque => v3mc::ZbufferType::Full,
+            _ => v3mc::ZbufferType::FullAlphaTest,
+        });
+    let fog = overrides.fog.unwrap_or(v3mc::FogType::Type0);
+    v3mc::encode_render_mode(tex_src, color_op, alpha_op, alpha_blend, zbuffer_type, fog)
+}
+
+fn change_texture_ext_to_tga(name: &str) -> String {
+    let path = Path::new(name).with_extension("tga");
+    // file name is missing if URI ends with ".." - keep it as is so the problem is visible in the output
+    path.file_name()
+        .map_or_else(|| name.to_string(), |n| n.to_string_lossy().into_owned())
+}
+
+/// Returns name of the solid color texture generated for a material, e.g. `Red_Plastic.tga`
+fn get_color_texture_name(material: &gltf::Material) -> String {
+    // leave room for the extension in the 32 bytes long V3M texture name field
+    const MAX_STEM_LEN: usize = 27;
+    let stem: String = match material.name() {
+        Some(name) => name
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .take(MAX_STEM_LEN)
+            .collect(),
+        None => format!("material{}", material.index().unwrap_or_default()),
+    };
+    format!("{}.tga", stem)
+}
+
+/// References a solid color texture filled with the material base color factor
+fn generate_color_texture(material: &gltf::Material, ctx: &Context) -> String {
+    let factor = material.pbr_metallic_roughness().base_color_factor();
+    let [r, g, b] = [0, 1, 2].map(|i| linear_to_srgb(factor[i].clamp(0.0, 1.0)));
+    let color = [r, g, b, factor[3].clamp(0.0, 1.0)].map(|c| (c * 255.0).round() as u8);
+    let name = get_color_texture_name(material);
+    ctx.record_fix(format!(
+        "{}: generated solid color texture {} from base color factor",
+        describe_material(material),
+        name
+    ));
+    ctx.add_generated_texture(GeneratedTexture {
+        name: name.clone(),
+        color,
+    });
+    name
+}
+
+/// Returns name of the base color texture with baked ambient occlusion, e.g. `wall_ao.tga`
+fn get_baked_texture_name(tex_name: &str) -> String {
+    // leave room for the suffix and the extension in the 32 bytes long V3M texture name field
+    const MAX_STEM_LEN: usize = 24;
+    let stem = Path::new(tex_name).file_stem().unwrap_or_default();
+    let stem: String = stem.to_string_lossy().chars().take(MAX_STEM_LEN).collect();
+    format!("{}_ao.tga", stem)
+}
+
+fn get_texture_image(texture: &gltf::Texture, ctx: &Context) -> Option<TextureImage> {
+    match texture.source().source() {
+        gltf::image::Source::Uri { uri, .. } if uri.starts_with("data:") => None,
+        gltf::image::Source::Uri { uri, .. } => Some(TextureImage::Uri(uri.to_owned())),
+        gltf::image::Source::View { view, .. } => {
+            let data = ctx.get_buffer_data(view.buffer())?;
+            let bytes = data.get(view.offset()..view.offset() + view.length())?;
+            Some(TextureImage::Data(bytes.to_vec()))
+        }
+    }
+}
+
+/// References the base color texture with ambient occlusion from the material occlusion texture baked in. Returns
+/// `None` if the material has no occlusion texture or it cannot be baked.
+fn bake_occlusion_texture(
+    material: &gltf::Material,
+    base_color_info: &gltf::texture::Info,
+    tex_name: &str,
+    ctx: &Context,
+) -> Option<String> {
+    let occlusion = material.occlusion_texture()?;
+    if occlusion.tex_coord() != base_color_info.tex_coord() {
+        ctx.warn(
+            WarningKind::UnsupportedTexture,
+            &format!(
+                "{}: occlusion texture is not baked because it uses different texture coordinates than the base \
+                 color texture",
+                describe_material(material)
+            ),
+        );
+        return None;
+    }
+    let base_color = get_texture_image(&base_color_info.texture(), ctx);
+    let occlusion_image = get_texture_image(&occlusion.texture(), ctx);
+    let (Some(base_color), Some(occlusion_image)) = (base_color, occlusion_image) else {
+        ctx.warn(
+            WarningKind::UnsupportedTexture,
+            &format!(
+                "{}: occlusion texture is not baked because images embedded as data URIs are not supported",
+                describe_material(material)
+            ),
+        );
+        return None;
+    };
+    let name = get_baked_texture_name(tex_name);
+    ctx.record_fix(format!(
+        "{}: baked occlusion texture into base color texture {}",
+        describe_material(material),
+        name
+    ));
+    ctx.add_baked_texture(BakedTexture {
+        name: name.clone(),
+        base_color,
+        occlusion: occlusion_image,
+        strength: occlusion.strength(),
+    });
+    Some(name)
+}
+
+/// Returns settings override for the material from the materials override file. Material name has priority over the
+/// texture name.
+fn get_material_override<'a>(
+    material: &gltf::Material,
+    ctx: &'a Context,
+) -> Option<&'a MaterialOverride> {
+    let overrides = &ctx.options.material_overrides;
+    if overrides.is_empty() {
+        return None;
+    }
+    material
+        .name()
+        .and_then(|name| overrides.get(name))
+        .or_else(|| {
+            // materials without texture would report the missing texture again
+            material.pbr_metallic_roughness().base_color_texture()?;
+            overrides.get(&resolve_base_color_texture_name(material, ctx))
+        })
+}
+
+/// Returns material settings with complete render state. Texture source is not included because it depends on texture
+/// coordinates of the primitive (see [`compute_render_mode_for_material`]).
+fn render_state_settings(
+    alpha_blend: v3mc::AlphaBlend,
+    zbuffer_type: v3mc::ZbufferType,
+    fog: v3mc::FogType,
+) -> MaterialOverride {
+    MaterialOverride {
+        color_op: Some(v3mc::ColorOp::Mul),
+        alpha_op: Some(v3mc::AlphaOp::Mul),
+        alpha_blend: Some(alpha_blend),
+        zbuffer_type: Some(zbuffer_type),
+        fog: Some(fog),
+        flags: Some(0x11),
+        ..Default::default()
+    }
+}
+
+/// Returns settings of a preset. Reflection map from material extras replaces the default environment map.
+fn get_preset_settings(preset: MaterialPreset, reflection_map: Option<String>) -> MaterialOverride {
+    use v3mc::{AlphaBlend, FogType, ZbufferType};
+    let refl_tex_name = reflection_map.unwrap_or_else(|| DEFAULT_REFLECTION_TEXTURE.into());
+    match preset {
+        MaterialPreset::Default => MaterialOverride {
+            reflection_amount: Some(0.0),
+            refl_tex_name: Some(String::new()),
+            ..render_state_settings(AlphaBlend::None, ZbufferType::Full, FogType::Type0)
+        },
+        MaterialPreset::Glass => MaterialOverride {
+            specular_level: Some(0.5),
+            glossiness: Some(1.0),
+            reflection_amount: Some(GLASS_REFLECTION_AMOUNT),
+            refl_tex_name: Some(refl_tex_name),
+            // objects behind the glass have to stay visible
+            ..render_state_settings(
+                AlphaBlend::AlphaBlendAlpha,
+                ZbufferType::Read,
+                FogType::Type0,
+            )
+        },
+        MaterialPreset::Chrome => MaterialOverride {
+            specular_level: Some(1.0),
+            glossiness: Some(1.0),
+            reflection_amount: Some(1.0),
+            refl_tex_name: Some(refl_tex_name),
+            ..render_state_settings(AlphaBlend::None, ZbufferType::Full, FogType::Type0)
+        },
+        MaterialPreset::AdditiveGlow => MaterialOverride {
+            self_illumination: Some(1.0),
+            reflection_amount: Some(0.0),
+            refl_tex_name: Some(String::new()),
+            // fog would brighten additive surfaces instead of hiding them
+            ..render_state_settings(
+                AlphaBlend::AlphaAdditive,
+                ZbufferType::Read,
+                FogType::ForceOff,
+            )
+        },
+        MaterialPreset::FoliageCutout => MaterialOverride {
+            double_sided: Some(true),
+            reflection_amount: Some(0.0),
+            refl_tex_name: Some(String::new()),
+            ..render_state_settings(AlphaBlend::None, ZbufferType::FullAlphaTest, FogType::Type0)
+        },
+        MaterialPreset::Water => MaterialOverride {
+            double_sided: Some(true),
+            specular_level: Some(0.5),
+            glossiness: Some(1.0),
+            reflection_amount: Some(0.5),
+            refl_tex_name: Some(refl_tex_name),
+            // surfaces below the water have to stay visible
+            ..render_state_settings(
+                AlphaBlend::AlphaBlendAlpha,
+                ZbufferType::Read,
+                FogType::Type0,
+            )
+        },
+    }
+}
+
+/// Fills fields that are not set in overrides with values from base settings
+fn merge_material_overrides(
+    overrides: MaterialOverride,
+    base: MaterialOverride,
+) -> MaterialOverride {
+    MaterialOverride {
+        preset: overrides.preset.or(base.preset),
+        texture: overrides.texture.or(base.texture),
+        tex_src: overrides.tex_src.or(base.tex_src),
+        color_op: overrides.color_op.or(base.color_op),
+        alpha_op: overrides.alpha_op.or(base.alpha_op),
+        alpha_blend: overrides.alpha_blend.or(base.alpha_blend),
+        zbuffer_type: overrides.zbuffer_type.or(base.zbuffer_type),
+        fog: overrides.fog.or(base.fog),
+        self_illumination: overrides.self_illumination.or(base.self_illumination),
+        specular_level: overrides.specular_level.or(base.specular_level),
+        glossiness: overrides.glossiness.or(base.glossiness),
+        reflection_amount: overrides.reflection_amount.or(base.reflection_amount),
+        refl_tex_name: overrides.refl_tex_name.or(base.refl_tex_name),
+        flags: overrides.flags.or(base.flags),
+        double_sided: overrides.double_sided.or(base.double_sided),
+    }
+}
+
+/// Returns preset of the material. Preset from the materials override file has priority over the one from material
+/// extras (unknown names in extras are reported by extras validation).
+pub(crate) fn get_material_preset(
+    material: &gltf::Material,
+    ctx: &Context,
+) -> Option<MaterialPreset> {
+    get_material_override(material, ctx)
+        .and_then(|o| o.preset)
+        .or_else(|| {
+            let extras: MaterialExtras = get_material_extras(material);
+            MaterialPreset::from_name(&extras.preset?)
+        })
+}
+
+/// Returns settings replacing values derived from the material: entry of the materials override file combined with
+/// the material preset
+fn get_material_settings(material: &gltf::Material, ctx: &Context) -> Option<MaterialOverride> {
+    let overrides = get_material_override(material, ctx).cloned();
+    let Some(preset) = get_material_preset(material, ctx) else {
+        return overrides;
+    };
+    let extras: MaterialExtras = get_material_extras(material);
+    let preset_settings = get_preset_settings(preset, extras.reflection_map);
+    Some(merge_material_overrides(
+        overrides.unwrap_or_default(),
+        preset_settings,
+    ))
+}
+
+/// Checks if faces using the material are rendered from both sides
+pub(crate) fn is_material_double_sided(material: &gltf::Material, ctx: &Context) -> bool {
+    get_material_settings(material, ctx)
+        .and_then(|o| o.double_sided)
+        .unwrap_or_else(|| material.double_sided())
+}
+
+pub(crate) fn get_material_base_color_texture_name(
+    material: &gltf::material::Material,
+    ctx: &Context,
+) -> String {
+    let name = if let Some(texture) = &ctx.options.force_texture {
+        texture.clone()
+    } else {
+        match get_material_settings(material, ctx).and_then(|o| o.texture) {
+            Some(texture) => texture,
+            None => resolve_base_color_texture_name(material, ctx),
+        }
+    };
+    to_ascii_name(&name, &describe_material(material), ctx)
+}
+
+fn resolve_base_color_texture_name(material: &gltf::material::Material, ctx: &Context) -> String {
+    const DEFAULT_TEXTURE: &str = "Rck_Default.tga";
+    if let Some(tex_info) = material.pbr_metallic_roughness().base_color_texture() {
+        let tex = tex_info.texture();
+        let img = tex.source();
+        let src_name = img.name().or(match img.source() {
+            gltf::image::Source::Uri { uri, .. } => Some(uri),
+            gltf::image::Source::View { .. } => None,
+        });
+        if let Some(src_name) = src_name {
+            if let Some(mapped_name) = ctx.options.texture_map.get(src_name) {
+                ctx.record_fix(format!(
+                    "{}: mapped texture '{}' to '{}'",
+                    describe_material(material),
+                    src_name,
+                    mapped_name
+                ));
+                return mapped_name.clone();
+            }
+            let tex_name = change_texture_ext_to_tga(src_name);
+            if tex_name != src_name {
+                ctx.record_fix(format!(
+                    "{}: renamed texture '{}' to '{}'",
+                    describe_material(material),
+                    src_name,
+                    tex_name
+                ));
+            }
+            if ctx.options.bake_occlusion {
+                if let Some(baked_name) =
+                    bake_occlusion_texture(material, &tex_info, &tex_name, ctx)
+                {
+                    return baked_name;
+                }
+            }
+            return tex_name;
+        }
+    } else if ctx.options.color_textures {
+        return generate_color_texture(material, ctx);
+    }
+    ctx.warn(
+        WarningKind::MissingTexture,
+        &format!(
+            "Cannot obtain texture name for {} (materials without base color texture are not supported)",
+            describe_material(material)
+        ),
+    );
+    ctx.record_fix(format!(
+        "{}: used default texture {}",
+        describe_material(material),
+        DEFAULT_TEXTURE
+    ));
+    DEFAULT_TEXTURE.into()
+}
+
+/// Reports materials with tinted base color. V3M materials have no color factor and meshes have no vertex colors so
+/// the tint has to be baked into the texture.
+fn check_base_color_factor(mat: &gltf::Material, ctx: &Context) {
+    const TOLERANCE: f32 = 1.0 / 255.0;
+    let pbr = mat.pbr_metallic_roughness();
+    if pbr.base_color_texture().is_none() && ctx.options.color_textures {
+        // factor is used as the generated texture color
+        return;
+    }
+    let [r, g, b, _] = pbr.base_color_factor();
+    if [r, g, b].iter().any(|c| (1.0 - c).abs() > TOLERANCE) {
+        ctx.warn(
+            WarningKind::BaseColorFactor,
+            &format!(
+                "{}: base color factor ({:.3}, {:.3}, {:.3}) is ignored - bake the tint into the base color texture",
+                describe_material(mat),
+                r,
+                g,
+                b
+            ),
+        );
+    }
+}
+
+fn get_material_self_illumination(mat: &gltf::Material) -> f32 {
+    mat.emissive_factor().iter().copied().fold(0_f32, f32::max)
+}
+
+/// Environment map used by reflective materials. RF PC ignores it but other tools display it.
+const DEFAULT_REFLECTION_TEXTURE: &str = "envmap.tga";
+
+/// Reflection coefficient of perfectly smooth glass (rough, frosted glass reflects less)
+const GLASS_REFLECTION_AMOUNT: f32 = 0.3;
+
+/// Derives reflection coefficient from PBR properties: smooth metals reflect the environment, rough or dielectric
+/// surfaces barely do. Glass is dielectric but it is mostly seen because of its reflections.
+fn get_material_reflection_amount(specular_level: f32, glossiness: f32, is_glass: bool) -> f32 {
+    let amount = specular_level * glossiness;
+    let amount = if is_glass {
+        amount.max(GLASS_REFLECTION_AMOUNT * glossiness)
+    } else {
+        amount
+    };
+    amount.clamp(0.0, 1.0)
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct MaterialExtras {
+    /// Material preset name (see [`MaterialPreset`])
+    #[serde(rename = "RF_preset", alias = "rf_preset")]
+    preset: Option<String>,
+    /// Environment map used by the preset instead of the default one
+    #[serde(rename = "RF_reflection_map")]
+    reflection_map: Option<String>,
+    /// Texture coordinates change per second (e.g. `[0.0, 0.5]`)
+    #[serde(rename = "RF_uv_scroll")]
+    uv_scroll: Option<[f32; 2]>,
+    /// Sky is rendered in place of faces using the material (level geometry only)
+    #[serde(rename = "RF_show_sky", default)]
+    show_sky: bool,
+    /// Faces using the material are portals between rooms (level geometry only)
+    #[serde(rename = "RF_portal", default)]
+    portal: bool,
+}
+
+fn get_material_extras<'a, T: serde::Deserialize<'a> + Default>(material: &'a gltf::Material) -> T {
+    material
+        .extras()
+        .as_ref()
+        .and_then(|raw| serde_json::from_str::<T>(raw.get()).ok())
+        .unwrap_or_default()
+}
+
+/// Returns texture scrolling velocity from material extras
+pub(crate) fn get_material_uv_scroll(material: &gltf::Material) -> Option<[f32; 2]> {
+    let extras: MaterialExtras = get_material_extras(material);
+    extras.uv_scroll.filter(|v| v.iter().any(|&c| c != 0.0))
+}
+
+/// Returns flags and portal setting of level geometry faces using the material
+pub(crate) fn get_material_face_flags(material: &gltf::Material) -> (u16, bool) {
+    let extras: MaterialExtras = get_material_extras(material);
+    let flags = if extras.show_sky {
+        rfg::Face::SHOW_SKY
+    } else {
+        0
+    };
+    (flags, extras.portal)
+}
+
+/// Reports sky and portal materials used by meshes. Both are properties of level geometry faces.
+fn check_face_flags(mat: &gltf::Material, ctx: &Context) {
+    let (flags, is_portal) = get_material_face_flags(mat);
+    if flags != 0 || is_portal {
+        ctx.warn(
+            WarningKind::UnsupportedMaterial,
+            &format!(
+                "{}: sky and portal faces are ignored - they are supported only by level geometry (RFG and RFL \
+                export)",
+                describe_material(mat)
+            ),
+        );
+    }
+}
+
+/// Reports UV scrolling of mesh materials. Only level geometry (brushes) can have scrolling textures.
+fn check_uv_scroll(mat: &gltf::Material, ctx: &Context) {
+    if get_material_uv_scroll(mat).is_some() {
+        ctx.warn(
+            WarningKind::UnsupportedMaterial,
+            &format!(
+                "{}: UV scrolling is ignored - it is supported only by level geometry (RFG and RFL export)",
+                describe_material(mat)
+            ),
+        );
+    }
+}
+
+/// Reports transmission textures. RF has no transmission - opacity of glass comes from the base color texture alpha.
+fn check_transmission_texture(mat: &gltf::Material, ctx: &Context) {
+    if mat
+        .transmission()
+        .is_some_and(|t| t.transmission_texture().is_some())
+    {
+        ctx.warn(
+            WarningKind::UnsupportedTexture,
+            &format!(
+                "{}: transmission texture is ignored - store opacity in the base color texture alpha channel instead",
+                describe_material(mat)
+            ),
+        );
+    }
+}
+
+pub(crate) fn convert_material(mat: &gltf::Material, ctx: &Context) -> v3mc::Material {
+    let tex_name = get_material_base_color_texture_name(mat, ctx);
+    // V3M has no vertex colors so occlusion can only be baked into the base color texture
+    if mat.occlusion_texture().is_some() && !ctx.options.bake_occlusion {
+        ctx.warn(
+            WarningKind::UnsupportedTexture,
+            &format!(
+                "{}: occlusion texture is ignored - enable occlusion baking to bake it into the base color texture",
+                describe_material(mat)
+            ),
+        );
+    }
+    check_base_color_factor(mat, ctx);
+    check_transmission_texture(mat, ctx);
+    check_uv_scroll(mat, ctx);
+    check_face_flags(mat, ctx);
+    let self_illumination = get_material_self_illumination(mat);
+    let specular_level = mat.pbr_specular_glossiness().map_or_else(
+        || mat.pbr_metallic_roughness().metallic_factor(),
+        |spec_glos| {
+            spec_glos
+                .specular_factor()
+                .iter()
+                .copied()
+                .fold(0_f32, f32::max)
+        },
+    );
+    let glossiness = mat.pbr_specular_glossiness().map_or_else(
+        || 1.0 - mat.pbr_metallic_roughness().roughness_factor(),
+        |spec_glos| spec_glos.glossiness_factor(),
+    );
+    let reflection_amount =
+        get_material_reflection_amount(specular_level, glossiness, is_glass_material(mat));
+    let refl_tex_name = if reflection_amount > 0.0 {
+        DEFAULT_REFLECTION_TEXTURE.to_string()
+    } else {
+        String::new()
+    };
+
+    let material = v3mc::Material {
+        tex_name,
+        self_illumination,
+        specular_level,
+        glossiness,
+        reflection_amount,
+        refl_tex_name,
+        flags: 0x11,
+    };
+    match get_material_settings(mat, ctx) {
+        Some(overrides) => apply_material_override(material, &overrides),
+        None => material,
+    }
+}
+
+fn apply_material_override(
+    material: v3mc::Material,
+    overrides: &MaterialOverride,
+) -> v3mc::Material {
+    v3mc::Material {
+        tex_name: overrides.texture.clone().unwrap_or(material.tex_name),
+        self_illumination: overrides
+            .self_illumination
+            .unwrap_or(material.self_illumination),
+        specular_level: overrides.specular_level.unwrap_or(material.specular_level),
+        glossiness: overrides.glossiness.unwrap_or(material.glossiness),
+        reflection_amount: overrides
+            .reflection_amount
+            .unwrap_or(material.reflection_amount),
+        refl_tex_name: overrides
+            .refl_tex_name
+            .clone()
+            .unwrap_or(material.refl_tex_name),
+        flags: overrides.flags.unwrap_or(material.flags),
+    }
+}
+
+/// Texture names used by a single LOD mesh
+pub(crate) struct LodTextures<'a> {
+    /// Index of the LOD mesh (0 is the most detailed one)
+    pub lod_index: usize,
+    /// Texture names mapping from `LOD_textures` node property (full resolution name to LOD texture name)
+    pub overrides: &'a BTreeMap<String, String>,
+}
+
+impl LodTextures<'_> {
+    /// Returns name of the texture used by the LOD instead of the full resolution texture. Mapping from node
+    /// property takes precedence over the suffix naming convention (see [`crate::ConvertOptions::lod_texture_suffix`])
+    fn get_texture_name(&self, tex_name: String, ctx: &Context) -> String {
+        if ctx.options.force_texture.is_some() {
+            return tex_name;
+        }
+        if let Some(lod_tex_name) = self.overrides.get(&tex_name) {
+            return lod_tex_name.clone();
+        }
+        match &ctx.options.lod_texture_suffix {
+            Some(suffix) if self.lod_index > 0 => {
+                let path = Path::new(&tex_name);
+                let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+                let suffix = suffix.replace("{lod}", &self.lod_index.to_string());
+                match path.extension() {
+                    Some(ext) => format!("{}{}.{}", stem, suffix, ext.to_string_lossy()),
+                    None => format!("{}{}", stem, suffix),
+                }
+            }
+            _ => tex_name,
+        }
+    }
+}
+
+pub(crate) fn create_mesh_material_ref(
+    material: &gltf::Material,
+    lod_mesh_materials: &[gltf::Material],
+    lod_textures: &LodTextures,
+    ctx: &Context,
+) -> ConvertResult<v3mc::MeshTextureRef> {
+    let material_index = lod_mesh_materials
+        .iter()
+        .position(|m| m.index() == material.index())
+        .and_then(|i| i.try_into().ok())
+        .ok_or_else(|| {
+            ConvertError::InvalidInput(format!(
+                "{}: too many materials in LOD group",
+                describe_material(material)
+            ))
+        })?;
+    Ok(v3mc::MeshTextureRef {
+        material_index,
+        tex_name: lod_textures
+            .get_texture_name(get_material_base_color_texture_name(material, ctx), ctx),
+    })
+}
@@ -3,6 +3,15 @@ pub(crate) type Plane = [f32; 4];
 pub(crate) type Matrix4 = [[f32; 4]; 4];
 pub(crate) type Matrix3 = [[f32; 3]; 3];
 
+/// Converts linear color component to sRGB
+pub(crate) fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
 pub(crate) fn get_vector_len(vec: &Vector3) -> f32 {
     vec.iter().map(|v| v * v).sum::<f32>().sqrt()
 }
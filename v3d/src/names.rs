@@ -0,0 +1,192 @@
+//! Sanitization of names stored in fixed size fields of RF files.
+//!
+//! Submesh and bone names are limited to 23 bytes and the game looks them up by name (ignoring letter case) so too
+//! long names are truncated and duplicates get a numeric suffix. Every rename is reported as a warning so it can be turned
+//! into an error in strict mode.
+//!
+//! RF handles names as single byte strings so non-ASCII characters are transliterated (e.g. `é` becomes `e`) or
+//! replaced with underscores.
+
+use crate::diagnostics::describe_node;
+use crate::warnings::WarningKind;
+use crate::Context;
+
+/// Maximal length of names stored in 24 bytes long fields (the last byte is the terminating zero)
+pub(crate) const MAX_NAME_LEN: usize = 23;
+
+/// Name used for nodes without a name
+const DEFAULT_NAME: &str = "Default";
+
+/// Returns ASCII replacement of a character (`None` if the character has no obvious Latin equivalent)
+fn transliterate_char(c: char) -> Option<&'static str> {
+    let s = match c {
+        'À'..='Å' | 'Ā' | 'Ă' | 'Ą' => "A",
+        'à'..='å' | 'ā' | 'ă' | 'ą' => "a",
+        'Æ' => "AE",
+        'æ' => "ae",
+        'Ç' | 'Ć' | 'Ĉ' | 'Ċ' | 'Č' => "C",
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+        'Ď' | 'Đ' | 'Ð' => "D",
+        'ď' | 'đ' | 'ð' => "d",
+        'È'..='Ë' | 'Ē' | 'Ĕ' | 'Ė' | 'Ę' | 'Ě' => "E",
+        'è'..='ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+        'Ĝ' | 'Ğ' | 'Ġ' | 'Ģ' => "G",
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+        'Ĥ' | 'Ħ' => "H",
+        'ĥ' | 'ħ' => "h",
+        'Ì'..='Ï' | 'Ĩ' | 'Ī' | 'Ĭ' | 'Į' | 'İ' => "I",
+        'ì'..='ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+        'Ĵ' => "J",
+        'ĵ' => "j",
+        'Ķ' => "K",
+        'ķ' => "k",
+        'Ĺ' | 'Ļ' | 'Ľ' | 'Ŀ' | 'Ł' => "L",
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+        'Ñ' | 'Ń' | 'Ņ' | 'Ň' => "N",
+        'ñ' | 'ń' | 'ņ' | 'ň' => "n",
+        'Ò'..='Ö' | 'Ø' | 'Ō' | 'Ŏ' | 'Ő' => "O",
+        'ò'..='ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+        'Œ' => "OE",
+        'œ' => "oe",
+        'Ŕ' | 'Ŗ' | 'Ř' => "R",
+        'ŕ' | 'ŗ' | 'ř' => "r",
+        'Ś' | 'Ŝ' | 'Ş' | 'Š' => "S",
+        'ś' | 'ŝ' | 'ş' | 'š' => "s",
+        'ß' => "ss",
+        'Ţ' | 'Ť' | 'Ŧ' => "T",
+        'ţ' | 'ť' | 'ŧ' => "t",
+        'Þ' => "TH",
+        'þ' => "th",
+        'Ù'..='Ü' | 'Ũ' | 'Ū' | 'Ŭ' | 'Ů' | 'Ű' | 'Ų' => "U",
+        'ù'..='ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+        'Ŵ' => "W",
+        'ŵ' => "w",
+        'Ý' | 'Ŷ' | 'Ÿ' => "Y",
+        'ý' | 'ŷ' | 'ÿ' => "y",
+        'Ź' | 'Ż' | 'Ž' => "Z",
+        'ź' | 'ż' | 'ž' => "z",
+        _ => return None,
+    };
+    Some(s)
+}
+
+/// Converts name to ASCII. Characters without ASCII equivalent are replaced with underscores. `what` describes the
+/// owner of the name in the warning, e.g. `node 'Dveře'`.
+pub(crate) fn to_ascii_name(name: &str, what: &str, ctx: &Context) -> String {
+    if name.is_ascii() {
+        return name.to_string();
+    }
+    let mut ascii = String::with_capacity(name.len());
+    for c in name.chars() {
+        if c.is_ascii() {
+            ascii.push(c);
+        } else {
+            ascii.push_str(transliterate_char(c).unwrap_or("_"));
+        }
+    }
+    ctx.warn(
+        WarningKind::NonAsciiName,
+        &format!(
+            "{}: name '{}' contains non-ASCII characters - changed to '{}'",
+            what, name, ascii
+        ),
+    );
+    ascii
+}
+
+/// Returns the longest prefix of the name that fits in `max_len` bytes without splitting a character
+pub(crate) fn truncate_name(name: &str, max_len: usize) -> &str {
+    let mut end = name.len().min(max_len);
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    &name[..end]
+}
+
+/// Appends the smallest numeric suffix (`_2`, `_3`, ...) that makes the name unique, shortening the name if needed
+fn make_unique_name(name: &str, used: &[String]) -> String {
+    let is_used = |candidate: &str| used.iter().any(|u| u.eq_ignore_ascii_case(candidate));
+    if !is_used(name) {
+        return name.to_string();
+    }
+    (2..)
+        .map(|i| {
+            let suffix = format!("_{}", i);
+            format!(
+                "{}{}",
+                truncate_name(name, MAX_NAME_LEN - suffix.len()),
+                suffix
+            )
+        })
+        .find(|candidate| !is_used(candidate))
+        .unwrap()
+}
+
+/// Returns names of given nodes truncated to the engine limit and made unique. Unnamed nodes get a name from
+/// `default_name` (called with the node position). Renames are reported as `kind` warnings, `owner` describes what
+/// the names are used for (e.g. `submesh`).
+fn assign_unique_names<'a>(
+    nodes: impl IntoIterator<Item = gltf::Node<'a>>,
+    default_name: impl Fn(usize) -> String,
+    kind: WarningKind,
+    owner: &str,
+    ctx: &Context,
+) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for (i, node) in nodes.into_iter().enumerate() {
+        let original = to_ascii_name(
+            &node.name().map_or_else(|| default_name(i), str::to_owned),
+            &describe_node(&node),
+            ctx,
+        );
+        let truncated = truncate_name(&original, MAX_NAME_LEN);
+        if truncated != original {
+            ctx.warn(
+                kind,
+                &format!(
+                    "{}: name is too long (max {} characters) - truncated to '{}'",
+                    describe_node(&node),
+                    MAX_NAME_LEN,
+                    truncated
+                ),
+            );
+        }
+        let unique = make_unique_name(truncated, &names);
+        if unique != truncated {
+            ctx.warn(
+                kind,
+                &format!(
+                    "{}: name '{}' is used by another {} - renamed to '{}'",
+                    describe_node(&node),
+                    truncated,
+                    owner,
+                    unique
+                ),
+            );
+        }
+        names.push(unique);
+    }
+    names
+}
+
+/// Returns names of submeshes created from given nodes. Names are truncated to the engine limit and made unique.
+pub(crate) fn assign_submesh_names(nodes: &[gltf::Node], ctx: &Context) -> Vec<String> {
+    assign_unique_names(
+        nodes.iter().cloned(),
+        |_| DEFAULT_NAME.to_owned(),
+        WarningKind::SubmeshName,
+        "submesh",
+        ctx,
+    )
+}
+
+/// Returns names of bones created from skin joints (in joint order). Unnamed joints are named after their index.
+pub(crate) fn assign_bone_names(skin: &gltf::Skin, ctx: &Context) -> Vec<String> {
+    assign_unique_names(
+        skin.joints(),
+        |i| format!("bone_{}", i),
+        WarningKind::BoneName,
+        "bone",
+        ctx,
+    )
+}
@@ -0,0 +1,86 @@
+//! Progress reporting for applications embedding the library.
+//!
+//! Converters report progress through [`ProgressListener`]. [`ProgressCallback`] adapts a closure receiving the
+//! current stage and completion percentage, which is usually all a GUI needs to update its progress bar.
+
+use crate::ProgressListener;
+use std::sync::{Mutex, PoisonError};
+
+/// Progress of the current conversion stage
+#[derive(Clone, Copy, Debug)]
+pub struct ProgressUpdate<'a> {
+    /// Stage name, e.g. "Converting primitives"
+    pub stage: &'a str,
+    /// Item that was just processed (empty when a stage starts or finishes)
+    pub item: &'a str,
+    pub done: usize,
+    pub total: usize,
+}
+
+impl ProgressUpdate<'_> {
+    /// Returns completion of the current stage in percent (0-100)
+    pub fn percentage(&self) -> f32 {
+        if self.total == 0 {
+            100.0
+        } else {
+            self.done as f32 * 100.0 / self.total as f32
+        }
+    }
+}
+
+#[derive(Default)]
+struct StageState {
+    stage: String,
+    done: usize,
+    total: usize,
+}
+
+/// Calls a closure every time conversion progress changes
+pub struct ProgressCallback<F> {
+    callback: F,
+    state: Mutex<StageState>,
+}
+
+impl<F: Fn(&ProgressUpdate) + Send + Sync> ProgressCallback<F> {
+    pub fn new(callback: F) -> Self {
+        Self {
+            callback,
+            state: Mutex::default(),
+        }
+    }
+
+    fn notify(&self, state: &StageState, item: &str) {
+        (self.callback)(&ProgressUpdate {
+            stage: &state.stage,
+            item,
+            done: state.done,
+            total: state.total,
+        });
+    }
+}
+
+impl<F: Fn(&ProgressUpdate) + Send + Sync> ProgressListener for ProgressCallback<F> {
+    fn start(&self, label: &str, total: usize) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        *state = StageState {
+            stage: label.to_owned(),
+            done: 0,
+            total,
+        };
+        self.notify(&state, "");
+    }
+
+    fn advance(&self, count: usize, item: &str) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.done = (state.done + count).min(state.total);
+        self.notify(&state, item);
+    }
+
+    fn finish(&self) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.done < state.total {
+            state.done = state.total;
+            self.notify(&state, "");
+        }
+    }
+}
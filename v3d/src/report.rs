@@ -0,0 +1,216 @@
+use crate::{rfg, v3mc};
+use binrw::BinWrite;
+use serde_derive::Serialize;
+use std::io::{Cursor, Write};
+use std::mem::size_of;
+use std::path::Path;
+
+/// Number of bytes used by each part of a converted submesh (all LODs together) in the output file
+#[derive(Serialize, Debug, Default, Clone, Copy)]
+pub struct SizeBreakdown {
+    pub positions: usize,
+    pub normals: usize,
+    pub uvs: usize,
+    pub indices: usize,
+    /// Triangle planes (static meshes only, see `--no-face-planes`)
+    pub planes: usize,
+    /// Bone weights and indices (character meshes only)
+    pub bone_links: usize,
+    /// Materials and texture references
+    pub materials: usize,
+    pub prop_points: usize,
+    /// Headers, alignment padding and other small structures
+    pub other: usize,
+    pub total: usize,
+}
+
+impl SizeBreakdown {
+    pub fn from_lod_mesh(lod_mesh: &v3mc::LodMesh) -> Self {
+        // sizes of serialized structures
+        const MATERIAL_SIZE: usize = 32 + 4 * 4 + 32 + 4;
+        const PROP_POINT_SIZE: usize = 0x44 + 4 * 4 + 3 * 4 + 4;
+        let mut sizes = Self {
+            materials: lod_mesh.materials.len() * MATERIAL_SIZE,
+            ..Self::default()
+        };
+        for mesh in &lod_mesh.meshes {
+            for data in &mesh.data_block.chunks_data {
+                sizes.positions += data.vecs.len() * size_of::<[f32; 3]>();
+                sizes.normals += data.norms.len() * size_of::<[f32; 3]>();
+                sizes.uvs += data.uvs.len() * size_of::<[f32; 2]>();
+                sizes.indices += data.faces.len() * size_of::<[u16; 4]>();
+                sizes.planes += data.face_planes.len() * size_of::<[f32; 4]>();
+                sizes.bone_links += data.wi.len() * size_of::<[u8; 8]>();
+            }
+            // texture index and null terminated name
+            sizes.materials += mesh
+                .textures
+                .iter()
+                .map(|t| 1 + t.tex_name.len() + 1)
+                .sum::<usize>();
+            sizes.prop_points += mesh.data_block.prop_points.len() * PROP_POINT_SIZE;
+        }
+        let sections = sizes.positions
+            + sizes.normals
+            + sizes.uvs
+            + sizes.indices
+            + sizes.planes
+            + sizes.bone_links
+            + sizes.materials
+            + sizes.prop_points;
+        // serialize the submesh to get headers and padding right
+        let mut wrt = Cursor::new(Vec::new());
+        sizes.total = match lod_mesh.write_le(&mut wrt) {
+            Ok(()) => wrt.get_ref().len().max(sections),
+            Err(_) => sections,
+        };
+        sizes.other = sizes.total - sections;
+        sizes
+    }
+}
+
+/// Conversion summary of a single node
+#[derive(Serialize, Debug, Default)]
+pub struct NodeReport {
+    pub name: String,
+    pub lods: usize,
+    /// Vertex count of the most detailed LOD
+    pub vertices: usize,
+    /// Triangle count of the most detailed LOD
+    pub triangles: usize,
+    /// Batch (chunk) count of the most detailed LOD
+    pub batches: usize,
+    pub textures: Vec<String>,
+    pub radius: f32,
+    /// Automatic corrections applied to the input (e.g. generated texture coordinates)
+    pub fixes: Vec<String>,
+    /// Output size of the node split by data type (V3M/V3C only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sizes: Option<SizeBreakdown>,
+}
+
+impl NodeReport {
+    pub fn from_lod_mesh(lod_mesh: &v3mc::LodMesh) -> Self {
+        let (vertices, triangles, batches) = lod_mesh.meshes.first().map_or((0, 0, 0), |mesh| {
+            (
+                mesh.num_vecs as usize,
+                mesh.chunks.iter().map(|c| c.num_faces as usize).sum(),
+                mesh.chunks.len(),
+            )
+        });
+        // less detailed LODs can use different (lower resolution) textures
+        let mut textures: Vec<String> = lod_mesh
+            .materials
+            .iter()
+            .map(|m| m.tex_name.clone())
+            .collect();
+        for tex_ref in lod_mesh.meshes.iter().flat_map(|m| &m.textures) {
+            if !textures.contains(&tex_ref.tex_name) {
+                textures.push(tex_ref.tex_name.clone());
+            }
+        }
+        Self {
+            name: lod_mesh.name.clone(),
+            lods: lod_mesh.meshes.len(),
+            vertices,
+            triangles,
+            batches,
+            textures,
+            radius: lod_mesh.radius,
+            fixes: Vec::new(),
+            sizes: Some(SizeBreakdown::from_lod_mesh(lod_mesh)),
+        }
+    }
+
+    pub fn from_brush(name: &str, brush: &rfg::Brush) -> Self {
+        let solid = &brush.solid;
+        let radius = solid
+            .vertices
+            .iter()
+            .map(|v| (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt())
+            .fold(0_f32, f32::max);
+        Self {
+            name: name.to_owned(),
+            lods: 1,
+            vertices: solid.vertices.len(),
+            // faces are convex polygons triangulated into triangle fans
+            triangles: solid
+                .faces
+                .iter()
+                .map(|f| f.vertices.len().saturating_sub(2))
+                .sum(),
+            batches: solid.textures.len(),
+            textures: solid.textures.clone(),
+            radius,
+            fixes: Vec::new(),
+            sizes: None,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct Report<'a> {
+    input: &'a Path,
+    nodes: &'a [NodeReport],
+}
+
+pub fn print_table<W: Write>(
+    nodes: &[NodeReport],
+    input_file: &Path,
+    wrt: &mut W,
+) -> std::io::Result<()> {
+    writeln!(wrt, "Conversion report for {}:", input_file.display())?;
+    let name_width = nodes.iter().map(|n| n.name.len()).max().unwrap_or(0).max(4);
+    writeln!(
+        wrt,
+        "  {:name_width$}  {:>4}  {:>8}  {:>9}  {:>7}  {:>8}  Textures",
+        "Node", "LODs", "Vertices", "Triangles", "Batches", "Radius",
+    )?;
+    for node in nodes {
+        writeln!(
+            wrt,
+            "  {:name_width$}  {:>4}  {:>8}  {:>9}  {:>7}  {:>8.2}  {}",
+            node.name,
+            node.lods,
+            node.vertices,
+            node.triangles,
+            node.batches,
+            node.radius,
+            node.textures.join(", "),
+        )?;
+        if let Some(s) = &node.sizes {
+            writeln!(
+                wrt,
+                "    size: {} bytes (positions {}, normals {}, UVs {}, indices {}, planes {}, bone links {}, \
+                materials {}, prop points {}, other {})",
+                s.total,
+                s.positions,
+                s.normals,
+                s.uvs,
+                s.indices,
+                s.planes,
+                s.bone_links,
+                s.materials,
+                s.prop_points,
+                s.other
+            )?;
+        }
+        for fix in &node.fixes {
+            writeln!(wrt, "    fix: {}", fix)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn write_json(
+    nodes: &[NodeReport],
+    input_file: &Path,
+    report_file: &Path,
+) -> std::io::Result<()> {
+    let report = Report {
+        input: input_file,
+        nodes,
+    };
+    let json = serde_json::to_string_pretty(&report)?;
+    std::fs::write(report_file, json + "\n")
+}
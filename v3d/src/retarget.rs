@@ -0,0 +1,551 @@
+//! Retargeting of animations authored on a different skeleton (e.g. a Mixamo rig) onto RF character skeleton.
+//!
+//! Bone rotations are transferred as world space rotation changes relative to the rest pose of the source skeleton,
+//! so mapped bones do not need to have the same local axes or bone lengths. Both rest poses should have the same
+//! posture (e.g. T-pose). Translation is transferred only to the root bone and scaled by the ratio of root heights.
+
+use crate::char_anim;
+use crate::errors::ConvertError;
+use crate::warnings::WarningKind;
+use crate::{gltf_to_rf_quat, gltf_to_rf_vec, rfa, Context, ConvertResult};
+use glam::{Mat4, Quat, Vec3};
+use gltf::animation::util::ReadOutputs;
+use gltf::animation::Interpolation;
+use serde_derive::Deserialize;
+use std::collections::BTreeMap;
+
+/// Rate of sampling source animations (RF animations run at 30 FPS)
+const SAMPLES_PER_SEC: f32 = 30.0_f32;
+
+/// Root heights smaller than this are not used for scaling root translation
+const MIN_ROOT_HEIGHT: f32 = 0.0001_f32;
+
+/// Mapping of RF skeleton bones to bones of the skeleton animations were authored on
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct BoneMap {
+    /// RF bone that receives root motion (translation). By default the mapped bone closest to the skeleton root is
+    /// used
+    pub root: Option<String>,
+    /// RF bone name -> source bone name
+    pub bones: BTreeMap<String, String>,
+}
+
+/// Keyframes of an animated node property. Cubic spline tangents are ignored like in the regular animation export.
+struct Track<T> {
+    times: Vec<f32>,
+    values: Vec<T>,
+    interpolation: Interpolation,
+}
+
+impl<T: Copy> Track<T> {
+    /// Returns `None` if there are no keyframes or number of values does not match number of keyframes
+    fn new(times: Vec<f32>, values: Vec<T>, interpolation: Interpolation) -> Option<Self> {
+        let values: Vec<T> = if interpolation == Interpolation::CubicSpline {
+            values.chunks_exact(3).map(|s| s[1]).collect()
+        } else {
+            values
+        };
+        (!times.is_empty() && times.len() == values.len()).then_some(Self {
+            times,
+            values,
+            interpolation,
+        })
+    }
+
+    fn sample(&self, time: f32, lerp: impl Fn(T, T, f32) -> T) -> T {
+        let next = self.times.partition_point(|&t| t <= time);
+        if next == 0 {
+            return self.values[0];
+        }
+        if next >= self.values.len() {
+            return self.values[self.values.len() - 1];
+        }
+        let prev = next - 1;
+        if self.interpolation == Interpolation::Step {
+            return self.values[prev];
+        }
+        let factor = (time - self.times[prev]) / (self.times[next] - self.times[prev]);
+        lerp(self.values[prev], self.values[next], factor)
+    }
+}
+
+#[derive(Default)]
+struct NodeTracks {
+    rotation: Option<Track<Quat>>,
+    translation: Option<Track<Vec3>>,
+}
+
+/// Local transformation of a node
+#[derive(Clone, Copy)]
+struct Pose {
+    translation: Vec3,
+    rotation: Quat,
+    scale: Vec3,
+}
+
+impl Pose {
+    fn rest(node: &gltf::Node) -> Self {
+        let (translation, rotation, scale) = node.transform().decomposed();
+        Self {
+            translation: Vec3::from(translation),
+            rotation: Quat::from_array(rotation),
+            scale: Vec3::from(scale),
+        }
+    }
+
+    fn matrix(&self) -> Mat4 {
+        Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+}
+
+/// World space rotation and transformation of every node of a document
+struct GlobalPoses {
+    rotations: Vec<Quat>,
+    matrices: Vec<Mat4>,
+}
+
+/// Node hierarchy of a document
+struct Hierarchy {
+    parents: Vec<Option<usize>>,
+    /// Node indices ordered so parents come before their children
+    order: Vec<usize>,
+}
+
+impl Hierarchy {
+    fn new(doc: &gltf::Document) -> Self {
+        let mut parents = vec![None; doc.nodes().len()];
+        for node in doc.nodes() {
+            for child in node.children() {
+                parents[child.index()] = Some(node.index());
+            }
+        }
+        let depth = |mut index: usize| {
+            let mut depth = 0;
+            while let Some(parent) = parents[index] {
+                index = parent;
+                depth += 1;
+            }
+            depth
+        };
+        let mut order: Vec<usize> = (0..parents.len()).collect();
+        order.sort_by_key(|&index| depth(index));
+        Self { parents, order }
+    }
+
+    fn global_poses(&self, local_poses: &[Pose]) -> GlobalPoses {
+        let mut rotations = vec![Quat::IDENTITY; local_poses.len()];
+        let mut matrices = vec![Mat4::IDENTITY; local_poses.len()];
+        for &index in &self.order {
+            let (parent_rotation, parent_matrix) = self.parents[index]
+                .map_or((Quat::IDENTITY, Mat4::IDENTITY), |p| {
+                    (rotations[p], matrices[p])
+                });
+            rotations[index] = parent_rotation * local_poses[index].rotation;
+            matrices[index] = parent_matrix * local_poses[index].matrix();
+        }
+        GlobalPoses {
+            rotations,
+            matrices,
+        }
+    }
+}
+
+fn find_node_by_name(doc: &gltf::Document, name: &str) -> Option<usize> {
+    doc.nodes()
+        .find(|n| n.name() == Some(name))
+        .map(|n| n.index())
+}
+
+fn read_tracks(anim: &gltf::Animation, ctx: &Context) -> BTreeMap<usize, NodeTracks> {
+    let mut tracks: BTreeMap<usize, NodeTracks> = BTreeMap::new();
+    for channel in anim.channels() {
+        let reader = channel.reader(|buffer| ctx.get_buffer_data(buffer));
+        let (Some(inputs), Some(outputs)) = (reader.read_inputs(), reader.read_outputs()) else {
+            continue;
+        };
+        let times: Vec<f32> = inputs.collect();
+        let interpolation = channel.sampler().interpolation();
+        let node_tracks = tracks.entry(channel.target().node().index()).or_default();
+        match outputs {
+            ReadOutputs::Rotations(rotations) => {
+                let values = rotations.into_f32().map(Quat::from_array).collect();
+                node_tracks.rotation = Track::new(times, values, interpolation);
+            }
+            ReadOutputs::Translations(translations) => {
+                let values = translations.map(Vec3::from).collect();
+                node_tracks.translation = Track::new(times, values, interpolation);
+            }
+            _ => {}
+        }
+    }
+    tracks
+}
+
+/// Returns local poses of all nodes of the source document at given time
+fn sample_source_poses(
+    rest_poses: &[Pose],
+    tracks: &BTreeMap<usize, NodeTracks>,
+    time: f32,
+) -> Vec<Pose> {
+    let mut poses = rest_poses.to_vec();
+    for (&index, node_tracks) in tracks {
+        if let Some(track) = &node_tracks.rotation {
+            poses[index].rotation = track.sample(time, Quat::slerp).normalize();
+        }
+        if let Some(track) = &node_tracks.translation {
+            poses[index].translation = track.sample(time, Vec3::lerp);
+        }
+    }
+    poses
+}
+
+/// Target joint mapped to a source node
+struct MappedJoint {
+    target_name: String,
+    /// Index of the joint in the skin
+    joint_index: usize,
+    target_node: usize,
+    source_node: usize,
+    /// Transfer translation (not only rotation) of the source node
+    translate: bool,
+}
+
+fn resolve_bone_map(
+    bone_map: &BoneMap,
+    skin: &gltf::Skin,
+    source_doc: &gltf::Document,
+) -> ConvertResult<Vec<MappedJoint>> {
+    let mut mapped = Vec::with_capacity(bone_map.bones.len());
+    for (target_name, source_name) in &bone_map.bones {
+        let (joint_index, joint) = skin
+            .joints()
+            .enumerate()
+            .find(|(_, j)| j.name() == Some(target_name.as_str()))
+            .ok_or_else(|| {
+                ConvertError::InvalidInput(format!(
+                    "bone map: bone '{}' not found in the target skeleton",
+                    target_name
+                ))
+            })?;
+        let source_node = find_node_by_name(source_doc, source_name).ok_or_else(|| {
+            ConvertError::InvalidInput(format!(
+                "bone map: bone '{}' (mapped to '{}') not found in the source file",
+                source_name, target_name
+            ))
+        })?;
+        mapped.push(MappedJoint {
+            target_name: target_name.clone(),
+            joint_index,
+            target_node: joint.index(),
+            source_node,
+            translate: false,
+        });
+    }
+    Ok(mapped)
+}
+
+/// Returns index of the mapped joint receiving root motion
+fn find_root_joint(
+    bone_map: &BoneMap,
+    mapped: &[MappedJoint],
+    hierarchy: &Hierarchy,
+) -> ConvertResult<Option<usize>> {
+    if let Some(root_name) = &bone_map.root {
+        let root = mapped.iter().position(|m| &m.target_name == root_name);
+        return root.map(Some).ok_or_else(|| {
+            ConvertError::InvalidInput(format!("bone map: root bone '{}' is not mapped", root_name))
+        });
+    }
+    Ok((0..mapped.len()).min_by_key(|&i| {
+        hierarchy
+            .order
+            .iter()
+            .position(|&index| index == mapped[i].target_node)
+    }))
+}
+
+/// Returns height (Y coordinate) of the node in the rest pose
+fn get_rest_height(doc: &gltf::Document, node_index: usize) -> f32 {
+    let rest: Vec<Pose> = doc.nodes().map(|n| Pose::rest(&n)).collect();
+    Hierarchy::new(doc).global_poses(&rest).matrices[node_index]
+        .w_axis
+        .y
+}
+
+/// Returns the first and the last keyframe time of the animation
+fn get_time_range(tracks: &BTreeMap<usize, NodeTracks>) -> Option<(f32, f32)> {
+    let all_times = tracks.values().flat_map(|t| {
+        let rotation_times = t.rotation.iter().flat_map(|r| r.times.iter());
+        let translation_times = t.translation.iter().flat_map(|r| r.times.iter());
+        rotation_times.chain(translation_times).copied()
+    });
+    let (start, end) = all_times.fold((f32::MAX, f32::MIN), |(min, max), t| {
+        (min.min(t), max.max(t))
+    });
+    (start <= end).then_some((start, end))
+}
+
+/// Samples the animation of source bones and applies their motion to mapped joints of the skin. `transform_rotation`
+/// and `transform_offset` transform world space rotation and translation of a source bone relative to its rest pose
+/// before they are applied to the target bone (e.g. to mirror the motion). Unmapped joints keep their rest pose.
+#[allow(clippy::too_many_arguments)]
+fn transfer_animation(
+    anim: &gltf::Animation,
+    source_doc: &gltf::Document,
+    source_ctx: &Context,
+    skin: &gltf::Skin,
+    target_doc: &gltf::Document,
+    mapped: &[MappedJoint],
+    transform_rotation: impl Fn(Quat) -> Quat,
+    transform_offset: impl Fn(Vec3) -> Vec3,
+) -> Vec<rfa::Bone> {
+    let source_hierarchy = Hierarchy::new(source_doc);
+    let target_hierarchy = Hierarchy::new(target_doc);
+    let source_rest: Vec<Pose> = source_doc.nodes().map(|n| Pose::rest(&n)).collect();
+    let target_rest: Vec<Pose> = target_doc.nodes().map(|n| Pose::rest(&n)).collect();
+    let source_rest_global = source_hierarchy.global_poses(&source_rest);
+    let target_rest_global = target_hierarchy.global_poses(&target_rest);
+
+    let tracks = read_tracks(anim, source_ctx);
+    let (start, end) = get_time_range(&tracks).unwrap_or((0.0_f32, -1.0_f32));
+    let num_samples = if start <= end {
+        ((end - start) * SAMPLES_PER_SEC).ceil() as usize + 1
+    } else {
+        0
+    };
+
+    let mapped_by_target: BTreeMap<usize, &MappedJoint> =
+        mapped.iter().map(|m| (m.target_node, m)).collect();
+    let num_joints = skin.joints().count();
+    let mut rotation_keys: Vec<Vec<rfa::RotationKey>> =
+        (0..num_joints).map(|_| Vec::new()).collect();
+    let mut translation_keys: Vec<Vec<rfa::TranslationKey>> =
+        (0..num_joints).map(|_| Vec::new()).collect();
+    let mut prev_rotations: Vec<Option<Quat>> = vec![None; num_joints];
+
+    for sample in 0..num_samples {
+        let time = (start + sample as f32 / SAMPLES_PER_SEC).min(end);
+        let rfa_time = char_anim::gltf_time_to_rfa_time(time);
+        let source_global =
+            source_hierarchy.global_poses(&sample_source_poses(&source_rest, &tracks, time));
+
+        let mut rotations = target_rest_global.rotations.clone();
+        let mut matrices = target_rest_global.matrices.clone();
+        for &node_index in &target_hierarchy.order {
+            let parent = target_hierarchy.parents[node_index];
+            let (parent_rotation, parent_matrix) = parent
+                .map_or((Quat::IDENTITY, Mat4::IDENTITY), |p| {
+                    (rotations[p], matrices[p])
+                });
+            let mut local = target_rest[node_index];
+            if let Some(m) = mapped_by_target.get(&node_index) {
+                // rotation of the source bone since its rest pose applied to the rest pose of the target bone
+                let delta = transform_rotation(
+                    source_global.rotations[m.source_node]
+                        * source_rest_global.rotations[m.source_node].inverse(),
+                );
+                let global_rotation = delta * target_rest_global.rotations[node_index];
+                local.rotation = (parent_rotation.inverse() * global_rotation).normalize();
+                if m.translate {
+                    let offset = transform_offset(
+                        source_global.matrices[m.source_node].w_axis.truncate()
+                            - source_rest_global.matrices[m.source_node].w_axis.truncate(),
+                    );
+                    let position =
+                        target_rest_global.matrices[node_index].w_axis.truncate() + offset;
+                    local.translation = parent_matrix.inverse().transform_point3(position);
+                    let translation = gltf_to_rf_vec(local.translation.to_array());
+                    translation_keys[m.joint_index].push(rfa::TranslationKey {
+                        time: rfa_time,
+                        translation,
+                        in_tangent: translation,
+                        out_tangent: translation,
+                    });
+                }
+                // keep quaternions in the same hemisphere so interpolation takes the shortest path
+                let mut rotation = local.rotation;
+                if prev_rotations[m.joint_index].is_some_and(|prev| prev.dot(rotation) < 0.0_f32) {
+                    rotation = -rotation;
+                }
+                prev_rotations[m.joint_index] = Some(rotation);
+                rotation_keys[m.joint_index].push(rfa::RotationKey {
+                    time: rfa_time,
+                    rotation: char_anim::make_short_quat(gltf_to_rf_quat(rotation.to_array())),
+                    ease_in: 0,
+                    ease_out: 0,
+                });
+            }
+            rotations[node_index] = parent_rotation * local.rotation;
+            matrices[node_index] = parent_matrix * local.matrix();
+        }
+    }
+
+    skin.joints()
+        .zip(rotation_keys.into_iter().zip(translation_keys))
+        .map(|(joint, (rotation_keys, translation_keys))| {
+            char_anim::make_bone_anim(&joint, anim, rotation_keys, translation_keys, source_ctx)
+        })
+        .collect()
+}
+
+/// Retargets animation from the source document onto the skin. `source_ctx` provides buffers of the source document
+/// and conversion options (animation weight, ramp times). Returns animation name (used as output file stem) and the
+/// RFA file.
+pub fn retarget_animation(
+    anim: &gltf::Animation,
+    index: usize,
+    source_doc: &gltf::Document,
+    source_ctx: &Context,
+    skin: &gltf::Skin,
+    target_doc: &gltf::Document,
+    bone_map: &BoneMap,
+) -> ConvertResult<(String, rfa::File)> {
+    let name = anim
+        .name()
+        .map_or_else(|| format!("anim_{}", index), str::to_owned);
+    source_ctx.check_cancelled()?;
+    info!("Retargeting animation: {}", name);
+
+    let mut mapped = resolve_bone_map(bone_map, skin, source_doc)?;
+    let root_index = find_root_joint(bone_map, &mapped, &Hierarchy::new(target_doc))?;
+
+    // root translation is scaled so characters of different size do not slide or float
+    let mut root_scale = 1.0_f32;
+    if let Some(root) = root_index.map(|i| &mut mapped[i]) {
+        root.translate = true;
+        let source_height = get_rest_height(source_doc, root.source_node);
+        let target_height = get_rest_height(target_doc, root.target_node);
+        if source_height.abs() > MIN_ROOT_HEIGHT && target_height.abs() > MIN_ROOT_HEIGHT {
+            root_scale = target_height / source_height;
+        }
+    }
+    debug!("Root translation scale: {}", root_scale);
+
+    let bones = transfer_animation(
+        anim,
+        source_doc,
+        source_ctx,
+        skin,
+        target_doc,
+        &mapped,
+        |rotation| rotation,
+        |offset| offset * root_scale,
+    );
+    let rfa = char_anim::make_rfa_from_bones(anim, skin, bones, source_ctx);
+    source_ctx.check_errors()?;
+    Ok((name, rfa))
+}
+
+/// Pairs of name prefixes marking left and right bones, e.g. `L_hand`
+const SIDE_PREFIXES: [(&str, &str); 2] = [("L_", "R_"), ("l_", "r_")];
+/// Pairs of name suffixes marking left and right bones, e.g. `hand_l` or `Arm.L`
+const SIDE_SUFFIXES: [(&str, &str); 4] = [("_L", "_R"), ("_l", "_r"), (".L", ".R"), (".l", ".r")];
+/// Pairs of name parts marking left and right bones, e.g. `Bip01 L Thigh` or `LeftArm`
+const SIDE_INFIXES: [(&str, &str); 3] = [(" L ", " R "), ("Left", "Right"), ("left", "right")];
+
+/// Returns names the bone from the opposite side may have
+fn get_mirrored_name_candidates(name: &str) -> Vec<String> {
+    let swapped = |(left, right): (&'static str, &'static str)| [(left, right), (right, left)];
+    let mut candidates = Vec::new();
+    for (from, to) in SIDE_PREFIXES.into_iter().flat_map(swapped) {
+        if let Some(rest) = name.strip_prefix(from) {
+            candidates.push(format!("{}{}", to, rest));
+        }
+    }
+    for (from, to) in SIDE_SUFFIXES.into_iter().flat_map(swapped) {
+        if let Some(rest) = name.strip_suffix(from) {
+            candidates.push(format!("{}{}", rest, to));
+        }
+    }
+    for (from, to) in SIDE_INFIXES.into_iter().flat_map(swapped) {
+        if name.contains(from) {
+            candidates.push(name.replace(from, to));
+        }
+    }
+    candidates
+}
+
+/// Reflects world space rotation across the YZ plane
+fn mirror_rotation(rotation: Quat) -> Quat {
+    Quat::from_xyzw(rotation.x, -rotation.y, -rotation.z, rotation.w)
+}
+
+/// Reflects world space translation across the YZ plane
+fn mirror_offset(offset: Vec3) -> Vec3 {
+    Vec3::new(-offset.x, offset.y, offset.z)
+}
+
+/// Creates left/right mirrored copy of the animation. Bones are paired by names (e.g. `Bip01 L Thigh` and
+/// `Bip01 R Thigh`) and every bone gets the reflected motion of its pair (bones without a pair, e.g. spine, get their
+/// own motion reflected). Motion is reflected across the YZ plane (X axis is negated) so the rest pose must be
+/// symmetrical. Returns animation name and the RFA file.
+pub fn mirror_animation(
+    anim: &gltf::Animation,
+    index: usize,
+    doc: &gltf::Document,
+    ctx: &Context,
+    skin: &gltf::Skin,
+) -> ConvertResult<(String, rfa::File)> {
+    let name = anim
+        .name()
+        .map_or_else(|| format!("anim_{}", index), str::to_owned);
+    ctx.check_cancelled()?;
+    info!("Mirroring animation: {}", name);
+
+    let joints: Vec<_> = skin.joints().collect();
+    let mut num_pairs = 0;
+    let mapped: Vec<_> = joints
+        .iter()
+        .enumerate()
+        .map(|(joint_index, joint)| {
+            let joint_name = joint.name().unwrap_or_default();
+            let pair = get_mirrored_name_candidates(joint_name)
+                .iter()
+                .find_map(|candidate| joints.iter().find(|j| j.name() == Some(candidate.as_str())));
+            if let Some(pair) = pair {
+                debug!(
+                    "Bone '{}' is paired with '{}'",
+                    joint_name,
+                    pair.name().unwrap_or_default()
+                );
+                num_pairs += 1;
+            }
+            let source_node = pair.map_or(joint.index(), gltf::Node::index);
+            let translate = anim.channels().any(|c| {
+                c.target().node().index() == source_node
+                    && c.target().property() == gltf::animation::Property::Translation
+            });
+            MappedJoint {
+                target_name: joint_name.to_owned(),
+                joint_index,
+                target_node: joint.index(),
+                source_node,
+                translate,
+            }
+        })
+        .collect();
+    if num_pairs == 0 {
+        ctx.warn(
+            WarningKind::MirrorPairs,
+            &format!(
+                "Animation {}: no left/right bone pairs found - only the motion is reflected",
+                name
+            ),
+        );
+    }
+
+    let bones = transfer_animation(
+        anim,
+        doc,
+        ctx,
+        skin,
+        doc,
+        &mapped,
+        mirror_rotation,
+        mirror_offset,
+    );
+    let rfa = char_anim::make_rfa_from_bones(anim, skin, bones, ctx);
+    ctx.check_errors()?;
+    Ok((name, rfa))
+}
@@ -4,6 +4,8 @@ use std::io::{Result, Seek, SeekFrom, Write};
 
 pub const RFA_SIGNATURE: u32 = 0x4656_4D56; // 'VMVF'
 pub const RFA_VERSION: i32 = 8; // 'VMVF'
+/// Animation times are stored in 1/160 of a frame at 30 FPS
+pub const RFA_TIME_UNITS_PER_SEC: f32 = 30.0_f32 * 160.0_f32;
 
 pub struct File {
     pub header: FileHeader,
@@ -0,0 +1,317 @@
+use crate::io_utils::{new_custom_error, ReadExt, WriteExt};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::convert::TryInto;
+use std::io::{Read, Result, Write};
+
+pub struct Rfg {
+    pub groups: Vec<Group>,
+}
+
+impl Rfg {
+    pub fn write<W: Write>(&self, wrt: &mut W) -> Result<()> {
+        wrt.write_all(&[0x0D, 0xD0, 0x3D, 0xD4])?; // signature
+        wrt.write_u32::<LittleEndian>(0xC8)?; // version
+        wrt.write_i32::<LittleEndian>(self.groups.len().try_into().unwrap())?;
+        for group in &self.groups {
+            group.write(wrt)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub struct Group {
+    pub group_name: String,
+    pub brushes: Vec<Brush>,
+    pub clutters: Vec<Clutter>,
+}
+
+impl Group {
+    pub fn write<W: Write>(&self, wrt: &mut W) -> Result<()> {
+        wrt.write_vstr(&self.group_name)?;
+        wrt.write_u8(0)?; // is_moving
+        wrt.write_u32::<LittleEndian>(self.brushes.len().try_into().unwrap())?; // brushes
+        for brush in &self.brushes {
+            brush.write(wrt)?;
+        }
+        wrt.write_u32::<LittleEndian>(0)?; // geo_regions
+        wrt.write_u32::<LittleEndian>(0)?; // lights
+        wrt.write_u32::<LittleEndian>(0)?; // cutscene_cameras
+        wrt.write_u32::<LittleEndian>(0)?; // cutscene_path_nodes
+        wrt.write_u32::<LittleEndian>(0)?; // ambient_sounds
+        wrt.write_u32::<LittleEndian>(0)?; // events
+        wrt.write_u32::<LittleEndian>(0)?; // mp_respawn_points
+        wrt.write_u32::<LittleEndian>(0)?; // nav_points
+        wrt.write_u32::<LittleEndian>(0)?; // entities
+        wrt.write_u32::<LittleEndian>(0)?; // items
+        wrt.write_u32::<LittleEndian>(self.clutters.len().try_into().unwrap())?; // clutters
+        for clutter in &self.clutters {
+            clutter.write(wrt)?;
+        }
+        wrt.write_u32::<LittleEndian>(0)?; // triggers
+        wrt.write_u32::<LittleEndian>(0)?; // particle_emitters
+        wrt.write_u32::<LittleEndian>(0)?; // gas_regions
+        wrt.write_u32::<LittleEndian>(0)?; // decals
+        wrt.write_u32::<LittleEndian>(0)?; // climbing_regions
+        wrt.write_u32::<LittleEndian>(0)?; // room_effects
+        wrt.write_u32::<LittleEndian>(0)?; // eax_effects
+        wrt.write_u32::<LittleEndian>(0)?; // bolt_emitters
+        wrt.write_u32::<LittleEndian>(0)?; // targets
+        wrt.write_u32::<LittleEndian>(0)?; // push_regions
+        Ok(())
+    }
+}
+
+pub struct Brush {
+    pub uid: i32,
+    pub pos: [f32; 3],
+    pub orient: [f32; 9],
+    pub solid: Solid,
+}
+
+impl Brush {
+    pub fn read<R: Read>(rdr: &mut R) -> Result<Self> {
+        let uid = rdr.read_i32::<LittleEndian>()?;
+        let pos = rdr.read_f32_array()?;
+        let orient = restore_matrix_rows(rdr.read_f32_array()?);
+        let solid = Solid::read(rdr)?;
+        let _flags = rdr.read_u32::<LittleEndian>()?;
+        let _life = rdr.read_i32::<LittleEndian>()?;
+        let _state = rdr.read_u32::<LittleEndian>()?;
+        Ok(Self {
+            uid,
+            pos,
+            orient,
+            solid,
+        })
+    }
+
+    pub fn write<W: Write>(&self, wrt: &mut W) -> Result<()> {
+        wrt.write_i32::<LittleEndian>(self.uid)?;
+        wrt.write_f32_slice::<LittleEndian>(&self.pos)?;
+        wrt.write_f32_slice::<LittleEndian>(&reorder_matrix_rows(self.orient))?;
+        self.solid.write(wrt)?;
+        wrt.write_u32::<LittleEndian>(0)?; // flags
+        wrt.write_i32::<LittleEndian>(-1)?; // life
+        wrt.write_u32::<LittleEndian>(0)?; // state
+        Ok(())
+    }
+}
+
+/// Clutter object placed in the level. Class name refers to an entry in `clutter.tbl` which defines the mesh
+pub struct Clutter {
+    pub uid: i32,
+    pub class_name: String,
+    pub pos: [f32; 3],
+    pub orient: [f32; 9],
+    pub script_name: String,
+}
+
+impl Clutter {
+    pub fn write<W: Write>(&self, wrt: &mut W) -> Result<()> {
+        wrt.write_i32::<LittleEndian>(self.uid)?;
+        wrt.write_vstr(&self.class_name)?;
+        wrt.write_f32_slice::<LittleEndian>(&self.pos)?;
+        wrt.write_f32_slice::<LittleEndian>(&reorder_matrix_rows(self.orient))?;
+        wrt.write_vstr(&self.script_name)?;
+        wrt.write_u8(0)?; // hidden_in_editor
+        wrt.write_vstr("")?; // skin
+        wrt.write_u32::<LittleEndian>(0)?; // links
+        Ok(())
+    }
+}
+
+pub(crate) fn reorder_matrix_rows(mat: [f32; 9]) -> [f32; 9] {
+    // rfl/rfg uses non-standard row order: forward, right, up
+    [
+        mat[6], mat[7], mat[8], mat[0], mat[1], mat[2], mat[3], mat[4], mat[5],
+    ]
+}
+
+fn restore_matrix_rows(mat: [f32; 9]) -> [f32; 9] {
+    // inverse of reorder_matrix_rows
+    [
+        mat[3], mat[4], mat[5], mat[6], mat[7], mat[8], mat[0], mat[1], mat[2],
+    ]
+}
+
+/// Texture scrolling of a face (e.g. conveyor belts and waterfalls)
+pub struct FaceScroll {
+    pub face_id: i32,
+    /// Texture coordinates change per second
+    pub uv_velocity: [f32; 2],
+}
+
+pub struct Solid {
+    pub textures: Vec<String>,
+    pub face_scroll: Vec<FaceScroll>,
+    pub vertices: Vec<[f32; 3]>,
+    pub faces: Vec<Face>,
+}
+
+fn read_count<R: Read>(rdr: &mut R) -> Result<usize> {
+    Ok(rdr.read_u32::<LittleEndian>()? as usize)
+}
+
+impl Solid {
+    pub fn read<R: Read>(rdr: &mut R) -> Result<Self> {
+        let _unknown1 = rdr.read_u32::<LittleEndian>()?;
+        let _modifiability = rdr.read_u32::<LittleEndian>()?;
+        let _name = rdr.read_vstr()?;
+        let num_textures = read_count(rdr)?;
+        let textures = (0..num_textures)
+            .map(|_| rdr.read_vstr())
+            .collect::<Result<Vec<_>>>()?;
+        let num_face_scroll_data = read_count(rdr)?;
+        let face_scroll = (0..num_face_scroll_data)
+            .map(|_| -> Result<FaceScroll> {
+                Ok(FaceScroll {
+                    face_id: rdr.read_i32::<LittleEndian>()?,
+                    uv_velocity: rdr.read_f32_array()?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let num_rooms = read_count(rdr)?;
+        let num_subroom_lists = read_count(rdr)?;
+        let num_portals = read_count(rdr)?;
+        if num_rooms != 0 || num_subroom_lists != 0 || num_portals != 0 {
+            return Err(new_custom_error("compiled solids are not supported"));
+        }
+        let num_vertices = read_count(rdr)?;
+        let vertices = (0..num_vertices)
+            .map(|_| rdr.read_f32_array())
+            .collect::<Result<Vec<_>>>()?;
+        let num_faces = read_count(rdr)?;
+        let faces = (0..num_faces)
+            .map(|_| Face::read(rdr))
+            .collect::<Result<Vec<_>>>()?;
+        let num_surfaces = read_count(rdr)?;
+        if num_surfaces != 0 {
+            return Err(new_custom_error(
+                "solids with lightmap surfaces are not supported",
+            ));
+        }
+        Ok(Self {
+            textures,
+            face_scroll,
+            vertices,
+            faces,
+        })
+    }
+
+    pub fn write<W: Write>(&self, wrt: &mut W) -> Result<()> {
+        wrt.write_u32::<LittleEndian>(0)?; // unknown1
+        wrt.write_u32::<LittleEndian>(0)?; // modifiability
+        wrt.write_vstr("")?; // modifiability
+        wrt.write_u32::<LittleEndian>(self.textures.len().try_into().unwrap())?; // num_textures
+        for texture in &self.textures {
+            wrt.write_vstr(texture)?;
+        }
+        wrt.write_u32::<LittleEndian>(self.face_scroll.len().try_into().unwrap())?; // num_face_scroll_data
+        for scroll in &self.face_scroll {
+            wrt.write_i32::<LittleEndian>(scroll.face_id)?;
+            wrt.write_f32_slice::<LittleEndian>(&scroll.uv_velocity)?;
+        }
+        wrt.write_u32::<LittleEndian>(0)?; // num_rooms
+        wrt.write_u32::<LittleEndian>(0)?; // num_subroom_lists
+        wrt.write_u32::<LittleEndian>(0)?; // num_portals
+        wrt.write_u32::<LittleEndian>(self.vertices.len().try_into().unwrap())?; // num_vertices
+        for vertex in &self.vertices {
+            wrt.write_f32_slice::<LittleEndian>(vertex)?;
+        }
+        wrt.write_u32::<LittleEndian>(self.faces.len().try_into().unwrap())?; // num_faces
+        for face in &self.faces {
+            face.write(wrt)?;
+        }
+        wrt.write_u32::<LittleEndian>(0)?; // num_surfaces
+        Ok(())
+    }
+}
+
+pub struct FaceVertex {
+    pub index: u32,
+    pub texture_coords: [f32; 2],
+}
+
+impl FaceVertex {
+    pub fn write<W: Write>(&self, wrt: &mut W) -> Result<()> {
+        wrt.write_u32::<LittleEndian>(self.index)?;
+        wrt.write_f32_slice::<LittleEndian>(&self.texture_coords)?;
+        Ok(())
+    }
+}
+
+pub struct Face {
+    pub plane: [f32; 4],
+    /// Identifier referenced by face scroll data (-1 if not needed)
+    pub id: i32,
+    pub texture: i32,
+    pub flags: u16,
+    /// Face is a portal between rooms (portals are connected when the level is compiled)
+    pub is_portal: bool,
+    pub vertices: Vec<FaceVertex>,
+}
+
+impl Face {
+    /// Sky is rendered in place of the face
+    pub const SHOW_SKY: u16 = 0x01;
+
+    pub fn read<R: Read>(rdr: &mut R) -> Result<Self> {
+        let plane = rdr.read_f32_array()?;
+        let texture = rdr.read_i32::<LittleEndian>()?;
+        let surface_index = rdr.read_i32::<LittleEndian>()?;
+        let id = rdr.read_i32::<LittleEndian>()?;
+        let _reserved1 = [
+            rdr.read_i32::<LittleEndian>()?,
+            rdr.read_i32::<LittleEndian>()?,
+        ];
+        let portal_index_plus_2 = rdr.read_i32::<LittleEndian>()?;
+        let flags = rdr.read_u16::<LittleEndian>()?;
+        let _reserved2 = rdr.read_u16::<LittleEndian>()?;
+        let _smoothing_groups = rdr.read_u32::<LittleEndian>()?;
+        let _room_index = rdr.read_i32::<LittleEndian>()?;
+        let num_vertices = read_count(rdr)?;
+        let mut vertices = Vec::with_capacity(num_vertices);
+        for _ in 0..num_vertices {
+            let index = rdr.read_u32::<LittleEndian>()?;
+            let texture_coords = rdr.read_f32_array()?;
+            if surface_index != -1 {
+                let _lightmap_coords = rdr.read_f32_array::<2>()?;
+            }
+            vertices.push(FaceVertex {
+                index,
+                texture_coords,
+            });
+        }
+        Ok(Self {
+            plane,
+            id,
+            texture,
+            flags,
+            is_portal: portal_index_plus_2 != 0,
+            vertices,
+        })
+    }
+
+    pub fn write<W: Write>(&self, wrt: &mut W) -> Result<()> {
+        wrt.write_f32_slice::<LittleEndian>(&self.plane)?;
+        wrt.write_i32::<LittleEndian>(self.texture)?;
+        wrt.write_i32::<LittleEndian>(-1)?; // surface_index
+        wrt.write_i32::<LittleEndian>(self.id)?;
+        wrt.write_i32::<LittleEndian>(-1)?; // reserved1
+        wrt.write_i32::<LittleEndian>(-1)?; // reserved1
+
+        // uncompiled portals have no index yet so 1 (index -1) is written for them and 0 for other faces
+        let portal_index_plus_2 = i32::from(self.is_portal);
+        wrt.write_i32::<LittleEndian>(portal_index_plus_2)?;
+        wrt.write_u16::<LittleEndian>(self.flags)?;
+        wrt.write_u16::<LittleEndian>(0)?; // reserved2
+        wrt.write_u32::<LittleEndian>(0)?; // smoothing_groups
+        wrt.write_i32::<LittleEndian>(-1)?; // room_index
+        wrt.write_i32::<LittleEndian>(self.vertices.len().try_into().unwrap())?; // num_vertices
+        for fvert in &self.vertices {
+            fvert.write(wrt)?;
+        }
+        Ok(())
+    }
+}
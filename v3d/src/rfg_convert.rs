@@ -3,53 +3,104 @@ use std::collections::HashMap;
 use glam::{Quat, Vec3};
 
 use crate::{
+    diagnostics::describe_material,
+    errors::{ConvertError, Location},
     gltf_to_rf_face, gltf_to_rf_quat, gltf_to_rf_vec,
-    io_utils::new_custom_error,
-    material::get_material_base_color_texture_name,
+    material::{
+        get_material_base_color_texture_name, get_material_face_flags, get_material_uv_scroll,
+    },
     math_utils::{compute_triangle_plane, generate_uv},
-    rfg::{Brush, Face, FaceVertex, Group, Rfg, Solid},
-    BoxResult, Context,
+    report::NodeReport,
+    rfg::{Brush, Clutter, Face, FaceScroll, FaceVertex, Group, Rfg, Solid},
+    v3mc_convert::get_node_extras,
+    Context, ConvertResult,
 };
+use serde_derive::Deserialize;
 
-pub fn convert_gltf_to_rfg(doc: &gltf::Document, ctx: &Context) -> BoxResult<Rfg> {
+#[derive(Deserialize, Default)]
+struct NodeExtras {
+    /// Class name from `clutter.tbl` - node is placed as a clutter object instead of being converted to a brush
+    #[serde(rename = "RF_clutter")]
+    clutter_class: Option<String>,
+}
+
+fn convert_clutter(node: &gltf::Node, class_name: String, uid: i32) -> Clutter {
+    let transform = glam::Mat4::from_cols_array_2d(&node.transform().matrix());
+    // clutter meshes cannot be scaled so only position and rotation are used
+    let (_scale, rotation, translation) = transform.to_scale_rotation_translation();
+    let orient_quat = gltf_to_rf_quat(rotation.to_array());
+    debug!("Clutter {}: '{}'", uid, class_name);
+    Clutter {
+        uid,
+        class_name,
+        pos: gltf_to_rf_vec(translation.to_array()),
+        orient: glam::Mat3::from_quat(Quat::from_array(orient_quat)).to_cols_array(),
+        script_name: node.name().unwrap_or_default().to_owned(),
+    }
+}
+
+pub fn convert_gltf_to_rfg(doc: &gltf::Document, ctx: &Context) -> ConvertResult<Rfg> {
     let mut next_uid = 1;
     let mut groups = Vec::new();
+    let num_meshes = doc.nodes().filter(|n| n.mesh().is_some()).count();
+    ctx.progress.start("Converting meshes", num_meshes);
     for node in doc.nodes() {
-        let Some(mesh) = node.mesh() else { continue };
         let group_name = node.name().unwrap_or_default().to_owned();
+        let extras = get_node_extras::<NodeExtras>(&node);
+        if let Some(class_name) = extras.clutter_class {
+            // mesh attached to a clutter node is only a preview of the clutter mesh
+            if node.mesh().is_some() {
+                ctx.progress.advance(1, &group_name);
+            }
+            let clutter = convert_clutter(&node, class_name, next_uid);
+            next_uid += 1;
+            groups.push(Group {
+                group_name,
+                brushes: Vec::new(),
+                clutters: vec![clutter],
+            });
+            continue;
+        }
+        let Some(mesh) = node.mesh() else { continue };
+        ctx.check_cancelled()?;
         let transform = glam::Mat4::from_cols_array_2d(&node.transform().matrix());
-        let brush = create_brush(mesh, next_uid, ctx, &transform)?;
+        let brush = create_brush(&node, mesh, next_uid, ctx, &transform)?;
+        ctx.add_node_report(NodeReport::from_brush(&group_name, &brush));
         next_uid += 1;
+        ctx.progress.advance(1, &group_name);
         let brushes = vec![brush];
         groups.push(Group {
             group_name,
             brushes,
+            clutters: Vec::new(),
         });
     }
+    ctx.progress.finish();
     let rfg = Rfg { groups };
     Ok(rfg)
 }
 
-fn create_brush(
+pub(crate) fn create_brush(
+    node: &gltf::Node,
     mesh: gltf::Mesh,
     uid: i32,
     ctx: &Context,
     transform: &glam::Mat4,
-) -> std::io::Result<Brush> {
+) -> ConvertResult<Brush> {
     let (scale, rotation, translation) = transform.to_scale_rotation_translation();
 
     let mut vertices = Vec::new();
     let mut textures = Vec::new();
     let mut faces = Vec::new();
+    let mut face_scroll = Vec::new();
 
-    for prim in mesh.primitives() {
+    for (i, prim) in mesh.primitives().enumerate() {
+        let location = || Location::primitive(Some(node), i, &prim);
         if prim.mode() != gltf::mesh::Mode::Triangles {
-            return Err(new_custom_error(
-                "only triangle list primitives are supported",
-            ));
+            return Err(ConvertError::UnsupportedPrimitiveMode(location()));
         }
 
-        let texture_name = get_material_base_color_texture_name(&prim.material());
+        let texture_name = get_material_base_color_texture_name(&prim.material(), ctx);
         let texture_index = textures
             .iter()
             .position(|t| t == &texture_name)
@@ -58,11 +109,16 @@ fn create_brush(
                 textures.len() - 1
             });
 
+        let uv_scroll = get_material_uv_scroll(&prim.material());
+        let (face_flags, is_portal) = get_material_face_flags(&prim.material());
         let reader = prim.reader(|buffer| ctx.get_buffer_data(buffer));
 
         let prim_v_index_to_brush_v_index: HashMap<usize, usize> = reader
             .read_positions()
-            .expect("mesh has no positions")
+            .ok_or_else(|| ConvertError::MissingAttribute {
+                location: location(),
+                attribute: "positions",
+            })?
             .map(gltf_to_rf_vec)
             .map(|v| (Vec3::from_array(v) * scale).to_array())
             .enumerate()
@@ -81,10 +137,17 @@ fn create_brush(
         let uvs_opt: Option<Vec<_>> = reader
             .read_tex_coords(0)
             .map(|iter| iter.into_f32().collect());
+        if uvs_opt.is_none() {
+            ctx.record_fix(format!(
+                "primitive #{} ({}): generated missing texture coordinates",
+                i,
+                describe_material(&prim.material())
+            ));
+        }
 
         let indices: Vec<u32> = reader
             .read_indices()
-            .expect("mesh has no indices")
+            .ok_or_else(|| ConvertError::NotIndexed(location()))?
             .into_u32()
             .collect();
 
@@ -99,7 +162,10 @@ fn create_brush(
                 let plane_normal = [plane[0], plane[1], plane[2]];
                 Face {
                     plane,
+                    id: -1,
                     texture: texture_index as i32,
+                    flags: face_flags,
+                    is_portal,
                     vertices: chunk
                         .iter()
                         .copied()
@@ -117,19 +183,28 @@ fn create_brush(
                         .collect(),
                 }
             })
-            .for_each(|f| faces.push(f));
+            .for_each(|mut f| {
+                if let Some(uv_velocity) = uv_scroll {
+                    // face identifiers only have to be unique within the solid
+                    f.id = faces.len() as i32;
+                    face_scroll.push(FaceScroll {
+                        face_id: f.id,
+                        uv_velocity,
+                    });
+                }
+                faces.push(f);
+            });
     }
 
-    if ctx.args.verbose >= 1 {
-        println!(
-            "Brush {}: {} vertices, {} faces",
-            uid,
-            vertices.len(),
-            faces.len()
-        );
-    }
+    info!(
+        "Brush {}: {} vertices, {} faces",
+        uid,
+        vertices.len(),
+        faces.len()
+    );
     let solid = Solid {
         textures,
+        face_scroll,
         vertices,
         faces,
     };
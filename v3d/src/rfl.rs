@@ -0,0 +1,382 @@
+use crate::io_utils::{new_custom_error, ReadExt, WriteExt};
+use crate::rfg::{reorder_matrix_rows, Brush};
+use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use std::convert::TryInto;
+use std::io::{Read, Result, Seek, SeekFrom, Write};
+
+pub const RFL_SIGNATURE: u32 = 0xD4BA_DA55;
+// RF 1.2 level version
+pub const RFL_VERSION: u32 = 0xC8;
+
+// Section types
+pub const END_SECTION: u32 = 0x0000_0000;
+pub const LIGHTS_SECTION: u32 = 0x0000_0300;
+pub const CUTSCENE_CAMERAS_SECTION: u32 = 0x0000_0400;
+pub const LEVEL_PROPERTIES_SECTION: u32 = 0x0000_0900;
+pub const PLAYER_START_SECTION: u32 = 0x0007_0000;
+pub const LEVEL_INFO_SECTION: u32 = 0x0100_0000;
+pub const BRUSHES_SECTION: u32 = 0x0200_0000;
+pub const GROUPS_SECTION: u32 = 0x0300_0000;
+
+pub struct Rfl {
+    pub level_name: String,
+    pub level_properties: LevelProperties,
+    pub level_info: LevelInfo,
+    pub player_start: PlayerStart,
+    pub brushes: Vec<Brush>,
+    pub lights: Vec<Light>,
+    pub cutscene_cameras: Vec<CutsceneCamera>,
+}
+
+impl Rfl {
+    pub fn write<W: Write + Seek>(&self, wrt: &mut W) -> Result<()> {
+        let header_pos = wrt.stream_position()?;
+        let mut header = FileHeader {
+            level_name: self.level_name.clone(),
+            ..FileHeader::default()
+        };
+        header.write(wrt)?;
+
+        let sections_start = wrt.stream_position()?;
+        write_section(wrt, LEVEL_PROPERTIES_SECTION, |wrt| {
+            self.level_properties.write(wrt)
+        })?;
+        write_section(wrt, LIGHTS_SECTION, |wrt| {
+            wrt.write_u32::<LittleEndian>(self.lights.len().try_into().unwrap())?;
+            for light in &self.lights {
+                light.write(wrt)?;
+            }
+            Ok(())
+        })?;
+        write_section(wrt, CUTSCENE_CAMERAS_SECTION, |wrt| {
+            wrt.write_u32::<LittleEndian>(self.cutscene_cameras.len().try_into().unwrap())?;
+            for camera in &self.cutscene_cameras {
+                camera.write(wrt)?;
+            }
+            Ok(())
+        })?;
+        header.level_info_offset = wrt.stream_position()?.try_into().unwrap();
+        write_section(wrt, LEVEL_INFO_SECTION, |wrt| self.level_info.write(wrt))?;
+        header.player_start_offset = wrt.stream_position()?.try_into().unwrap();
+        write_section(wrt, PLAYER_START_SECTION, |wrt| {
+            self.player_start.write(wrt)
+        })?;
+        write_section(wrt, BRUSHES_SECTION, |wrt| {
+            wrt.write_u32::<LittleEndian>(self.brushes.len().try_into().unwrap())?;
+            for brush in &self.brushes {
+                brush.write(wrt)?;
+            }
+            Ok(())
+        })?;
+        header.sections_count = 6;
+        header.sections_size = (wrt.stream_position()? - sections_start)
+            .try_into()
+            .unwrap();
+        SectionHeader {
+            section_type: END_SECTION,
+            size: 0,
+        }
+        .write(wrt)?;
+
+        // Update header now that offsets are known
+        let end_pos = wrt.stream_position()?;
+        wrt.seek(SeekFrom::Start(header_pos))?;
+        header.write(wrt)?;
+        wrt.seek(SeekFrom::Start(end_pos))?;
+        Ok(())
+    }
+}
+
+/// Editor data read from an existing level
+pub struct RflBrushes {
+    pub brushes: Vec<Brush>,
+    pub groups: Vec<Group>,
+}
+
+impl RflBrushes {
+    pub fn read<R: Read>(rdr: &mut R) -> Result<Self> {
+        let signature = rdr.read_u32::<LittleEndian>()?;
+        if signature != RFL_SIGNATURE {
+            return Err(new_custom_error("invalid RFL file signature"));
+        }
+        let version = rdr.read_u32::<LittleEndian>()?;
+        if version != RFL_VERSION {
+            // older (demo, pre-1.2 retail) and newer (extended editors) versions use section layouts that are not
+            // handled here so reading them would silently produce garbage
+            return Err(new_custom_error(format!(
+                "unsupported RFL version {} (only version {} saved by RF 1.2 and the stock RED is supported)",
+                version, RFL_VERSION
+            )));
+        }
+        // timestamp, player_start_offset, level_info_offset, sections_count, sections_size
+        let mut header_fields = [0_u32; 5];
+        rdr.read_u32_into::<LittleEndian>(&mut header_fields)?;
+        let _level_name = rdr.read_vstr()?;
+        let _mod_name = rdr.read_vstr()?;
+
+        let mut brushes = Vec::new();
+        let mut groups = Vec::new();
+        loop {
+            let section_type = rdr.read_u32::<LittleEndian>()?;
+            let size = rdr.read_u32::<LittleEndian>()?;
+            if section_type == END_SECTION {
+                break;
+            }
+            let mut data = vec![0_u8; size as usize];
+            rdr.read_exact(&mut data)?;
+            let mut section_rdr = data.as_slice();
+            match section_type {
+                BRUSHES_SECTION => {
+                    let num_brushes = section_rdr.read_u32::<LittleEndian>()?;
+                    for _ in 0..num_brushes {
+                        brushes.push(Brush::read(&mut section_rdr)?);
+                    }
+                }
+                GROUPS_SECTION => {
+                    let num_groups = section_rdr.read_u32::<LittleEndian>()?;
+                    for _ in 0..num_groups {
+                        let Some(group) = Group::read(&mut section_rdr)? else {
+                            // moving group data is not parsed so next groups cannot be read
+                            break;
+                        };
+                        groups.push(group);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(Self { brushes, groups })
+    }
+}
+
+pub struct Group {
+    pub name: String,
+    pub brush_uids: Vec<i32>,
+}
+
+impl Group {
+    fn read<R: Read>(rdr: &mut R) -> Result<Option<Self>> {
+        let name = rdr.read_vstr()?;
+        let _unknown = rdr.read_u8()?;
+        let is_moving = rdr.read_u8()? != 0;
+        if is_moving {
+            return Ok(None);
+        }
+        let num_objects = rdr.read_u32::<LittleEndian>()?;
+        for _ in 0..num_objects {
+            let _object_uid = rdr.read_i32::<LittleEndian>()?;
+        }
+        let num_brushes = rdr.read_u32::<LittleEndian>()?;
+        let brush_uids = (0..num_brushes)
+            .map(|_| rdr.read_i32::<LittleEndian>())
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Some(Self { name, brush_uids }))
+    }
+}
+
+#[derive(Default)]
+pub struct FileHeader {
+    pub timestamp: u32,
+    pub player_start_offset: u32,
+    pub level_info_offset: u32,
+    pub sections_count: u32,
+    pub sections_size: u32,
+    pub level_name: String,
+    pub mod_name: String,
+}
+
+impl FileHeader {
+    pub fn write<W: Write>(&self, wrt: &mut W) -> Result<()> {
+        wrt.write_u32::<LittleEndian>(RFL_SIGNATURE)?;
+        wrt.write_u32::<LittleEndian>(RFL_VERSION)?;
+        wrt.write_u32::<LittleEndian>(self.timestamp)?;
+        wrt.write_u32::<LittleEndian>(self.player_start_offset)?;
+        wrt.write_u32::<LittleEndian>(self.level_info_offset)?;
+        wrt.write_u32::<LittleEndian>(self.sections_count)?;
+        wrt.write_u32::<LittleEndian>(self.sections_size)?;
+        wrt.write_vstr(&self.level_name)?;
+        wrt.write_vstr(&self.mod_name)?;
+        Ok(())
+    }
+}
+
+pub struct SectionHeader {
+    pub section_type: u32,
+    pub size: u32,
+}
+
+impl SectionHeader {
+    pub fn write<W: Write>(&self, wrt: &mut W) -> Result<()> {
+        wrt.write_u32::<LittleEndian>(self.section_type)?;
+        wrt.write_u32::<LittleEndian>(self.size)?;
+        Ok(())
+    }
+}
+
+fn write_section<W: Write + Seek, F: FnMut(&mut W) -> Result<()>>(
+    wrt: &mut W,
+    section_type: u32,
+    mut fun: F,
+) -> Result<()> {
+    let header_pos = wrt.stream_position()?;
+    let mut section_hdr = SectionHeader {
+        section_type,
+        size: 0,
+    };
+    section_hdr.write(wrt)?;
+    let pos_before = wrt.stream_position()?;
+    fun(wrt)?;
+    let pos_after = wrt.stream_position()?;
+    // Update section size
+    section_hdr.size = (pos_after - pos_before).try_into().unwrap();
+    wrt.seek(SeekFrom::Start(header_pos))?;
+    section_hdr.write(wrt)?;
+    wrt.seek(SeekFrom::Start(pos_after))?;
+    Ok(())
+}
+
+pub struct LevelProperties {
+    pub geomod_texture: String,
+    pub hardness: u32,
+    pub ambient_color: [u8; 4],
+    pub fog_color: [u8; 4],
+    pub fog_near_plane: f32,
+    pub fog_far_plane: f32,
+}
+
+impl Default for LevelProperties {
+    fn default() -> Self {
+        // values used by RED for a new level
+        Self {
+            geomod_texture: "Rck_Default.tga".into(),
+            hardness: 50,
+            ambient_color: [0, 0, 0, 0xFF],
+            fog_color: [0, 0, 0, 0xFF],
+            fog_near_plane: 10.0,
+            fog_far_plane: 100.0,
+        }
+    }
+}
+
+impl LevelProperties {
+    pub fn write<W: Write>(&self, wrt: &mut W) -> Result<()> {
+        wrt.write_vstr(&self.geomod_texture)?;
+        wrt.write_u32::<LittleEndian>(self.hardness)?;
+        wrt.write_all(&self.ambient_color)?;
+        wrt.write_u8(0)?; // unknown
+        wrt.write_all(&self.fog_color)?;
+        wrt.write_f32::<LittleEndian>(self.fog_near_plane)?;
+        wrt.write_f32::<LittleEndian>(self.fog_far_plane)?;
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct LevelInfo {
+    pub level_name: String,
+    pub author: String,
+    pub date: String,
+    pub has_moving_geometry: bool,
+    pub multiplayer_level: bool,
+}
+
+impl LevelInfo {
+    pub fn write<W: Write>(&self, wrt: &mut W) -> Result<()> {
+        wrt.write_u32::<LittleEndian>(1)?; // unknown
+        wrt.write_vstr(&self.level_name)?;
+        wrt.write_vstr(&self.author)?;
+        wrt.write_vstr(&self.date)?;
+        wrt.write_u8(self.has_moving_geometry.into())?;
+        wrt.write_u8(self.multiplayer_level.into())?;
+        // editor viewports configuration - RED resets it when it is invalid
+        wrt.write_all(&[0_u8; 220])?;
+        Ok(())
+    }
+}
+
+pub struct PlayerStart {
+    pub pos: [f32; 3],
+    pub orient: [f32; 9],
+}
+
+impl Default for PlayerStart {
+    fn default() -> Self {
+        Self {
+            pos: [0.0; 3],
+            orient: [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0],
+        }
+    }
+}
+
+impl PlayerStart {
+    pub fn write<W: Write>(&self, wrt: &mut W) -> Result<()> {
+        wrt.write_f32_slice::<LittleEndian>(&self.pos)?;
+        wrt.write_f32_slice::<LittleEndian>(&reorder_matrix_rows(self.orient))?;
+        Ok(())
+    }
+}
+
+pub struct Light {
+    pub uid: i32,
+    pub pos: [f32; 3],
+    pub orient: [f32; 9],
+    pub script_name: String,
+    pub light_type: LightType,
+    pub color: [u8; 4],
+    pub range: f32,
+    // spot light cone angles in degrees
+    pub fov: f32,
+    pub fov_dropoff: f32,
+    pub intensity: f32,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LightType {
+    Point = 0,
+    Spot = 1,
+}
+
+impl Light {
+    const FLAG_ENABLED: u32 = 0x1;
+
+    pub fn write<W: Write>(&self, wrt: &mut W) -> Result<()> {
+        wrt.write_i32::<LittleEndian>(self.uid)?;
+        wrt.write_vstr("Light")?; // class_name
+        wrt.write_f32_slice::<LittleEndian>(&self.pos)?;
+        wrt.write_f32_slice::<LittleEndian>(&reorder_matrix_rows(self.orient))?;
+        wrt.write_vstr(&self.script_name)?;
+        wrt.write_u8(0)?; // hidden_in_editor
+        wrt.write_u32::<LittleEndian>(Self::FLAG_ENABLED)?; // flags
+        wrt.write_u32::<LittleEndian>(self.light_type as u32)?;
+        wrt.write_all(&self.color)?;
+        wrt.write_f32::<LittleEndian>(self.range)?;
+        wrt.write_f32::<LittleEndian>(self.fov)?;
+        wrt.write_f32::<LittleEndian>(self.fov_dropoff)?;
+        wrt.write_f32::<LittleEndian>(0.0)?; // intensity_at_max_range
+        wrt.write_f32::<LittleEndian>(0.0)?; // dropoff (linear)
+        wrt.write_f32::<LittleEndian>(0.0)?; // tube_light_width
+        wrt.write_f32::<LittleEndian>(self.intensity)?;
+        wrt.write_f32::<LittleEndian>(0.0)?; // unknown
+        wrt.write_f32::<LittleEndian>(0.0)?; // unknown
+        Ok(())
+    }
+}
+
+pub struct CutsceneCamera {
+    pub uid: i32,
+    pub pos: [f32; 3],
+    pub orient: [f32; 9],
+    pub script_name: String,
+}
+
+impl CutsceneCamera {
+    pub fn write<W: Write>(&self, wrt: &mut W) -> Result<()> {
+        wrt.write_i32::<LittleEndian>(self.uid)?;
+        wrt.write_vstr("Cutscene Camera")?; // class_name
+        wrt.write_f32_slice::<LittleEndian>(&self.pos)?;
+        wrt.write_f32_slice::<LittleEndian>(&reorder_matrix_rows(self.orient))?;
+        wrt.write_vstr(&self.script_name)?;
+        wrt.write_u8(0)?; // hidden_in_editor
+        Ok(())
+    }
+}
@@ -0,0 +1,288 @@
+use crate::errors::ConvertError;
+use crate::limits::Limits;
+use crate::math_utils::compute_triangle_plane;
+use crate::rfg::Brush;
+use crate::rfl::RflBrushes;
+use crate::v3mc;
+use crate::v3mc_convert::{check_chunk_geometry_limits, create_v3mc_file_header, new_mesh_chunk};
+use crate::ConvertResult;
+use glam::{Mat3, Vec3};
+use std::collections::HashMap;
+
+#[derive(Default)]
+struct ChunkBuilder {
+    tex_name: String,
+    vecs: Vec<[f32; 3]>,
+    norms: Vec<[f32; 3]>,
+    uvs: Vec<[f32; 2]>,
+    faces: Vec<v3mc::MeshFace>,
+    vertex_map: HashMap<[u32; 8], u16>,
+}
+
+impl ChunkBuilder {
+    fn add_vertex(&mut self, pos: Vec3, norm: Vec3, uv: [f32; 2]) -> ConvertResult<u16> {
+        let key = [
+            pos.x.to_bits(),
+            pos.y.to_bits(),
+            pos.z.to_bits(),
+            norm.x.to_bits(),
+            norm.y.to_bits(),
+            norm.z.to_bits(),
+            uv[0].to_bits(),
+            uv[1].to_bits(),
+        ];
+        if let Some(index) = self.vertex_map.get(&key) {
+            return Ok(*index);
+        }
+        let index = self.vecs.len().try_into().map_err(|_| {
+            ConvertError::InvalidInput(format!(
+                "texture '{}': vertex index does not fit in 16 bits",
+                self.tex_name
+            ))
+        })?;
+        self.vecs.push(pos.to_array());
+        self.norms.push(norm.to_array());
+        self.uvs.push(uv);
+        self.vertex_map.insert(key, index);
+        Ok(index)
+    }
+
+    fn build_chunk_data(self) -> v3mc::MeshChunkData {
+        let nv = self.vecs.len();
+        let face_planes = self
+            .faces
+            .iter()
+            .map(|face| face.vindices.map(usize::from))
+            .map(|[i, j, k]| compute_triangle_plane(&self.vecs[i], &self.vecs[j], &self.vecs[k]))
+            .collect();
+        v3mc::MeshChunkData {
+            vecs: self.vecs,
+            norms: self.norms,
+            uvs: self.uvs,
+            faces: self.faces,
+            face_planes,
+            same_pos_vertex_offsets: vec![0; nv],
+            wi: vec![v3mc::WeightIndexArray::default(); nv],
+        }
+    }
+}
+
+/// Selects brushes to be converted from RFL level
+#[derive(Clone, Debug, Default)]
+pub struct BrushSelection {
+    /// Name of group containing brushes
+    pub group: Option<String>,
+    /// UIDs of brushes. All brushes are selected if neither group nor UIDs are given
+    pub uids: Vec<i32>,
+}
+
+fn select_brushes<'a>(
+    rfl: &'a RflBrushes,
+    selection: &BrushSelection,
+) -> ConvertResult<Vec<&'a Brush>> {
+    let mut uids = selection.uids.clone();
+    if let Some(group_name) = &selection.group {
+        let group = rfl
+            .groups
+            .iter()
+            .find(|g| g.name.eq_ignore_ascii_case(group_name))
+            .ok_or_else(|| ConvertError::InvalidInput(format!("group {} not found", group_name)))?;
+        uids.extend(&group.brush_uids);
+    }
+    let brushes: Vec<_> = if uids.is_empty() {
+        rfl.brushes.iter().collect()
+    } else {
+        rfl.brushes
+            .iter()
+            .filter(|b| uids.contains(&b.uid))
+            .collect()
+    };
+    if brushes.is_empty() {
+        return Err(ConvertError::InvalidInput("no brushes selected".to_owned()));
+    }
+    Ok(brushes)
+}
+
+fn get_brush_transform(brush: &Brush) -> (Mat3, Vec3) {
+    (
+        Mat3::from_cols_array(&brush.orient),
+        Vec3::from_array(brush.pos),
+    )
+}
+
+fn compute_brushes_center(brushes: &[&Brush]) -> Vec3 {
+    let (mut min, mut max) = (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN));
+    for brush in brushes {
+        let (rot, pos) = get_brush_transform(brush);
+        for v in &brush.solid.vertices {
+            let world_pos = rot * Vec3::from_array(*v) + pos;
+            min = min.min(world_pos);
+            max = max.max(world_pos);
+        }
+    }
+    (min + max) * 0.5
+}
+
+fn build_chunks(brushes: &[&Brush], center: Vec3) -> ConvertResult<Vec<ChunkBuilder>> {
+    let mut chunks: Vec<ChunkBuilder> = Vec::new();
+    for brush in brushes {
+        let (rot, pos) = get_brush_transform(brush);
+        let solid = &brush.solid;
+        for face in &solid.faces {
+            let tex_name = solid
+                .textures
+                .get(face.texture as usize)
+                .cloned()
+                .unwrap_or_else(|| "Rck_Default.tga".into());
+            let chunk_index = match chunks.iter().position(|c| c.tex_name == tex_name) {
+                Some(i) => i,
+                None => {
+                    chunks.push(ChunkBuilder {
+                        tex_name,
+                        ..ChunkBuilder::default()
+                    });
+                    chunks.len() - 1
+                }
+            };
+            let chunk = &mut chunks[chunk_index];
+            let norm = (rot * Vec3::new(face.plane[0], face.plane[1], face.plane[2])).normalize();
+            let mut vindices = Vec::with_capacity(face.vertices.len());
+            for fvert in &face.vertices {
+                let local_pos = solid.vertices.get(fvert.index as usize).ok_or_else(|| {
+                    ConvertError::InvalidInput(format!(
+                        "invalid vertex index in brush {}",
+                        brush.uid
+                    ))
+                })?;
+                let world_pos = rot * Vec3::from_array(*local_pos) + pos;
+                vindices.push(chunk.add_vertex(world_pos - center, norm, fvert.texture_coords)?);
+            }
+            // faces are convex polygons so they can be triangulated using a triangle fan
+            for i in 1..vindices.len().saturating_sub(1) {
+                chunk.faces.push(v3mc::MeshFace {
+                    vindices: [vindices[0], vindices[i], vindices[i + 1]],
+                    flags: 0,
+                });
+            }
+        }
+    }
+    Ok(chunks)
+}
+
+fn check_limits(chunks: &[ChunkBuilder], limits: &Limits) -> ConvertResult<()> {
+    let mut errors = Vec::new();
+    if chunks.len() > limits.max_textures {
+        errors.push(format!(
+            "found {} textures in selected brushes but only {} are allowed",
+            chunks.len(),
+            limits.max_textures
+        ));
+    }
+    for (i, chunk) in chunks.iter().enumerate() {
+        errors.extend(check_chunk_geometry_limits(
+            &format!("chunk #{} (texture '{}')", i, chunk.tex_name),
+            chunk.vecs.len(),
+            chunk.faces.len() * 3,
+            limits,
+        ));
+    }
+    if !errors.is_empty() {
+        return Err(ConvertError::Validation(errors));
+    }
+    Ok(())
+}
+
+pub fn convert_rfl_brushes_to_v3m(
+    rfl: &RflBrushes,
+    name: &str,
+    selection: &BrushSelection,
+    limits: &Limits,
+) -> ConvertResult<v3mc::File> {
+    let brushes = select_brushes(rfl, selection)?;
+    info!("Converting {} brushes", brushes.len());
+    let center = compute_brushes_center(&brushes);
+    let chunk_builders = build_chunks(&brushes, center)?;
+    check_limits(&chunk_builders, limits)?;
+
+    let render_mode = v3mc::encode_render_mode(
+        v3mc::TextureSource::Wrap,
+        v3mc::ColorOp::Mul,
+        v3mc::AlphaOp::Mul,
+        v3mc::AlphaBlend::None,
+        v3mc::ZbufferType::Full,
+        v3mc::FogType::Type0,
+    );
+    let mut chunks = Vec::with_capacity(chunk_builders.len());
+    let mut chunks_info = Vec::with_capacity(chunk_builders.len());
+    let mut chunks_data = Vec::with_capacity(chunk_builders.len());
+    let mut materials = Vec::with_capacity(chunk_builders.len());
+    let mut textures = Vec::with_capacity(chunk_builders.len());
+    let (mut bbox_min, mut bbox_max) = ([f32::MAX; 3], [f32::MIN; 3]);
+    let mut radius = 0_f32;
+    for (i, builder) in chunk_builders.into_iter().enumerate() {
+        let vertex_count = builder.vecs.len();
+        let tri_count = builder.faces.len();
+        for v in &builder.vecs {
+            #[allow(clippy::needless_range_loop)]
+            for axis in 0..3 {
+                bbox_min[axis] = bbox_min[axis].min(v[axis]);
+                bbox_max[axis] = bbox_max[axis].max(v[axis]);
+            }
+            radius = radius.max(Vec3::from_array(*v).length());
+        }
+        let chunk = new_mesh_chunk(vertex_count, tri_count, render_mode).ok_or_else(|| {
+            ConvertError::InvalidInput(format!(
+                "texture '{}': too much geometry to be stored in a single mesh chunk",
+                builder.tex_name
+            ))
+        })?;
+        chunks.push(chunk);
+        chunks_info.push(v3mc::MeshDataBlockChunkInfo {
+            texture_index: i as i32,
+        });
+        materials.push(v3mc::Material {
+            tex_name: builder.tex_name.clone(),
+            flags: 0x11,
+            ..v3mc::Material::default()
+        });
+        textures.push(v3mc::MeshTextureRef {
+            material_index: i as u8,
+            tex_name: builder.tex_name.clone(),
+        });
+        chunks_data.push(builder.build_chunk_data());
+    }
+
+    let num_vecs = chunks_data.iter().map(|c| c.vecs.len()).sum::<usize>() as i32;
+    let mesh = v3mc::Mesh {
+        flags: v3mc::VIF_MESH_FLAG_FACE_PLANES,
+        num_vecs,
+        chunks,
+        data_block: v3mc::MeshDataBlock {
+            chunks: chunks_info,
+            chunks_data,
+            prop_points: Vec::new(),
+        },
+        textures,
+    };
+    let name: String = name.chars().take(23).collect();
+    let lod_mesh = v3mc::LodMesh {
+        unknown1: v3mc::LodMeshUnknown1::default_table(&name),
+        name,
+        parent_name: "None".to_string(),
+        version: v3mc::MeshDataBlock::VERSION,
+        distances: vec![0.0],
+        offset: [0.0; 3],
+        radius,
+        bbox_min,
+        bbox_max,
+        meshes: vec![mesh],
+        materials,
+    };
+    let lod_meshes = vec![lod_mesh];
+    Ok(v3mc::File {
+        header: create_v3mc_file_header(&lod_meshes, &[], false),
+        lod_meshes,
+        cspheres: Vec::new(),
+        bones: Vec::new(),
+    })
+}
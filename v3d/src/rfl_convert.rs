@@ -0,0 +1,143 @@
+use crate::math_utils::linear_to_srgb;
+use crate::warnings::WarningKind;
+use crate::{
+    gltf_to_rf_quat, gltf_to_rf_vec,
+    report::NodeReport,
+    rfg_convert::create_brush,
+    rfl::{CutsceneCamera, LevelInfo, LevelProperties, Light, LightType, PlayerStart, Rfl},
+    Context, ConvertResult,
+};
+use glam::{Mat3, Quat};
+use gltf::khr_lights_punctual::Kind;
+
+// Illuminance (in lux) at which light is considered to have no effect. Used to compute light range
+// when it is not explicitly defined.
+const MIN_ILLUMINANCE: f32 = 0.05;
+
+fn convert_light_color(color: [f32; 3]) -> [u8; 4] {
+    let [r, g, b] = color.map(|c| (linear_to_srgb(c.clamp(0.0, 1.0)) * 255.0).round() as u8);
+    [r, g, b, 0xFF]
+}
+
+fn convert_directed_node_transform(node: &gltf::Node) -> ([f32; 3], [f32; 9]) {
+    let transform = glam::Mat4::from_cols_array_2d(&node.transform().matrix());
+    let (_scale, rotation, translation) = transform.to_scale_rotation_translation();
+    // glTF lights and cameras point towards -Z and RF objects point towards +Z so rotate them around Y axis
+    let rf_rotation = Mat3::from_quat(Quat::from_array(gltf_to_rf_quat(rotation.to_array())));
+    let orient = (rf_rotation * Mat3::from_rotation_y(std::f32::consts::PI)).to_cols_array();
+    (gltf_to_rf_vec(translation.to_array()), orient)
+}
+
+fn convert_light(node: &gltf::Node, uid: i32, ctx: &Context) -> Option<Light> {
+    let light = node.light()?;
+    let name = node.name().unwrap_or_default().to_owned();
+    let (light_type, fov, fov_dropoff) = match light.kind() {
+        Kind::Point => (LightType::Point, 360.0, 0.0),
+        Kind::Spot {
+            inner_cone_angle,
+            outer_cone_angle,
+        } => (
+            LightType::Spot,
+            (outer_cone_angle * 2.0).to_degrees(),
+            ((outer_cone_angle - inner_cone_angle) * 2.0).to_degrees(),
+        ),
+        Kind::Directional => {
+            ctx.warn(
+                WarningKind::UnsupportedLight,
+                &format!(
+                    "Directional light '{}' is not supported by RFL - use level ambient light instead",
+                    name
+                ),
+            );
+            return None;
+        }
+    };
+    // RF light brightness is controlled mostly by its range. Point and spot light intensity in glTF is
+    // in candela so range is the distance at which illuminance drops below MIN_ILLUMINANCE.
+    let range = light
+        .range()
+        .unwrap_or_else(|| (light.intensity() / MIN_ILLUMINANCE).sqrt());
+
+    let (pos, orient) = convert_directed_node_transform(node);
+    debug!("Light {}: '{}', range {}", uid, name, range);
+    Some(Light {
+        uid,
+        pos,
+        orient,
+        script_name: name,
+        light_type,
+        color: convert_light_color(light.color()),
+        range,
+        fov,
+        fov_dropoff,
+        intensity: 1.0,
+    })
+}
+
+fn convert_camera(node: &gltf::Node, uid: i32) -> Option<CutsceneCamera> {
+    node.camera()?;
+    let name = node.name().unwrap_or_default().to_owned();
+    let (pos, orient) = convert_directed_node_transform(node);
+    debug!("Cutscene camera {}: '{}'", uid, name);
+    Some(CutsceneCamera {
+        uid,
+        pos,
+        orient,
+        script_name: name,
+    })
+}
+
+/// Level name is stored in the file header and level info
+pub fn convert_gltf_to_rfl(
+    doc: &gltf::Document,
+    level_name: &str,
+    ctx: &Context,
+) -> ConvertResult<Rfl> {
+    let mut next_uid = 1;
+    let mut brushes = Vec::new();
+    let mut lights = Vec::new();
+    let mut cutscene_cameras = Vec::new();
+    let num_meshes = doc.nodes().filter(|n| n.mesh().is_some()).count();
+    ctx.progress.start("Converting meshes", num_meshes);
+    for node in doc.nodes() {
+        if let Some(mesh) = node.mesh() {
+            ctx.check_cancelled()?;
+            let transform = glam::Mat4::from_cols_array_2d(&node.transform().matrix());
+            let brush = create_brush(&node, mesh, next_uid, ctx, &transform)?;
+            let name = node.name().unwrap_or("<unnamed>");
+            ctx.add_node_report(NodeReport::from_brush(name, &brush));
+            brushes.push(brush);
+            next_uid += 1;
+            ctx.progress.advance(1, node.name().unwrap_or("<unnamed>"));
+        }
+        if let Some(light) = convert_light(&node, next_uid, ctx) {
+            lights.push(light);
+            next_uid += 1;
+        }
+        if let Some(camera) = convert_camera(&node, next_uid) {
+            cutscene_cameras.push(camera);
+            next_uid += 1;
+        }
+    }
+    ctx.progress.finish();
+    info!(
+        "Exported {} brushes, {} lights and {} cutscene cameras",
+        brushes.len(),
+        lights.len(),
+        cutscene_cameras.len()
+    );
+
+    let rfl = Rfl {
+        level_name: level_name.to_owned(),
+        level_properties: LevelProperties::default(),
+        level_info: LevelInfo {
+            level_name: level_name.to_owned(),
+            ..LevelInfo::default()
+        },
+        player_start: PlayerStart::default(),
+        brushes,
+        lights,
+        cutscene_cameras,
+    };
+    Ok(rfl)
+}
@@ -0,0 +1,103 @@
+//! Reconstruction of vertex normals from geometry using a crease angle (similar to classic smoothing groups).
+//!
+//! Normal of a face corner is the average of normals of faces sharing the corner position whose angle to the corner
+//! face is not bigger than the crease angle. Corners of the same vertex that end up with different normals are split
+//! into separate vertices. Vertices split by the exporter only because of normals are not merged (they may still
+//! differ in texture coordinates) but they get the same normals.
+
+use crate::math_utils::Vector3;
+use crate::v3mc::{MeshChunkData, WeightIndexArray};
+use std::collections::HashMap;
+
+fn sub(a: &Vector3, b: &Vector3) -> Vector3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: &Vector3, b: &Vector3) -> Vector3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn dot(a: &Vector3, b: &Vector3) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn normalize(v: &Vector3) -> Vector3 {
+    let len = dot(v, v).sqrt();
+    if len > 0.0 {
+        v.map(|c| c / len)
+    } else {
+        [0.0, 1.0, 0.0]
+    }
+}
+
+/// Recomputes normals of the chunk. Returns number of vertices added by splitting or `None` if the chunk would need
+/// more vertices than 16-bit indices can address.
+pub(crate) fn apply_crease_angle(
+    chunk: &mut MeshChunkData,
+    crease_angle_deg: f32,
+) -> Option<usize> {
+    let min_cos = crease_angle_deg.to_radians().cos();
+    let vecs = &chunk.vecs;
+    // area weighted face normals (length is twice the area)
+    let face_normals: Vec<Vector3> = chunk
+        .faces
+        .iter()
+        .map(|f| f.vindices.map(usize::from))
+        .map(|[i, j, k]| cross(&sub(&vecs[j], &vecs[i]), &sub(&vecs[k], &vecs[i])))
+        .collect();
+    let unit_face_normals: Vec<Vector3> = face_normals.iter().map(normalize).collect();
+
+    let mut position_faces: HashMap<[u32; 3], Vec<usize>> = HashMap::new();
+    for (face_index, face) in chunk.faces.iter().enumerate() {
+        for &v in &face.vindices {
+            position_faces
+                .entry(vecs[usize::from(v)].map(f32::to_bits))
+                .or_default()
+                .push(face_index);
+        }
+    }
+
+    let mut new_vertices: Vec<(usize, Vector3)> = Vec::with_capacity(vecs.len());
+    let mut vertex_map: HashMap<(usize, [u32; 3]), u16> = HashMap::new();
+    let mut new_faces = chunk.faces.clone();
+    for (face_index, face) in new_faces.iter_mut().enumerate() {
+        for vindex in &mut face.vindices {
+            let v = usize::from(*vindex);
+            let mut sum = [0_f32; 3];
+            for &other in &position_faces[&vecs[v].map(f32::to_bits)] {
+                if dot(&unit_face_normals[face_index], &unit_face_normals[other]) >= min_cos {
+                    for (s, n) in sum.iter_mut().zip(face_normals[other]) {
+                        *s += n;
+                    }
+                }
+            }
+            let normal = normalize(&sum);
+            let key = (v, normal.map(f32::to_bits));
+            *vindex = match vertex_map.get(&key) {
+                Some(&new_index) => new_index,
+                None => {
+                    let new_index = u16::try_from(new_vertices.len()).ok()?;
+                    new_vertices.push((v, normal));
+                    vertex_map.insert(key, new_index);
+                    new_index
+                }
+            };
+        }
+    }
+
+    let num_added = new_vertices.len().saturating_sub(chunk.vecs.len());
+    chunk.vecs = new_vertices.iter().map(|&(v, _)| chunk.vecs[v]).collect();
+    chunk.norms = new_vertices.iter().map(|&(_, n)| n).collect();
+    chunk.uvs = new_vertices.iter().map(|&(v, _)| chunk.uvs[v]).collect();
+    chunk.wi = new_vertices
+        .iter()
+        .map(|&(v, _)| chunk.wi.get(v).copied().unwrap_or_default())
+        .collect::<Vec<WeightIndexArray>>();
+    chunk.same_pos_vertex_offsets = vec![0; chunk.vecs.len()];
+    chunk.faces = new_faces;
+    Some(num_added)
+}
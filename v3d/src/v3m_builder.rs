@@ -0,0 +1,355 @@
+//! Construction of V3M files from geometry generated by code, without going through GLTF.
+//!
+//! All positions and normals are expected to be in the RF coordinate system (left-handed, Y axis pointing up) and
+//! triangles are expected to use the clockwise winding order used by the game.
+
+use crate::errors::ConvertError;
+use crate::limits::Limits;
+use crate::math_utils::compute_triangle_plane;
+use crate::v3mc;
+use crate::v3mc_convert::{check_chunk_geometry_limits, create_v3mc_file_header, new_mesh_chunk};
+use crate::ConvertResult;
+use glam::Vec3;
+use std::io::{Seek, Write};
+
+/// Vertex of a batch
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vertex {
+    pub pos: [f32; 3],
+    pub norm: [f32; 3],
+    pub uv: [f32; 2],
+}
+
+/// Material used by a batch
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BatchMaterial {
+    /// Texture file name, e.g. "Rck_Default.tga"
+    pub texture: String,
+    /// Render triangles from both sides
+    pub double_sided: bool,
+}
+
+impl From<&str> for BatchMaterial {
+    fn from(texture: &str) -> Self {
+        Self {
+            texture: texture.to_string(),
+            double_sided: false,
+        }
+    }
+}
+
+struct Batch {
+    material: BatchMaterial,
+    vertices: Vec<Vertex>,
+    indices: Vec<u16>,
+}
+
+struct Submesh {
+    name: String,
+    batches: Vec<Batch>,
+    prop_points: Vec<v3mc::PropPoint>,
+}
+
+impl Submesh {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            batches: Vec::new(),
+            prop_points: Vec::new(),
+        }
+    }
+}
+
+/// Builds a V3M file submesh by submesh.
+///
+/// Batches and prop points are added to the most recently added submesh. If no submesh was added a submesh named
+/// "Default" is created automatically. Geometry is validated when the file is built.
+pub struct V3mBuilder {
+    limits: Limits,
+    submeshes: Vec<Submesh>,
+}
+
+impl Default for V3mBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl V3mBuilder {
+    pub fn new() -> Self {
+        Self {
+            limits: Limits::STOCK,
+            submeshes: Vec::new(),
+        }
+    }
+
+    /// Sets engine limits used to validate geometry (stock limits are used by default)
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Starts a new submesh
+    pub fn add_submesh(&mut self, name: &str) -> &mut Self {
+        self.submeshes.push(Submesh::new(name));
+        self
+    }
+
+    /// Adds a batch of triangles using a single material. Every three indices form a triangle.
+    pub fn add_batch<M: Into<BatchMaterial>>(
+        &mut self,
+        vertices: &[Vertex],
+        indices: &[u16],
+        material: M,
+    ) -> &mut Self {
+        self.current_submesh().batches.push(Batch {
+            material: material.into(),
+            vertices: vertices.to_vec(),
+            indices: indices.to_vec(),
+        });
+        self
+    }
+
+    /// Adds a prop point. Orientation is a quaternion in XYZW order.
+    pub fn add_prop_point(&mut self, name: &str, pos: [f32; 3], orient: [f32; 4]) -> &mut Self {
+        self.current_submesh().prop_points.push(v3mc::PropPoint {
+            name: name.to_string(),
+            orient,
+            pos,
+            parent_index: -1,
+        });
+        self
+    }
+
+    fn current_submesh(&mut self) -> &mut Submesh {
+        if self.submeshes.is_empty() {
+            self.submeshes.push(Submesh::new("Default"));
+        }
+        self.submeshes.last_mut().unwrap()
+    }
+
+    /// Validates added geometry and creates the V3M file
+    pub fn build(self) -> ConvertResult<v3mc::File> {
+        let errors: Vec<_> = self
+            .submeshes
+            .iter()
+            .flat_map(|s| check_submesh(s, &self.limits))
+            .collect();
+        if !errors.is_empty() {
+            return Err(ConvertError::Validation(errors));
+        }
+        let lod_meshes: Vec<_> = self
+            .submeshes
+            .into_iter()
+            .map(build_lod_mesh)
+            .collect::<ConvertResult<_>>()?;
+        Ok(v3mc::File {
+            header: create_v3mc_file_header(&lod_meshes, &[], false),
+            lod_meshes,
+            cspheres: Vec::new(),
+            bones: Vec::new(),
+        })
+    }
+
+    /// Validates added geometry and writes the V3M file
+    pub fn finish<W: Write + Seek>(self, wrt: &mut W) -> ConvertResult<()> {
+        self.build()?.write(wrt)?;
+        Ok(())
+    }
+}
+
+fn check_submesh(submesh: &Submesh, limits: &Limits) -> Vec<String> {
+    let mut errors = Vec::new();
+    let location = format!("submesh '{}'", submesh.name);
+    if submesh.name.len() >= 24 {
+        errors.push(format!(
+            "{}: name is too long (max 23 characters)",
+            location
+        ));
+    }
+    if submesh.batches.is_empty() {
+        errors.push(format!("{}: no batches", location));
+    }
+    let num_textures = get_submesh_materials(submesh).len();
+    if num_textures > limits.max_textures {
+        errors.push(format!(
+            "{}: too many textures: {} (limit {})",
+            location, num_textures, limits.max_textures
+        ));
+    }
+    for (i, batch) in submesh.batches.iter().enumerate() {
+        let batch_location = format!(
+            "{}, batch #{} (texture '{}')",
+            location, i, batch.material.texture
+        );
+        if batch.indices.len() % 3 != 0 {
+            errors.push(format!(
+                "{}: number of indices is not a multiple of three: {}",
+                batch_location,
+                batch.indices.len()
+            ));
+        }
+        if let Some(index) = batch
+            .indices
+            .iter()
+            .find(|&&i| usize::from(i) >= batch.vertices.len())
+        {
+            errors.push(format!(
+                "{}: vertex index {} is out of range (vertex count {})",
+                batch_location,
+                index,
+                batch.vertices.len()
+            ));
+        }
+        errors.extend(check_chunk_geometry_limits(
+            &batch_location,
+            batch.vertices.len(),
+            batch.indices.len(),
+            limits,
+        ));
+    }
+    for prop_point in &submesh.prop_points {
+        if prop_point.name.len() >= 0x44 {
+            errors.push(format!(
+                "{}: prop point name '{}' is too long",
+                location, prop_point.name
+            ));
+        }
+    }
+    errors
+}
+
+fn get_submesh_materials(submesh: &Submesh) -> Vec<&str> {
+    let mut textures: Vec<&str> = Vec::new();
+    for batch in &submesh.batches {
+        if !textures.contains(&batch.material.texture.as_str()) {
+            textures.push(&batch.material.texture);
+        }
+    }
+    textures
+}
+
+fn build_chunk_data(batch: &Batch) -> v3mc::MeshChunkData {
+    let nv = batch.vertices.len();
+    let vecs: Vec<_> = batch.vertices.iter().map(|v| v.pos).collect();
+    let flags = if batch.material.double_sided {
+        v3mc::MeshFace::DOUBLE_SIDED
+    } else {
+        0
+    };
+    let faces: Vec<_> = batch
+        .indices
+        .chunks(3)
+        .map(|tri| v3mc::MeshFace {
+            vindices: [tri[0], tri[1], tri[2]],
+            flags,
+        })
+        .collect();
+    let face_planes = faces
+        .iter()
+        .map(|face| face.vindices.map(usize::from))
+        .map(|[i, j, k]| compute_triangle_plane(&vecs[i], &vecs[j], &vecs[k]))
+        .collect();
+    v3mc::MeshChunkData {
+        norms: batch.vertices.iter().map(|v| v.norm).collect(),
+        uvs: batch.vertices.iter().map(|v| v.uv).collect(),
+        vecs,
+        faces,
+        face_planes,
+        same_pos_vertex_offsets: vec![0; nv],
+        wi: vec![v3mc::WeightIndexArray::default(); nv],
+    }
+}
+
+fn build_lod_mesh(submesh: Submesh) -> ConvertResult<v3mc::LodMesh> {
+    let render_mode = v3mc::encode_render_mode(
+        v3mc::TextureSource::Wrap,
+        v3mc::ColorOp::Mul,
+        v3mc::AlphaOp::Mul,
+        v3mc::AlphaBlend::None,
+        v3mc::ZbufferType::Full,
+        v3mc::FogType::Type0,
+    );
+    let textures = get_submesh_materials(&submesh);
+    let materials: Vec<_> = textures
+        .iter()
+        .map(|tex_name| v3mc::Material {
+            tex_name: tex_name.to_string(),
+            flags: 0x11,
+            ..v3mc::Material::default()
+        })
+        .collect();
+    let texture_refs: Vec<_> = textures
+        .iter()
+        .enumerate()
+        .map(|(i, tex_name)| v3mc::MeshTextureRef {
+            material_index: i as u8,
+            tex_name: tex_name.to_string(),
+        })
+        .collect();
+
+    let (mut bbox_min, mut bbox_max) = ([f32::MAX; 3], [f32::MIN; 3]);
+    let mut radius = 0_f32;
+    for v in submesh.batches.iter().flat_map(|b| &b.vertices) {
+        #[allow(clippy::needless_range_loop)]
+        for axis in 0..3 {
+            bbox_min[axis] = bbox_min[axis].min(v.pos[axis]);
+            bbox_max[axis] = bbox_max[axis].max(v.pos[axis]);
+        }
+        radius = radius.max(Vec3::from_array(v.pos).length());
+    }
+
+    let chunks = submesh
+        .batches
+        .iter()
+        .enumerate()
+        .map(|(i, b)| {
+            new_mesh_chunk(b.vertices.len(), b.indices.len() / 3, render_mode).ok_or_else(|| {
+                ConvertError::InvalidInput(format!(
+                    "submesh '{}', batch #{}: batch is too big to be stored in a single mesh chunk",
+                    submesh.name, i
+                ))
+            })
+        })
+        .collect::<ConvertResult<_>>()?;
+    let chunks_info = submesh
+        .batches
+        .iter()
+        .map(|b| v3mc::MeshDataBlockChunkInfo {
+            texture_index: textures
+                .iter()
+                .position(|t| *t == b.material.texture)
+                .unwrap() as i32,
+        })
+        .collect();
+    let chunks_data = submesh.batches.iter().map(build_chunk_data).collect();
+    let num_vecs = submesh
+        .batches
+        .iter()
+        .map(|b| b.vertices.len())
+        .sum::<usize>() as i32;
+    let mesh = v3mc::Mesh {
+        flags: v3mc::VIF_MESH_FLAG_FACE_PLANES,
+        num_vecs,
+        chunks,
+        data_block: v3mc::MeshDataBlock {
+            chunks: chunks_info,
+            chunks_data,
+            prop_points: submesh.prop_points,
+        },
+        textures: texture_refs,
+    };
+    Ok(v3mc::LodMesh {
+        unknown1: v3mc::LodMeshUnknown1::default_table(&submesh.name),
+        name: submesh.name,
+        parent_name: "None".to_string(),
+        version: v3mc::MeshDataBlock::VERSION,
+        distances: vec![0.0],
+        offset: [0.0; 3],
+        radius,
+        bbox_min,
+        bbox_max,
+        meshes: vec![mesh],
+        materials,
+    })
+}
@@ -0,0 +1,780 @@
+use crate::io_utils::{decode_string, encode_string, parse_char_array, write_char_array_field};
+use binrw::{binrw, BinRead, BinResult, BinWrite, Endian, NullString, VecArgs};
+use serde_derive::{Deserialize, Serialize};
+use std::convert::TryInto;
+use std::io::{Read, Seek, SeekFrom, Write};
+
+// File signatures
+pub const V3M_SIGNATURE: u32 = 0x5246_3344; // RF3D
+pub const V3C_SIGNATURE: u32 = 0x5246_434D; // RFCM
+
+// Supported format version
+pub const VERSION: u32 = 0x40000;
+
+// File chunk types
+pub const END_CHUNK: u32 = 0x0000_0000; // terminating section
+pub const SUBMESH_CHUNK: u32 = 0x5355_424D; // 'SUBM'
+pub const CSPHERE_CHUNK: u32 = 0x4353_5048; // 'CSPH'
+pub const BONE_CHUNK: u32 = 0x424F_4E45; // 'BONE'
+
+// Vif mesh flags
+pub const VIF_MESH_FLAG_MORPH: u32 = 0x01;
+pub const VIF_MESH_FLAG_CHARACTER: u32 = 0x02;
+pub const VIF_MESH_FLAG_REFLECTION: u32 = 0x04;
+#[allow(unused)]
+pub const VIF_MESH_FLAG_DETAILED_COLLISION: u32 = 0x10;
+pub const VIF_MESH_FLAG_FACE_PLANES: u32 = 0x20;
+
+// Alignment of sections in mesh data block
+const DATA_BLOCK_ALIGNMENT: u64 = 0x10;
+
+fn invalid_data<S: Seek>(stream: &mut S, message: String) -> binrw::Error {
+    binrw::Error::AssertFail {
+        pos: stream.stream_position().unwrap_or_default(),
+        message,
+    }
+}
+
+fn read_count<R: Read + Seek>(reader: &mut R, endian: Endian) -> BinResult<usize> {
+    let count = i32::read_options(reader, endian, ())?;
+    count
+        .try_into()
+        .map_err(|_| invalid_data(reader, format!("invalid element count: {}", count)))
+}
+
+fn read_vec<R, T>(reader: &mut R, endian: Endian, count: usize) -> BinResult<Vec<T>>
+where
+    R: Read + Seek,
+    T: for<'a> BinRead<Args<'a> = ()> + 'static,
+{
+    Vec::<T>::read_options(reader, endian, VecArgs { count, inner: () })
+}
+
+fn write_count<W: Write + Seek>(writer: &mut W, endian: Endian, count: usize) -> BinResult<()> {
+    let count: i32 = count
+        .try_into()
+        .map_err(|_| invalid_data(writer, format!("element count {} is too large", count)))?;
+    count.write_options(writer, endian, ())
+}
+
+/// Stream adapter with positions relative to the place where it was created. Mesh data block is aligned relative
+/// to its beginning so this allows reading and writing it in place, without copying it into a separate buffer.
+struct Section<'a, S> {
+    inner: &'a mut S,
+    base: u64,
+}
+
+impl<'a, S: Seek> Section<'a, S> {
+    fn new(inner: &'a mut S) -> std::io::Result<Self> {
+        let base = inner.stream_position()?;
+        Ok(Self { inner, base })
+    }
+}
+
+impl<S: Read> Read for Section<'_, S> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl<S: Write> Write for Section<'_, S> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<S: Seek> Seek for Section<'_, S> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(offset) => SeekFrom::Start(self.base + offset),
+            pos => pos,
+        };
+        let abs_pos = self.inner.seek(pos)?;
+        abs_pos.checked_sub(self.base).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "seek before the beginning of a section",
+            )
+        })
+    }
+}
+
+/// V3M/V3C file. `BinRead` and `BinWrite` implementations can be used to read and write it with any endianness.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct File {
+    pub header: FileHeader,
+    pub lod_meshes: Vec<LodMesh>,
+    pub cspheres: Vec<ColSphere>,
+    pub bones: Vec<Bone>,
+}
+
+impl File {
+    /// Reads file in little-endian byte order (used by RF on PC)
+    pub fn read<R: Read + Seek>(rdr: &mut R) -> std::io::Result<Self> {
+        Self::read_options(rdr, Endian::Little, ()).map_err(crate::io_utils::binrw_to_io_error)
+    }
+
+    /// Writes file in little-endian byte order (used by RF on PC)
+    pub fn write<W: Write + Seek>(&self, wrt: &mut W) -> std::io::Result<()> {
+        self.write_options(wrt, Endian::Little, ())
+            .map_err(crate::io_utils::binrw_to_io_error)
+    }
+}
+
+impl BinRead for File {
+    type Args<'a> = ();
+
+    fn read_options<R: Read + Seek>(
+        reader: &mut R,
+        endian: Endian,
+        _args: Self::Args<'_>,
+    ) -> BinResult<Self> {
+        let header = FileHeader::read_options(reader, endian, ())?;
+        if header.signature != V3M_SIGNATURE && header.signature != V3C_SIGNATURE {
+            return Err(invalid_data(
+                reader,
+                "invalid V3M/V3C file signature".to_string(),
+            ));
+        }
+        if header.version != VERSION {
+            return Err(invalid_data(
+                reader,
+                format!("unsupported V3M/V3C version: {:#x}", header.version),
+            ));
+        }
+        let mut lod_meshes = Vec::new();
+        let mut cspheres = Vec::new();
+        let mut bones = Vec::new();
+        loop {
+            let chunk = FileChunk::read_options(reader, endian, ())?;
+            match chunk.chunk_type {
+                END_CHUNK => break,
+                SUBMESH_CHUNK => lod_meshes.push(LodMesh::read_options(reader, endian, ())?),
+                CSPHERE_CHUNK => cspheres.push(ColSphere::read_options(reader, endian, ())?),
+                BONE_CHUNK => {
+                    let num_bones = read_count(reader, endian)?;
+                    bones = read_vec(reader, endian, num_bones)?;
+                }
+                _ => {
+                    // skip unsupported sections (e.g. dumbs)
+                    reader.seek(SeekFrom::Current(i64::from(chunk.chunk_size)))?;
+                }
+            }
+        }
+        Ok(Self {
+            header,
+            lod_meshes,
+            cspheres,
+            bones,
+        })
+    }
+}
+
+impl BinWrite for File {
+    type Args<'a> = ();
+
+    fn write_options<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        endian: Endian,
+        _args: Self::Args<'_>,
+    ) -> BinResult<()> {
+        self.header.write_options(writer, endian, ())?;
+        for lod_mesh in &self.lod_meshes {
+            FileChunk {
+                chunk_type: SUBMESH_CHUNK,
+                chunk_size: 0, // ccrunch sets it to 0
+            }
+            .write_options(writer, endian, ())?;
+            lod_mesh.write_options(writer, endian, ())?;
+        }
+        for csphere in &self.cspheres {
+            FileChunk::write_new(writer, endian, CSPHERE_CHUNK, |wrt| {
+                csphere.write_options(wrt, endian, ())
+            })?;
+        }
+        if !self.bones.is_empty() {
+            FileChunk::write_new(writer, endian, BONE_CHUNK, |wrt| {
+                write_count(wrt, endian, self.bones.len())?;
+                self.bones.write_options(wrt, endian, ())
+            })?;
+        }
+        FileChunk {
+            chunk_type: END_CHUNK,
+            chunk_size: 0,
+        }
+        .write_options(writer, endian, ())
+    }
+}
+
+#[binrw]
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct FileHeader {
+    pub signature: u32,
+    pub version: u32,
+    pub num_lod_meshes: i32,
+    pub num_all_vertices: i32,       // ccrunch resets value to 0
+    pub num_all_faces: i32,          // ccrunch resets value to 0
+    pub num_all_vertex_normals: i32, // ccrunch resets value to 0
+    pub num_all_materials: i32,
+    pub num_all_meshes: i32, // ccrunch resets value to 0
+    pub num_dumbs: i32,      // ccrunch resets value to 0 and discards dumb sections
+    pub num_cspheres: i32,
+}
+
+#[binrw]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FileChunk {
+    pub chunk_type: u32,
+    pub chunk_size: u32,
+}
+
+impl FileChunk {
+    pub fn write_new<W: Write + Seek, F: FnMut(&mut W) -> BinResult<()>>(
+        wrt: &mut W,
+        endian: Endian,
+        chunk_type: u32,
+        mut fun: F,
+    ) -> BinResult<()> {
+        let header_pos = wrt.stream_position()?;
+        let mut chunk_hdr = FileChunk {
+            chunk_type,
+            chunk_size: 0,
+        };
+        chunk_hdr.write_options(wrt, endian, ())?;
+        let pos_before = wrt.stream_position()?;
+        fun(wrt)?;
+        let pos_after = wrt.stream_position()?;
+        // Update chunk size
+        chunk_hdr.chunk_size = (pos_after - pos_before)
+            .try_into()
+            .map_err(|_| invalid_data(wrt, format!("chunk {:#X} is too large", chunk_type)))?;
+        wrt.seek(SeekFrom::Start(header_pos))?;
+        chunk_hdr.write_options(wrt, endian, ())?;
+        // Seek the stream to previous position
+        wrt.seek(SeekFrom::Start(pos_after))?;
+        Ok(())
+    }
+}
+
+/// Entry of the table stored after submesh materials. Its meaning is unknown: all stock files contain exactly one entry
+/// with the submesh name and zero value, and the game does not seem to use it.
+#[binrw]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct LodMeshUnknown1 {
+    #[br(parse_with = parse_char_array, args_raw = 24)]
+    #[bw(write_with = write_char_array_field, args_raw = 24)]
+    pub name: String,
+    pub value: f32,
+}
+
+impl LodMeshUnknown1 {
+    /// Returns the table written by the original exporter for a submesh
+    pub fn default_table(submesh_name: &str) -> Vec<Self> {
+        vec![Self {
+            name: submesh_name.to_string(),
+            value: 0.0,
+        }]
+    }
+}
+
+#[binrw]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct LodMesh {
+    #[br(parse_with = parse_char_array, args_raw = 24)]
+    #[bw(write_with = write_char_array_field, args_raw = 24)]
+    pub name: String,
+    #[br(parse_with = parse_char_array, args_raw = 24)]
+    #[bw(write_with = write_char_array_field, args_raw = 24)]
+    pub parent_name: String,
+    pub version: i32,
+    #[br(temp)]
+    #[bw(calc = meshes.len() as i32)]
+    num_meshes: i32,
+    #[br(count = num_meshes)]
+    pub distances: Vec<f32>,
+    pub offset: [f32; 3],
+    pub radius: f32,
+    pub bbox_min: [f32; 3],
+    pub bbox_max: [f32; 3],
+    #[br(count = num_meshes)]
+    pub meshes: Vec<Mesh>,
+    #[br(temp)]
+    #[bw(calc = materials.len() as i32)]
+    num_materials: i32,
+    #[br(count = num_materials)]
+    pub materials: Vec<Material>,
+    #[br(temp)]
+    #[bw(calc = unknown1.len() as u32)]
+    num_unknown1: u32,
+    #[br(count = num_unknown1)]
+    #[serde(default)]
+    pub unknown1: Vec<LodMeshUnknown1>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Mesh {
+    pub flags: u32,
+    pub num_vecs: i32,
+    pub chunks: Vec<MeshChunk>,
+    pub data_block: MeshDataBlock,
+    pub textures: Vec<MeshTextureRef>,
+}
+
+impl BinRead for Mesh {
+    type Args<'a> = ();
+
+    fn read_options<R: Read + Seek>(
+        reader: &mut R,
+        endian: Endian,
+        _args: Self::Args<'_>,
+    ) -> BinResult<Self> {
+        let flags = u32::read_options(reader, endian, ())?;
+        let num_vecs = i32::read_options(reader, endian, ())?;
+        let num_chunks = u16::read_options(reader, endian, ())?;
+        let data_block_size = read_count(reader, endian)?;
+        // data block can be parsed only after reading chunks because it does not contain its own element counts
+        let data_block_pos = reader.stream_position()?;
+        reader.seek(SeekFrom::Current(data_block_size as i64))?;
+        let _unknown1 = i32::read_options(reader, endian, ())?;
+        let chunks: Vec<MeshChunk> = read_vec(reader, endian, usize::from(num_chunks))?;
+        let num_prop_points = read_count(reader, endian)?;
+        let num_textures = read_count(reader, endian)?;
+        let textures = read_vec(reader, endian, num_textures)?;
+        let end_pos = reader.stream_position()?;
+        reader.seek(SeekFrom::Start(data_block_pos))?;
+        let data_block = MeshDataBlock::read_options(
+            &mut Section::new(reader)?,
+            endian,
+            (flags, chunks.as_slice(), num_prop_points),
+        )?;
+        if reader.stream_position()? > data_block_pos + data_block_size as u64 {
+            return Err(invalid_data(
+                reader,
+                "mesh data block is bigger than its declared size".to_string(),
+            ));
+        }
+        reader.seek(SeekFrom::Start(end_pos))?;
+        Ok(Self {
+            flags,
+            num_vecs,
+            chunks,
+            data_block,
+            textures,
+        })
+    }
+}
+
+impl BinWrite for Mesh {
+    type Args<'a> = ();
+
+    fn write_options<W: Write + Seek>(
+        &self,
+        writer: &mut W,
+        endian: Endian,
+        _args: Self::Args<'_>,
+    ) -> BinResult<()> {
+        self.flags.write_options(writer, endian, ())?;
+        self.num_vecs.write_options(writer, endian, ())?;
+        let num_chunks: u16 = self.chunks.len().try_into().map_err(|_| {
+            invalid_data(
+                writer,
+                format!("too many mesh chunks: {}", self.chunks.len()),
+            )
+        })?;
+        num_chunks.write_options(writer, endian, ())?;
+
+        // data block is written directly to the output and its size is filled in afterwards
+        let data_block_size_pos = writer.stream_position()?;
+        0_i32.write_options(writer, endian, ())?;
+        let data_block_pos = writer.stream_position()?;
+        self.data_block
+            .write_options(&mut Section::new(writer)?, endian, ())?;
+        let data_block_end_pos = writer.stream_position()?;
+        writer.seek(SeekFrom::Start(data_block_size_pos))?;
+        let data_block_size = (data_block_end_pos - data_block_pos)
+            .try_into()
+            .map_err(|_| invalid_data(writer, "mesh data block is too large".to_string()))?;
+        write_count(writer, endian, data_block_size)?;
+        writer.seek(SeekFrom::Start(data_block_end_pos))?;
+
+        (-1_i32).write_options(writer, endian, ())?; // unknown1
+        self.chunks.write_options(writer, endian, ())?;
+
+        write_count(writer, endian, self.data_block.prop_points.len())?;
+
+        // material index is stored as u8
+        assert!(self.textures.len() <= usize::from(u8::MAX));
+        write_count(writer, endian, self.textures.len())?;
+        self.textures.write_options(writer, endian, ())
+    }
+}
+
+/// Batch info: geometry sizes and render mode of a single chunk (one material) of the mesh
+#[binrw]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MeshChunk {
+    pub num_vecs: u16,
+    pub num_faces: u16,
+    pub vecs_alloc: u16,
+    pub faces_alloc: u16,
+    pub same_pos_vertex_offsets_alloc: u16,
+    pub wi_alloc: u16,
+    pub uvs_alloc: u16,
+    pub render_mode: u32,
+}
+
+#[binrw]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MeshTextureRef {
+    pub material_index: u8,
+    #[br(map = |s: NullString| decode_string(&s.0))]
+    #[bw(try_map = |s: &String| encode_string(s).map(NullString))]
+    pub tex_name: String,
+}
+
+#[derive(BinWrite, Serialize, Deserialize, Clone, Debug)]
+pub struct MeshDataBlock {
+    #[bw(align_after = 0x10)]
+    pub chunks: Vec<MeshDataBlockChunkInfo>,
+    #[bw(align_after = 0x10)]
+    pub chunks_data: Vec<MeshChunkData>,
+    pub prop_points: Vec<PropPoint>,
+}
+
+impl MeshDataBlock {
+    pub const VERSION: i32 = 7;
+}
+
+fn align_reader<R: Seek>(reader: &mut R) -> std::io::Result<()> {
+    let pos = reader.stream_position()?;
+    let aligned_pos = (pos + DATA_BLOCK_ALIGNMENT - 1) & !(DATA_BLOCK_ALIGNMENT - 1);
+    reader.seek(SeekFrom::Start(aligned_pos))?;
+    Ok(())
+}
+
+impl BinRead for MeshDataBlock {
+    /// Mesh flags, mesh chunks and number of prop points
+    type Args<'a> = (u32, &'a [MeshChunk], usize);
+
+    fn read_options<R: Read + Seek>(
+        reader: &mut R,
+        endian: Endian,
+        (mesh_flags, mesh_chunks, num_prop_points): Self::Args<'_>,
+    ) -> BinResult<Self> {
+        if mesh_flags & VIF_MESH_FLAG_MORPH != 0 {
+            return Err(invalid_data(
+                reader,
+                "morph meshes are not supported".to_string(),
+            ));
+        }
+        let chunks = read_vec(reader, endian, mesh_chunks.len())?;
+        align_reader(reader)?;
+        let mut chunks_data = Vec::with_capacity(mesh_chunks.len());
+        for chunk in mesh_chunks {
+            let num_face_planes = if mesh_flags & VIF_MESH_FLAG_FACE_PLANES != 0 {
+                chunk.num_faces
+            } else {
+                0
+            };
+            let args = (
+                usize::from(chunk.num_vecs),
+                usize::from(chunk.uvs_alloc) / 8,
+                usize::from(chunk.num_faces),
+                usize::from(num_face_planes),
+                usize::from(chunk.same_pos_vertex_offsets_alloc) / 2,
+                usize::from(chunk.wi_alloc) / 8,
+            );
+            chunks_data.push(MeshChunkData::read_options(reader, endian, args)?);
+        }
+        align_reader(reader)?;
+        let prop_points = read_vec(reader, endian, num_prop_points)?;
+        Ok(Self {
+            chunks,
+            chunks_data,
+            prop_points,
+        })
+    }
+}
+
+#[binrw]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MeshDataBlockChunkInfo {
+    // unused data before and after texture index (game overrides it with data from MeshChunk)
+    // texture index is at offset 0x20 and the whole structure is 0x38 bytes long
+    #[brw(pad_before = 0x20, pad_after = 0x14)]
+    pub texture_index: i32,
+}
+
+#[binrw]
+#[br(import(num_vecs: usize, num_uvs: usize, num_faces: usize, num_face_planes: usize, num_offsets: usize, num_wi: usize))]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MeshChunkData {
+    #[br(count = num_vecs)]
+    #[brw(align_after = 0x10)]
+    pub vecs: Vec<[f32; 3]>,
+    #[br(count = num_vecs)]
+    #[brw(align_after = 0x10)]
+    pub norms: Vec<[f32; 3]>,
+    #[br(count = num_uvs)]
+    #[brw(align_after = 0x10)]
+    pub uvs: Vec<[f32; 2]>,
+    #[br(count = num_faces)]
+    #[brw(align_after = 0x10)]
+    pub faces: Vec<MeshFace>,
+    // triangle planes (used for backface culling)
+    #[br(count = num_face_planes)]
+    #[brw(align_after = 0x10)]
+    pub face_planes: Vec<[f32; 4]>,
+    #[br(count = num_offsets)]
+    #[brw(align_after = 0x10)]
+    pub same_pos_vertex_offsets: Vec<i16>,
+    #[br(count = num_wi)]
+    #[brw(align_after = 0x10)]
+    pub wi: Vec<WeightIndexArray>,
+    // if (Mesh::flags & 0x1) { // morph_vertices_map
+    //     orig_vert_map: [u16; Mesh::num_vertices];
+    //     // padding to 0x10 (to data section begin)
+    // }
+}
+
+#[binrw]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MeshFace {
+    pub vindices: [u16; 3],
+    pub flags: u16,
+}
+
+impl MeshFace {
+    pub const DOUBLE_SIDED: u16 = 0x20;
+}
+
+#[binrw]
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+pub struct WeightIndexArray {
+    pub weights: [u8; 4],
+    pub indices: [u8; 4],
+}
+
+#[binrw]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PropPoint {
+    #[br(parse_with = parse_char_array, args_raw = 0x44)]
+    #[bw(write_with = write_char_array_field, args_raw = 0x44)]
+    pub name: String,
+    pub orient: [f32; 4],
+    pub pos: [f32; 3],
+    pub parent_index: i32,
+}
+
+#[binrw]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ColSphere {
+    #[br(parse_with = parse_char_array, args_raw = 24)]
+    #[bw(write_with = write_char_array_field, args_raw = 24)]
+    pub name: String,
+    pub parent_index: i32,
+    pub pos: [f32; 3],
+    pub radius: f32,
+}
+
+#[binrw]
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Bone {
+    #[br(parse_with = parse_char_array, args_raw = 24)]
+    #[bw(write_with = write_char_array_field, args_raw = 24)]
+    pub name: String,
+    pub base_rotation: [f32; 4],
+    pub base_translation: [f32; 3],
+    pub parent_index: i32,
+}
+
+#[binrw]
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Material {
+    #[br(parse_with = parse_char_array, args_raw = 32)]
+    #[bw(write_with = write_char_array_field, args_raw = 32)]
+    pub tex_name: String, // not used by RF PC
+    pub self_illumination: f32, // used by static lighting code that is not working in RF PC (it does work in DF)
+    pub specular_level: f32,    // not used by RF PC
+    pub glossiness: f32,        // not used by RF PC
+    pub reflection_amount: f32, // not used by RF PC
+    #[br(parse_with = parse_char_array, args_raw = 32)]
+    #[bw(write_with = write_char_array_field, args_raw = 32)]
+    pub refl_tex_name: String, // not used by RF PC
+    pub flags: u32,             // not used by RF PC
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TextureSource {
+    None = 0,
+    Wrap = 1,
+    Clamp = 2,
+    ClampNoFiltering = 3,
+    // Other values are used with multi-texturing so they do not make sense here because
+    // V3M and V3C support only a single diffuse texture
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ColorOp {
+    SelectArg0IgnoreCurrentColor = 0x0,
+    SelectArg0 = 0x1,
+    Mul = 0x2,
+    Add = 0x3,
+    Mul2x = 0x4,
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AlphaOp {
+    SelArg2 = 0x0,
+    SelArg1 = 0x1,
+    SelArg1IgnoreCurrentColor = 0x2,
+    Mul = 0x3,
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+#[allow(clippy::enum_variant_names)]
+pub enum AlphaBlend {
+    None = 0x0,
+    AlphaAdditive = 0x1,
+    SrcAlpha2 = 0x2,
+    AlphaBlendAlpha = 0x3,
+    SrcAlpha4 = 0x4,
+    DestColor = 0x5,
+    InvDestColor = 0x6,
+    SwappedSrcDestColor = 0x7,
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ZbufferType {
+    None = 0x0,
+    Read = 0x1,
+    ReadEqFunc = 0x2,
+    Write = 0x3,
+    Full = 0x4,
+    FullAlphaTest = 0x5,
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FogType {
+    Type0 = 0x0,
+    Type1 = 0x1,
+    Type2 = 0x2,
+    ForceOff = 0x3,
+}
+
+pub fn encode_render_mode(
+    tex_src: TextureSource,
+    color_op: ColorOp,
+    alpha_op: AlphaOp,
+    alpha_blend: AlphaBlend,
+    zbuffer_type: ZbufferType,
+    fog: FogType,
+) -> u32 {
+    (tex_src as u32)
+        | ((color_op as u32) << 5)
+        | ((alpha_op as u32) << 10)
+        | ((alpha_blend as u32) << 15)
+        | ((zbuffer_type as u32) << 20)
+        | ((fog as u32) << 25)
+}
+
+/// Render mode split into fields (see [`encode_render_mode`]). Values are kept as numbers because stock assets use
+/// values that are not covered by the enums (e.g. multi-texturing texture sources).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct RenderModeFields {
+    pub tex_src: u32,
+    pub color_op: u32,
+    pub alpha_op: u32,
+    pub alpha_blend: u32,
+    pub zbuffer_type: u32,
+    pub fog: u32,
+}
+
+// Field names used in the materials override file, indexed by value
+const TEXTURE_SOURCE_NAMES: [&str; 4] = ["none", "wrap", "clamp", "clamp-no-filtering"];
+const COLOR_OP_NAMES: [&str; 5] = [
+    "select-arg0-ignore-current-color",
+    "select-arg0",
+    "mul",
+    "add",
+    "mul2x",
+];
+const ALPHA_OP_NAMES: [&str; 4] = [
+    "sel-arg2",
+    "sel-arg1",
+    "sel-arg1-ignore-current-color",
+    "mul",
+];
+const ALPHA_BLEND_NAMES: [&str; 8] = [
+    "none",
+    "alpha-additive",
+    "src-alpha2",
+    "alpha-blend-alpha",
+    "src-alpha4",
+    "dest-color",
+    "inv-dest-color",
+    "swapped-src-dest-color",
+];
+const ZBUFFER_TYPE_NAMES: [&str; 6] = [
+    "none",
+    "read",
+    "read-eq-func",
+    "write",
+    "full",
+    "full-alpha-test",
+];
+const FOG_TYPE_NAMES: [&str; 4] = ["type0", "type1", "type2", "force-off"];
+
+pub fn decode_render_mode(render_mode: u32) -> RenderModeFields {
+    let field = |shift: u32| (render_mode >> shift) & 0x1F;
+    RenderModeFields {
+        tex_src: field(0),
+        color_op: field(5),
+        alpha_op: field(10),
+        alpha_blend: field(15),
+        zbuffer_type: field(20),
+        fog: field(25),
+    }
+}
+
+impl std::fmt::Display for RenderModeFields {
+    /// Formats fields like the materials override file, e.g. `tex_src=wrap color_op=mul ...`. Values without a name
+    /// are printed as numbers.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let fields: [(&str, u32, &[&str]); 6] = [
+            ("tex_src", self.tex_src, &TEXTURE_SOURCE_NAMES),
+            ("color_op", self.color_op, &COLOR_OP_NAMES),
+            ("alpha_op", self.alpha_op, &ALPHA_OP_NAMES),
+            ("alpha_blend", self.alpha_blend, &ALPHA_BLEND_NAMES),
+            ("zbuffer_type", self.zbuffer_type, &ZBUFFER_TYPE_NAMES),
+            ("fog", self.fog, &FOG_TYPE_NAMES),
+        ];
+        for (i, (name, value, value_names)) in fields.into_iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            match value_names.get(value as usize) {
+                Some(value_name) => write!(f, "{}={}", name, value_name)?,
+                None => write!(f, "{}={}", name, value)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,1161 @@
+use crate::char_anim;
+use crate::decimate::decimate_chunk;
+use crate::diagnostics::{describe_node, describe_primitive};
+use crate::errors::{ConvertError, Location};
+use crate::extract_translation_from_matrix;
+use crate::extras;
+use crate::get_mesh_materials;
+use crate::get_node_local_transform;
+use crate::get_primitive_vertex_count;
+use crate::get_rendered_primitives;
+use crate::get_submesh_nodes;
+use crate::gltf_to_rf_face;
+use crate::gltf_to_rf_quat;
+use crate::gltf_to_rf_vec;
+use crate::is_collision_only;
+use crate::limits::Limits;
+use crate::material;
+use crate::material::{convert_material, create_mesh_material_ref, LodTextures};
+use crate::math_utils::{
+    compute_triangle_normal, compute_triangle_plane, generate_uv, get_vector_len, transform_normal,
+    transform_point, Matrix3, Vector3,
+};
+use crate::names;
+use crate::report::NodeReport;
+use crate::smoothing;
+use crate::v3mc;
+use crate::warnings::WarningKind;
+use crate::{
+    import_gltf_slice, Context, ConvertOptions, ConvertResult, MaterialPreset, NoProgress,
+    ProgressListener, PropAxis,
+};
+use rayon::prelude::*;
+use serde_derive::Deserialize;
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::env;
+use std::f32;
+use std::io::Cursor;
+use std::iter;
+use std::ops::Mul;
+use std::vec::Vec;
+
+/// Maximal number of vertices in a primitive that can be addressed by 16-bit face indices
+const MAX_INDEXED_VERTICES: usize = 1 << 16;
+
+/// Distance (in bounding radii) at which a LOD with a quarter of the triangles of the most detailed mesh is used
+const LOD_DISTANCE_RADIUS_FACTOR: f32 = 10.0;
+
+pub(crate) fn create_v3mc_file_header(
+    lod_meshes: &[v3mc::LodMesh],
+    cspheres: &[v3mc::ColSphere],
+    is_character: bool,
+) -> v3mc::FileHeader {
+    v3mc::FileHeader {
+        signature: if is_character {
+            v3mc::V3C_SIGNATURE
+        } else {
+            v3mc::V3M_SIGNATURE
+        },
+        version: v3mc::VERSION,
+        num_lod_meshes: lod_meshes.len() as i32,
+        num_all_materials: lod_meshes
+            .iter()
+            .map(|lm| lm.materials.len())
+            .sum::<usize>() as i32,
+        num_cspheres: cspheres.len() as i32,
+        ..v3mc::FileHeader::default()
+    }
+}
+
+/// Computes AABB and bounding sphere radius in a single pass over already transformed vertex positions
+fn compute_bounds(chunks_data: &[v3mc::MeshChunkData]) -> (gltf::mesh::BoundingBox, f32) {
+    // Note: primitive AABB from gltf cannot be used because vertices are being transformed
+    let mut aabb = gltf::mesh::BoundingBox {
+        min: [f32::MAX; 3],
+        max: [f32::MIN; 3],
+    };
+    let mut radius = 0_f32;
+    for pos in chunks_data.iter().flat_map(|c| &c.vecs) {
+        #[allow(clippy::needless_range_loop)]
+        for i in 0..3 {
+            aabb.min[i] = aabb.min[i].min(pos[i]);
+            aabb.max[i] = aabb.max[i].max(pos[i]);
+        }
+        radius = radius.max(get_vector_len(pos));
+    }
+    if radius == 0_f32 && aabb.min[0] > aabb.max[0] {
+        // Mesh has no vertices so return empty AABB
+        aabb.min = [0_f32; 3];
+        aabb.max = [0_f32; 3];
+    }
+    (aabb, radius)
+}
+
+fn create_mesh_chunk_info(
+    prim: &gltf::Primitive,
+    materials: &[gltf::Material],
+) -> v3mc::MeshDataBlockChunkInfo {
+    // write texture index in LOD model textures array
+    let prim_mat = prim.material();
+    let texture_index = materials
+        .iter()
+        .position(|m| m.index() == prim_mat.index())
+        .expect("cannot find texture") as i32;
+
+    v3mc::MeshDataBlockChunkInfo { texture_index }
+}
+
+/// Normals read from the input whose length differs more than this from 1 are reported as re-normalized
+const NORMAL_LENGTH_TOLERANCE: f32 = 0.01;
+
+/// Returns true if the normal can be normalized (it has a non-zero length and no NaN or infinite components)
+fn is_valid_normal(n: &Vector3) -> bool {
+    n.iter().all(|v| v.is_finite()) && get_vector_len(n) > f32::EPSILON
+}
+
+/// Replaces invalid normals by normals generated from adjacent triangles. Returns number of replaced normals
+fn fix_invalid_normals(norms: &mut [Vector3], vecs: &[Vector3], faces: &[v3mc::MeshFace]) -> usize {
+    let invalid: Vec<usize> = (0..norms.len())
+        .filter(|&i| !is_valid_normal(&norms[i]))
+        .collect();
+    if invalid.is_empty() {
+        return 0;
+    }
+    let mut sums = vec![[0_f32; 3]; norms.len()];
+    for [i, j, k] in faces.iter().map(|face| face.vindices.map(usize::from)) {
+        let normal = compute_triangle_normal(&vecs[i], &vecs[j], &vecs[k]);
+        // degenerate triangles have no normal
+        if is_valid_normal(&normal) {
+            for v in [i, j, k] {
+                for (sum, n) in sums[v].iter_mut().zip(normal) {
+                    *sum += n;
+                }
+            }
+        }
+    }
+    for &i in &invalid {
+        let len = get_vector_len(&sums[i]);
+        norms[i] = if len > f32::EPSILON {
+            sums[i].map(|v| v / len)
+        } else {
+            // vertex is not used by any proper triangle so its normal does not matter
+            [0_f32, 1_f32, 0_f32]
+        };
+    }
+    invalid.len()
+}
+
+/// Converts a primitive into mesh chunk data. Returns the chunk data and fixes applied to the primitive (they are
+/// recorded by the caller because primitives are processed in parallel)
+fn create_mesh_chunk_data(
+    node: &gltf::Node,
+    index: usize,
+    prim: &gltf::Primitive,
+    transform: &Matrix3,
+    ctx: &Context,
+) -> ConvertResult<(v3mc::MeshChunkData, Vec<String>)> {
+    let buffers = ctx.buffers();
+    let is_character = ctx.is_character;
+    let location = || Location::primitive(Some(node), index, prim);
+    let missing_attribute = |attribute| ConvertError::MissingAttribute {
+        location: location(),
+        attribute,
+    };
+    let reader = prim.reader(|buffer| buffers.get(buffer));
+
+    let vecs: Vec<_> = reader
+        .read_positions()
+        .ok_or_else(|| missing_attribute("positions"))?
+        .map(|pos| gltf_to_rf_vec(transform_point(&pos, transform)))
+        .collect();
+    let mut num_denormalized_normals = 0;
+    let mut norms: Vec<_> = reader
+        .read_normals()
+        // FIXME: according to GLTF spec we should generate flat normals here
+        .ok_or_else(|| missing_attribute("normals"))?
+        .map(|norm| {
+            if is_valid_normal(&norm)
+                && (get_vector_len(&norm) - 1.0).abs() > NORMAL_LENGTH_TOLERANCE
+            {
+                num_denormalized_normals += 1;
+            }
+            // invalid normals become NaNs here and they are replaced when faces are known
+            gltf_to_rf_vec(transform_normal(&norm, transform))
+        })
+        .collect();
+    if norms.len() != vecs.len() {
+        return Err(ConvertError::AttributeCountMismatch {
+            location: location(),
+            attribute: "normals",
+            count: norms.len(),
+            expected: vecs.len(),
+        });
+    }
+    let uvs: Vec<_> = reader.read_tex_coords(0).map_or_else(
+        || {
+            (0..vecs.len())
+                .map(|i| generate_uv(&vecs[i], &norms[i]))
+                .collect()
+        },
+        |iter| iter.into_f32().collect(),
+    );
+    let nv = vecs.len();
+    if uvs.len() != nv {
+        return Err(ConvertError::AttributeCountMismatch {
+            location: location(),
+            attribute: "texture coordinates",
+            count: uvs.len(),
+            expected: nv,
+        });
+    }
+    let face_flags = if material::is_material_double_sided(&prim.material(), ctx) {
+        v3mc::MeshFace::DOUBLE_SIDED
+    } else {
+        0
+    };
+    // Build faces directly from the index accessor without collecting indices first
+    let index_iter = reader
+        .read_indices()
+        .ok_or_else(|| ConvertError::NotIndexed(location()))?
+        .into_u32();
+    let num_indices = index_iter.len();
+    if num_indices % 3 != 0 {
+        return Err(ConvertError::InvalidInput(format!(
+            "{}: number of indices is not a multiple of three: {}",
+            location(),
+            num_indices
+        )));
+    }
+    let mut faces = Vec::with_capacity(num_indices / 3);
+    let mut tri = [0_u16; 3];
+    for (i, vindex) in index_iter.enumerate() {
+        let vindex: u16 = vindex
+            .try_into()
+            .map_err(|_| ConvertError::VertexIndexOverflow(location()))?;
+        if usize::from(vindex) >= nv {
+            return Err(ConvertError::VertexIndexOutOfRange {
+                location: location(),
+                index: vindex,
+                vertex_count: nv,
+            });
+        }
+        tri[i % 3] = vindex;
+        if i % 3 == 2 {
+            faces.push(v3mc::MeshFace {
+                vindices: gltf_to_rf_face(tri),
+                flags: face_flags,
+            });
+        }
+    }
+
+    let mut fixes = Vec::new();
+    let num_fixed_normals = fix_invalid_normals(&mut norms, &vecs, &faces);
+    if num_fixed_normals > 0 {
+        fixes.push(format!(
+            "{}: generated {} invalid (zero-length or NaN) normals from geometry",
+            location(),
+            num_fixed_normals
+        ));
+    }
+    if num_denormalized_normals > 0 {
+        fixes.push(format!(
+            "{}: normalized {} normals",
+            location(),
+            num_denormalized_normals
+        ));
+    }
+
+    let wis: Vec<_> = if let Some(joints) = reader.read_joints(0) {
+        let weights = reader
+            .read_weights(0)
+            .ok_or_else(|| missing_attribute("weights"))?;
+        joints
+            .into_u16()
+            .zip(weights.into_u8())
+            .map(|(joints, weights)| {
+                let mut indices = [0_u8; 4];
+                for (index, joint) in indices.iter_mut().zip(joints) {
+                    *index = joint
+                        .try_into()
+                        .map_err(|_| ConvertError::JointIndexOverflow(location()))?;
+                }
+                Ok(v3mc::WeightIndexArray { weights, indices })
+            })
+            .collect::<ConvertResult<Vec<_>>>()?
+    } else {
+        vec![v3mc::WeightIndexArray::default(); nv]
+    };
+    if wis.len() != nv {
+        return Err(ConvertError::AttributeCountMismatch {
+            location: location(),
+            attribute: "joints",
+            count: wis.len(),
+            expected: nv,
+        });
+    }
+
+    let mut chunk_data = v3mc::MeshChunkData {
+        vecs,
+        norms,
+        uvs,
+        faces,
+        face_planes: Vec::new(),
+        same_pos_vertex_offsets: vec![0; nv],
+        wi: wis,
+    };
+
+    if let Some(crease_angle) = ctx.options.crease_angle {
+        let num_split = smoothing::apply_crease_angle(&mut chunk_data, crease_angle)
+            .ok_or_else(|| ConvertError::VertexIndexOverflow(location()))?;
+        fixes.push(format!(
+            "{}: recomputed normals using crease angle {} degrees ({} vertices added)",
+            location(),
+            crease_angle,
+            num_split
+        ));
+        // splitting adds vertices so limits checked before conversion may no longer hold
+        let errors = check_chunk_geometry_limits(
+            &location().to_string(),
+            chunk_data.vecs.len(),
+            chunk_data.faces.len() * 3,
+            &ctx.options.limits,
+        );
+        if !errors.is_empty() {
+            return Err(ConvertError::Validation(errors));
+        }
+    }
+
+    if !is_character && ctx.options.face_planes {
+        let vecs = &chunk_data.vecs;
+        chunk_data.face_planes = chunk_data
+            .faces
+            .par_iter()
+            .map(|face| face.vindices.map(usize::from))
+            .map(|[i, j, k]| compute_triangle_plane(&vecs[i], &vecs[j], &vecs[k]))
+            .collect();
+    }
+
+    Ok((chunk_data, fixes))
+}
+
+fn create_mesh_data_block(
+    mesh: &gltf::Mesh,
+    chunks_data: Vec<v3mc::MeshChunkData>,
+    mesh_materials: &[gltf::Material],
+    prop_points: &[v3mc::PropPoint],
+) -> v3mc::MeshDataBlock {
+    v3mc::MeshDataBlock {
+        chunks: get_rendered_primitives(mesh)
+            .iter()
+            .map(|(_, prim)| create_mesh_chunk_info(prim, mesh_materials))
+            .collect(),
+        chunks_data,
+        prop_points: prop_points.to_vec(),
+    }
+}
+
+fn create_mesh_chunk(
+    node: &gltf::Node,
+    index: usize,
+    prim: &gltf::Primitive,
+    chunk_data: &mut v3mc::MeshChunkData,
+    ctx: &Context,
+) -> ConvertResult<v3mc::MeshChunk> {
+    let location = || Location::primitive(Some(node), index, prim);
+    if prim.mode() != gltf::mesh::Mode::Triangles {
+        return Err(ConvertError::UnsupportedPrimitiveMode(location()));
+    }
+    let render_mode = material::compute_render_mode_for_material(
+        &prim.material(),
+        &location().to_string(),
+        &mut chunk_data.uvs,
+        ctx,
+    );
+    // counts are taken from the converted data because it can be simplified (see auto LOD)
+    new_mesh_chunk(chunk_data.vecs.len(), chunk_data.faces.len(), render_mode)
+        .ok_or_else(|| ConvertError::ChunkTooBig(location()))
+}
+
+pub(crate) fn check_chunk_geometry_limits(
+    location: &str,
+    vertex_count: usize,
+    index_count: usize,
+    limits: &Limits,
+) -> Vec<String> {
+    let vertex_limit = limits.max_vertices;
+    let index_limit = limits.max_indices;
+
+    debug!(
+        "{}: vertices {}/{}, indices {}/{}",
+        location, vertex_count, vertex_limit, index_count, index_limit
+    );
+
+    let mut errors = Vec::new();
+    if env::var("IGNORE_GEOMETRY_LIMITS").is_err() {
+        if vertex_count > vertex_limit {
+            errors.push(format!(
+                "{}: too many vertices: {} (limit {})",
+                location, vertex_count, vertex_limit
+            ));
+        }
+        if index_count > index_limit {
+            errors.push(format!(
+                "{}: too many indices: {} (limit {})",
+                location, index_count, index_limit
+            ));
+        }
+    }
+    errors
+}
+
+fn check_primitive_attributes(prim: &gltf::Primitive, location: &str, ctx: &Context) {
+    use gltf::Semantic;
+    if prim.mode() != gltf::mesh::Mode::Triangles {
+        ctx.report_error(format!(
+            "{}: only triangle list primitives are supported (found {:?})",
+            location,
+            prim.mode()
+        ));
+    }
+    if prim.indices().is_none() {
+        ctx.report_error(format!(
+            "{}: not indexed geometry is not supported",
+            location
+        ));
+    }
+    if prim.get(&Semantic::Positions).is_none() {
+        ctx.report_error(format!("{}: mesh has no positions", location));
+    }
+    if prim.get(&Semantic::Normals).is_none() {
+        ctx.report_error(format!("{}: mesh has no normals", location));
+    }
+    if prim.get(&Semantic::Joints(0)).is_some() && prim.get(&Semantic::Weights(0)).is_none() {
+        ctx.report_error(format!("{}: mesh has joints but no weights", location));
+    }
+}
+
+fn check_mesh(node: &gltf::Node, ctx: &Context) {
+    let mesh = node.mesh().unwrap();
+    let materials = get_mesh_materials(&mesh);
+    if materials.len() > ctx.options.limits.max_textures {
+        ctx.report_error(format!(
+            "{}: found {} materials in a submesh but only {} are allowed",
+            describe_node(node),
+            materials.len(),
+            ctx.options.limits.max_textures
+        ));
+    }
+    for (i, prim) in get_rendered_primitives(&mesh) {
+        let location = describe_primitive(node, i, &prim);
+        check_primitive_attributes(&prim, &location, ctx);
+        let vertex_count = get_primitive_vertex_count(&prim);
+        let index_count = prim.indices().map_or(0, |a| a.count());
+        // checked even if geometry limits are ignored because V3M faces use 16-bit indices
+        if vertex_count > MAX_INDEXED_VERTICES {
+            ctx.report_error(format!(
+                "{}: {} vertices cannot be addressed by 16-bit indices (max {}) - split the primitive",
+                location, vertex_count, MAX_INDEXED_VERTICES
+            ));
+        }
+        for error in
+            check_chunk_geometry_limits(&location, vertex_count, index_count, &ctx.options.limits)
+        {
+            ctx.report_error(error);
+        }
+    }
+}
+
+/// Checks all submeshes (including LOD meshes) and the skeleton against engine limits and supported geometry
+/// layout so all problems can be reported at once
+fn check_limits(doc: &gltf::Document, ctx: &Context) -> ConvertResult<()> {
+    for node in get_submesh_nodes(doc) {
+        check_mesh(&node, ctx);
+        for child in node.children().filter(|n| n.mesh().is_some()) {
+            check_mesh(&child, ctx);
+        }
+    }
+    if let Some(skin) = doc.skins().next() {
+        char_anim::check_bones_limit(&skin, ctx);
+    }
+    ctx.check_errors()
+}
+
+/// Returns `None` if vertex or triangle data is too big to be described by the chunk
+pub(crate) fn new_mesh_chunk(
+    vertex_count: usize,
+    tri_count: usize,
+    render_mode: u32,
+) -> Option<v3mc::MeshChunk> {
+    let num_vecs = vertex_count.try_into().ok()?;
+    let num_faces = tri_count.try_into().ok()?;
+    let vecs_alloc = (vertex_count * 3 * 4).try_into().ok()?;
+    let faces_alloc = (tri_count * 4 * 2).try_into().ok()?;
+    let same_pos_vertex_offsets_alloc = (vertex_count * 2).try_into().ok()?;
+    let wi_alloc = (vertex_count * 2 * 4).try_into().ok()?;
+    let uvs_alloc = (vertex_count * 2 * 4).try_into().ok()?;
+    Some(v3mc::MeshChunk {
+        num_vecs,
+        num_faces,
+        vecs_alloc,
+        faces_alloc,
+        same_pos_vertex_offsets_alloc,
+        wi_alloc,
+        uvs_alloc,
+        render_mode,
+    })
+}
+
+fn convert_mesh(
+    node: &gltf::Node,
+    lod_mesh_materials: &[gltf::Material],
+    prop_points: &[v3mc::PropPoint],
+    mut chunks_data: Vec<v3mc::MeshChunkData>,
+    lod_textures: &LodTextures,
+    ctx: &Context,
+) -> ConvertResult<v3mc::Mesh> {
+    let mesh = node.mesh().unwrap();
+    let mut flags = if ctx.is_character {
+        v3mc::VIF_MESH_FLAG_CHARACTER
+    } else if ctx.options.face_planes {
+        v3mc::VIF_MESH_FLAG_FACE_PLANES
+    } else {
+        0
+    };
+    let num_vecs = chunks_data.iter().map(|c| c.vecs.len()).sum::<usize>() as i32;
+
+    let materials: Vec<_> = get_mesh_materials(&mesh);
+    if materials
+        .iter()
+        .any(|m| material::get_material_preset(m, ctx) == Some(MaterialPreset::Chrome))
+    {
+        // enables environment mapping of the mesh
+        flags |= v3mc::VIF_MESH_FLAG_REFLECTION;
+    }
+
+    let chunks = get_rendered_primitives(&mesh)
+        .into_iter()
+        .zip(&mut chunks_data)
+        .map(|((i, prim), chunk_data)| create_mesh_chunk(node, i, &prim, chunk_data, ctx))
+        .collect::<ConvertResult<Vec<_>>>()?;
+
+    let data_block = create_mesh_data_block(&mesh, chunks_data, &materials, prop_points);
+    let tex_refs: Vec<_> = materials
+        .iter()
+        .map(|m| create_mesh_material_ref(m, lod_mesh_materials, lod_textures, ctx))
+        .collect::<ConvertResult<_>>()?;
+
+    Ok(v3mc::Mesh {
+        flags,
+        num_vecs,
+        chunks,
+        data_block,
+        textures: tex_refs,
+    })
+}
+
+/// Returns rotation that turns Z axis (RF prop point direction) into the given axis
+fn get_prop_axis_rotation(axis: PropAxis) -> glam::Quat {
+    use std::f32::consts::{FRAC_PI_2, PI};
+    match axis {
+        PropAxis::X => glam::Quat::from_rotation_y(FRAC_PI_2),
+        PropAxis::NegX => glam::Quat::from_rotation_y(-FRAC_PI_2),
+        PropAxis::Y => glam::Quat::from_rotation_x(-FRAC_PI_2),
+        PropAxis::NegY => glam::Quat::from_rotation_x(FRAC_PI_2),
+        PropAxis::Z => glam::Quat::IDENTITY,
+        PropAxis::NegZ => glam::Quat::from_rotation_y(PI),
+    }
+}
+
+/// Converts a node into a prop point. `transform` is the transform of the node parent relative to the mesh origin
+fn convert_prop_point(
+    node: &gltf::Node,
+    transform: &glam::Mat4,
+    parent_index: i32,
+    ctx: &Context,
+) -> ConvertResult<v3mc::PropPoint> {
+    let local_transform = get_node_local_transform(node);
+    let (_scale, rotation, translation) = transform
+        .mul(local_transform)
+        .to_scale_rotation_translation();
+    // scale is dropped so rotation has to be normalized again
+    let rotation = (rotation * get_prop_axis_rotation(ctx.options.prop_axis)).normalize();
+
+    let name = node
+        .name()
+        .ok_or_else(|| ConvertError::MissingName(Location::node(node)))?;
+    Ok(v3mc::PropPoint {
+        name: names::to_ascii_name(name, &describe_node(node), ctx),
+        orient: gltf_to_rf_quat(rotation.into()),
+        pos: gltf_to_rf_vec(translation.into()),
+        parent_index,
+    })
+}
+
+/// Converts nodes without mesh below `parent` into prop points. Nested nodes are converted too and their parent
+/// transforms are applied. `parent_index` is the index of the bone prop points are attached to (-1 if none).
+fn collect_prop_points(
+    parent: &gltf::Node,
+    transform: &glam::Mat4,
+    parent_index: i32,
+    prop_points: &mut Vec<v3mc::PropPoint>,
+    ctx: &Context,
+) -> ConvertResult<()> {
+    for node in parent
+        .children()
+        .filter(|n| n.mesh().is_none() && !is_csphere(n))
+    {
+        prop_points.push(convert_prop_point(&node, transform, parent_index, ctx)?);
+        let node_transform = transform.mul(get_node_local_transform(&node));
+        collect_prop_points(&node, &node_transform, parent_index, prop_points, ctx)?;
+    }
+    Ok(())
+}
+
+fn get_prop_points(
+    parent: &gltf::Node,
+    transform: &glam::Mat4,
+    ctx: &Context,
+) -> ConvertResult<Vec<v3mc::PropPoint>> {
+    let mut prop_points = Vec::new();
+    collect_prop_points(parent, transform, -1, &mut prop_points, ctx)?;
+    if let Some(skin) = parent.skin() {
+        // prop points attached to bones are stored relative to the bone so they follow its animation
+        for (node, parent_index) in char_anim::get_nodes_parented_to_bones(&skin)
+            .filter(|(node, _)| node.mesh().is_none() && !is_csphere(node))
+            .filter(|(node, _)| node.name().is_some())
+        {
+            prop_points.push(convert_prop_point(
+                &node,
+                &glam::Mat4::IDENTITY,
+                parent_index,
+                ctx,
+            )?);
+            let node_transform = get_node_local_transform(&node);
+            collect_prop_points(&node, &node_transform, parent_index, &mut prop_points, ctx)?;
+        }
+    }
+    debug!("Found {} prop points", prop_points.len());
+    Ok(prop_points)
+}
+
+fn is_csphere(node: &gltf::Node) -> bool {
+    node.mesh().is_none() && node.name().unwrap_or_default().starts_with("csphere_")
+}
+
+fn convert_cspheres(doc: &gltf::Document, ctx: &Context) -> Vec<v3mc::ColSphere> {
+    let mut cspheres = doc
+        .nodes()
+        .filter(is_csphere)
+        .map(|n| convert_csphere(&n, -1, ctx))
+        .collect::<Vec<_>>();
+    if let Some(skin) = doc.skins().next() {
+        cspheres.extend(
+            char_anim::get_nodes_parented_to_bones(&skin)
+                .filter(|(node, _)| is_csphere(node))
+                .filter(|(node, _)| node.name().is_some())
+                .map(|(node, parent_index)| convert_csphere(&node, parent_index, ctx)),
+        );
+    }
+    cspheres
+}
+
+fn convert_csphere(node: &gltf::Node, parent_index: i32, ctx: &Context) -> v3mc::ColSphere {
+    let name = names::to_ascii_name(
+        node.name().expect("csphere name is missing"),
+        &describe_node(node),
+        ctx,
+    );
+    debug!("Processing csphere: node #{} '{}'", node.index(), name);
+    let transform = get_node_local_transform(node);
+    let (scale, _rotation, translation) = transform.to_scale_rotation_translation();
+    let radius = scale.max_element();
+    v3mc::ColSphere {
+        name,
+        parent_index,
+        pos: gltf_to_rf_vec(translation.into()),
+        radius,
+    }
+}
+
+fn convert_lod_meshes(doc: &gltf::Document, ctx: &Context) -> ConvertResult<Vec<v3mc::LodMesh>> {
+    let submesh_nodes: Vec<_> = get_submesh_nodes(doc)
+        .into_iter()
+        .filter(|n| !skip_empty_mesh_node(n, ctx))
+        .collect();
+    let mut lod_meshes = Vec::with_capacity(submesh_nodes.len());
+    if submesh_nodes.is_empty() {
+        ctx.warn(WarningKind::NoMeshes, "Found no LOD groups");
+    }
+    warn_about_ignored_mesh_nodes(doc, ctx);
+    let names = names::assign_submesh_names(&submesh_nodes, ctx);
+    let lod_nodes: Vec<_> = submesh_nodes
+        .iter()
+        .map(|n| find_lod_nodes(n, ctx))
+        .collect();
+    let num_prims = lod_nodes
+        .iter()
+        .flatten()
+        .map(|(n, _)| get_rendered_primitives(&n.mesh().unwrap()).len())
+        .sum();
+    // geometry of every LOD group is independent so it is processed in parallel
+    ctx.progress
+        .start("Processing geometry", submesh_nodes.len());
+    let geometry = submesh_nodes
+        .par_iter()
+        .zip(lod_nodes.par_iter())
+        .map(|(n, lods)| compute_lod_geometry(n, lods, ctx))
+        .collect::<ConvertResult<Vec<_>>>()?;
+    ctx.progress.advance(submesh_nodes.len(), "");
+    ctx.progress.finish();
+    ctx.progress.start("Converting primitives", num_prims);
+    for (((n, name), lods), mut geometry) in
+        submesh_nodes.iter().zip(names).zip(lod_nodes).zip(geometry)
+    {
+        ctx.check_cancelled()?;
+        // fixes found in the parallel phase are recorded here so they are reported for the right node
+        for fix in std::mem::take(&mut geometry.fixes) {
+            ctx.record_fix(fix);
+        }
+        let lod_mesh = convert_lod_mesh(n, name, lods, geometry, ctx)?;
+        ctx.add_node_report(NodeReport::from_lod_mesh(&lod_mesh));
+        lod_meshes.push(lod_mesh);
+    }
+    ctx.progress.finish();
+    Ok(lod_meshes)
+}
+
+fn warn_about_ignored_mesh_nodes(doc: &gltf::Document, ctx: &Context) {
+    // Only top-level mesh nodes and their direct mesh children (LODs) are exported
+    for parent in doc.nodes() {
+        for child in parent.children().filter(|n| n.mesh().is_some()) {
+            if parent.mesh().is_none() {
+                ctx.warn(
+                    WarningKind::IgnoredHierarchy,
+                    &format!(
+                        "Mesh {} is ignored because its parent {} has no mesh",
+                        describe_node(&child),
+                        describe_node(&parent)
+                    ),
+                );
+            }
+        }
+    }
+}
+
+/// Geometry of a LOD group that does not depend on other nodes so it can be computed in parallel
+struct LodGeometry {
+    bbox: gltf::mesh::BoundingBox,
+    radius: f32,
+    /// Chunk data of every LOD mesh
+    chunks_data: Vec<Vec<v3mc::MeshChunkData>>,
+    /// Switch distance of every LOD mesh (computed if not defined in node extras)
+    distances: Vec<f32>,
+    /// Distance and chunk data of every generated LOD mesh (see [`ConvertOptions::auto_lod`])
+    auto_lods: Vec<(f32, Vec<v3mc::MeshChunkData>)>,
+    /// Fixes applied to primitives of the LOD group
+    fixes: Vec<String>,
+}
+
+fn get_lod_group_transform(node: &gltf::Node) -> (Vector3, Matrix3) {
+    let node_transform =
+        glam::Mat4::from_cols_array_2d(&node.transform().matrix()).to_cols_array_2d();
+    extract_translation_from_matrix(&node_transform)
+}
+
+fn count_triangles(chunks_data: &[v3mc::MeshChunkData]) -> usize {
+    chunks_data.iter().map(|c| c.faces.len()).sum()
+}
+
+/// Computes switch distance of a LOD mesh. Projected area of a mesh falls with the square of the distance so a LOD
+/// with N times fewer triangles keeps the on-screen triangle density of the most detailed mesh at sqrt(N) times the
+/// distance. Distances grow with the mesh size and can be tuned by [`ConvertOptions::lod_distance_scale`].
+fn compute_lod_distance(
+    radius: f32,
+    base_triangles: usize,
+    lod_triangles: usize,
+    ctx: &Context,
+) -> f32 {
+    let density_ratio = base_triangles as f32 / lod_triangles.max(1) as f32;
+    let distance = radius * LOD_DISTANCE_RADIUS_FACTOR * (density_ratio.sqrt() - 1.0).max(0.0);
+    distance * ctx.options.lod_distance_scale
+}
+
+fn compute_lod_geometry(
+    node: &gltf::Node,
+    lod_nodes: &[(gltf::Node, Option<f32>)],
+    ctx: &Context,
+) -> ConvertResult<LodGeometry> {
+    let (_, rot_scale_mat) = get_lod_group_transform(node);
+    let chunks_data_and_fixes: Vec<_> = lod_nodes
+        .par_iter()
+        .map(|(n, _)| {
+            let prims = get_rendered_primitives(&n.mesh().unwrap());
+            prims
+                .par_iter()
+                .map(|(i, prim)| {
+                    ctx.check_cancelled()?;
+                    create_mesh_chunk_data(n, *i, prim, &rot_scale_mat, ctx)
+                })
+                .collect::<ConvertResult<Vec<_>>>()
+        })
+        .collect::<ConvertResult<Vec<_>>>()?;
+    let mut fixes = Vec::new();
+    let chunks_data: Vec<Vec<_>> = chunks_data_and_fixes
+        .into_iter()
+        .map(|lod_chunks| {
+            lod_chunks
+                .into_iter()
+                .map(|(chunk_data, chunk_fixes)| {
+                    fixes.extend(chunk_fixes);
+                    chunk_data
+                })
+                .collect()
+        })
+        .collect();
+    // bounds of the LOD group are based on the LOD group node mesh which was converted above
+    let lod_group_index = lod_nodes
+        .iter()
+        .position(|(n, _)| n.index() == node.index())
+        .unwrap();
+    let (bbox, radius) = compute_bounds(&chunks_data[lod_group_index]);
+    let base_triangles = count_triangles(&chunks_data[lod_group_index]);
+    let distances = lod_nodes
+        .iter()
+        .zip(&chunks_data)
+        .map(|((n, dist_opt), lod_chunks_data)| {
+            dist_opt.unwrap_or_else(|| {
+                let lod_triangles = count_triangles(lod_chunks_data);
+                let dist = compute_lod_distance(radius, base_triangles, lod_triangles, ctx);
+                ctx.warn(
+                    WarningKind::MissingLodDistance,
+                    &format!(
+                        "Expected LOD_distance in child {} - using computed distance {:.1}",
+                        describe_node(n),
+                        dist
+                    ),
+                );
+                dist
+            })
+        })
+        .collect();
+    // LODs are only generated if the artist did not model them
+    let auto_lods = if lod_nodes.len() == 1 {
+        generate_auto_lods(&chunks_data[lod_group_index], radius, ctx)?
+    } else {
+        if !ctx.options.auto_lod.is_empty() {
+            debug!(
+                "Not generating LODs for {} because it already has LOD meshes",
+                describe_node(node)
+            );
+        }
+        Vec::new()
+    };
+    Ok(LodGeometry {
+        bbox,
+        radius,
+        chunks_data,
+        distances,
+        auto_lods,
+        fixes,
+    })
+}
+
+fn generate_auto_lods(
+    chunks_data: &[v3mc::MeshChunkData],
+    radius: f32,
+    ctx: &Context,
+) -> ConvertResult<Vec<(f32, Vec<v3mc::MeshChunkData>)>> {
+    let base_triangles = count_triangles(chunks_data);
+    let mut ratios = ctx.options.auto_lod.clone();
+    // most detailed LODs come first
+    ratios.sort_by(|a, b| b.total_cmp(a));
+    ratios.dedup();
+    ratios
+        .into_iter()
+        .map(|ratio| {
+            ctx.check_cancelled()?;
+            let lod_chunks_data: Vec<_> = chunks_data
+                .par_iter()
+                .map(|c| decimate_chunk(c, ratio))
+                .collect();
+            let lod_triangles = count_triangles(&lod_chunks_data);
+            let dist = compute_lod_distance(radius, base_triangles, lod_triangles, ctx);
+            Ok((dist, lod_chunks_data))
+        })
+        .collect()
+}
+
+fn convert_lod_mesh(
+    node: &gltf::Node,
+    name: String,
+    lod_nodes: Vec<(gltf::Node, Option<f32>)>,
+    geometry: LodGeometry,
+    ctx: &Context,
+) -> ConvertResult<v3mc::LodMesh> {
+    debug!("Processing LOD group: node #{} '{}'", node.index(), name);
+    if node.name() != Some(name.as_str()) {
+        ctx.record_fix(format!("{}: renamed to '{}'", describe_node(node), name));
+    }
+
+    let parent_name = "None".to_string();
+    let version = v3mc::MeshDataBlock::VERSION;
+    // distances are known only after geometry is processed so LODs are ordered here
+    let mut lods: Vec<_> = lod_nodes
+        .into_iter()
+        .map(|(n, _)| n)
+        .zip(geometry.distances)
+        .zip(geometry.chunks_data)
+        .collect();
+    lods.sort_by(|((_, a), _), ((_, b), _)| a.total_cmp(b));
+    let distances = lods
+        .iter()
+        .map(|((_, dist), _)| *dist)
+        .chain(geometry.auto_lods.iter().map(|(dist, _)| *dist))
+        .collect();
+    let (origin, rot_scale_mat) = get_lod_group_transform(node);
+
+    let (bbox_min, bbox_max) = (geometry.bbox.min, geometry.bbox.max);
+    let offset = gltf_to_rf_vec(origin);
+    let radius = geometry.radius;
+
+    let transform = glam::Mat4::from_mat3(glam::Mat3::from_cols_array_2d(&rot_scale_mat));
+    let prop_points = get_prop_points(node, &transform, ctx)?;
+
+    let mut gltf_materials: Vec<_> = lods
+        .iter()
+        .flat_map(|((n, _), _)| get_mesh_materials(&n.mesh().unwrap()))
+        .collect();
+    gltf_materials.dedup_by_key(|m| m.index());
+    let materials: Vec<_> = gltf_materials
+        .iter()
+        .map(|m| convert_material(m, ctx))
+        .collect();
+
+    let mut meshes: Vec<_> = Vec::with_capacity(lods.len() + geometry.auto_lods.len());
+    for (i, ((n, d), chunks_data)) in lods.into_iter().enumerate() {
+        debug!(
+            "Processing LOD{} mesh: node #{} '{}', distance {}",
+            i,
+            n.index(),
+            n.name().unwrap_or("<unnamed>"),
+            d
+        );
+        for (j, prim) in n.mesh().unwrap().primitives().enumerate() {
+            if is_collision_only(&prim) {
+                ctx.record_fix(format!(
+                    "{}: excluded collision-only primitive from rendered geometry",
+                    describe_primitive(&n, j, &prim)
+                ));
+            } else if prim.get(&gltf::Semantic::TexCoords(0)).is_none() {
+                ctx.record_fix(format!(
+                    "{}: generated missing texture coordinates",
+                    describe_primitive(&n, j, &prim)
+                ));
+            }
+        }
+        let extras = get_node_extras::<NodeExtras>(&n);
+        let lod_textures = LodTextures {
+            lod_index: i,
+            overrides: &extras.lod_textures,
+        };
+        let mesh = convert_mesh(
+            &n,
+            &gltf_materials,
+            &prop_points,
+            chunks_data,
+            &lod_textures,
+            ctx,
+        )?;
+        ctx.progress.advance(
+            mesh.chunks.len(),
+            &format!(
+                "{} ({} vertices)",
+                n.name().unwrap_or("<unnamed>"),
+                mesh.num_vecs
+            ),
+        );
+        meshes.push(mesh);
+    }
+    let lod_offset = meshes.len();
+    for (i, (d, chunks_data)) in geometry.auto_lods.into_iter().enumerate() {
+        debug!(
+            "Processing generated LOD{} mesh: distance {}",
+            lod_offset + i,
+            d
+        );
+        // generated LODs use the same primitives and materials as the LOD group node
+        let lod_textures = LodTextures {
+            lod_index: lod_offset + i,
+            overrides: &BTreeMap::new(),
+        };
+        meshes.push(convert_mesh(
+            node,
+            &gltf_materials,
+            &prop_points,
+            chunks_data,
+            &lod_textures,
+            ctx,
+        )?);
+    }
+
+    let unknown1 = get_node_extras::<NodeExtras>(node)
+        .unknown1
+        .unwrap_or_else(|| v3mc::LodMeshUnknown1::default_table(&name));
+    Ok(v3mc::LodMesh {
+        unknown1,
+        name,
+        parent_name,
+        version,
+        distances,
+        offset,
+        radius,
+        bbox_min,
+        bbox_max,
+        meshes,
+        materials,
+    })
+}
+
+pub fn convert_gltf_to_v3mc(doc: &gltf::Document, ctx: &Context) -> ConvertResult<v3mc::File> {
+    if doc.skins().count() > 1 {
+        ctx.warn(
+            WarningKind::MultipleSkins,
+            "There is more than one skin defined. Only first skin will be used.",
+        );
+    }
+
+    extras::validate_extras(doc, ctx);
+    check_limits(doc, ctx)?;
+    let lod_meshes = convert_lod_meshes(doc, ctx)?;
+    let mut cspheres = convert_cspheres(doc, ctx);
+    let bones = if let Some(skin) = doc.skins().next() {
+        char_anim::convert_bones(&skin, ctx)?
+    } else {
+        Vec::new()
+    };
+    if ctx.options.auto_cspheres {
+        if let Some(skin) = doc.skins().next().filter(|_| cspheres.is_empty()) {
+            cspheres = char_anim::generate_cspheres(&skin, &bones);
+            info!(
+                "Generated {} collision spheres from the skeleton",
+                cspheres.len()
+            );
+        } else {
+            info!("Collision spheres are not generated: input has no skin or has csphere nodes");
+        }
+    }
+
+    Ok(v3mc::File {
+        header: create_v3mc_file_header(&lod_meshes, &cspheres, ctx.is_character),
+        lod_meshes,
+        cspheres,
+        bones,
+    })
+}
+
+/// Splits static mesh file into files containing a single submesh each (e.g. to be placed in a level as separate
+/// props). Submesh offsets are cleared so every file has its origin at the node origin.
+pub fn split_v3mc_file(file: v3mc::File) -> Vec<v3mc::File> {
+    file.lod_meshes
+        .into_iter()
+        .map(|lod_mesh| {
+            let lod_meshes = vec![v3mc::LodMesh {
+                offset: [0.0; 3],
+                ..lod_mesh
+            }];
+            v3mc::File {
+                header: create_v3mc_file_header(&lod_meshes, &[], false),
+                lod_meshes,
+                cspheres: Vec::new(),
+                bones: Vec::new(),
+            }
+        })
+        .collect()
+}
+
+/// Converts GLB file (or GLTF with embedded buffers) from memory and returns serialized V3M file (V3C if the model
+/// is skinned). It does not access filesystem.
+pub fn convert_glb_to_v3mc(data: &[u8], options: ConvertOptions) -> ConvertResult<Vec<u8>> {
+    convert_glb_to_v3mc_with_progress(data, options, NoProgress)
+}
+
+/// Same as [`convert_glb_to_v3mc`] but reports progress of every stage (importing, converting and writing) to
+/// `progress`
+pub fn convert_glb_to_v3mc_with_progress<P: ProgressListener + 'static>(
+    data: &[u8],
+    options: ConvertOptions,
+    progress: P,
+) -> ConvertResult<Vec<u8>> {
+    progress.start("Importing", 1);
+    let (document, buffers) = import_gltf_slice(data)?;
+    progress.advance(1, "");
+    progress.finish();
+    let is_character = document.skins().next().is_some();
+    let ctx = Context::new(buffers, is_character, options).with_progress(progress);
+    let file = convert_gltf_to_v3mc(&document, &ctx)?;
+    ctx.check_errors()?;
+    ctx.progress.start("Writing", 1);
+    let mut wrt = Cursor::new(Vec::new());
+    file.write(&mut wrt)?;
+    ctx.progress.advance(1, "");
+    ctx.progress.finish();
+    Ok(wrt.into_inner())
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct NodeExtras {
+    #[serde(rename = "LOD_distance")]
+    lod_distance: Option<f32>,
+    /// Textures used by this LOD instead of the full resolution textures (e.g. `{"wall.tga": "wall_lo.tga"}`)
+    #[serde(rename = "LOD_textures", default)]
+    lod_textures: BTreeMap<String, String>,
+    /// Entries of the table stored after submesh materials (see [`v3mc::LodMeshUnknown1`])
+    #[serde(rename = "V3M_unknown1")]
+    unknown1: Option<Vec<v3mc::LodMeshUnknown1>>,
+}
+
+pub(crate) fn get_node_extras<'a, T: serde::Deserialize<'a> + Default>(node: &'a gltf::Node) -> T {
+    node.extras()
+        .as_ref()
+        .and_then(|raw| serde_json::from_str::<T>(raw.get()).ok())
+        .unwrap_or_default()
+}
+
+/// Returns the LOD group node followed by its LOD children with distances from node extras (`None` if a child has no
+/// distance defined)
+/// Checks if mesh of the node has no vertices. Such nodes are skipped because they have no bounds. Returns `true` if
+/// the node should be skipped.
+fn skip_empty_mesh_node(node: &gltf::Node, ctx: &Context) -> bool {
+    let mesh = node.mesh().unwrap();
+    let rendered_prims = get_rendered_primitives(&mesh);
+    let is_empty = rendered_prims
+        .iter()
+        .all(|(_, p)| get_primitive_vertex_count(p) == 0);
+    if is_empty {
+        let reason = if rendered_prims.len() < mesh.primitives().len() {
+            "mesh has only collision-only primitives"
+        } else {
+            "mesh has no vertices"
+        };
+        ctx.warn(
+            WarningKind::EmptyMesh,
+            &format!("{}: {} - node is skipped", describe_node(node), reason),
+        );
+    }
+    is_empty
+}
+
+fn find_lod_nodes<'a>(node: &'a gltf::Node, ctx: &Context) -> Vec<(gltf::Node<'a>, Option<f32>)> {
+    let children = node
+        .children()
+        .filter(|n| n.mesh().is_some())
+        .filter(|n| !skip_empty_mesh_node(n, ctx))
+        .map(|n| {
+            let dist_opt = get_node_extras::<NodeExtras>(&n).lod_distance;
+            (n, dist_opt)
+        });
+    iter::once((node.clone(), Some(0_f32)))
+        .chain(children)
+        .collect()
+}
@@ -0,0 +1,53 @@
+use serde_derive::Deserialize;
+
+/// Classes of problems that do not stop the conversion by default but can be turned into errors in strict mode
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[cfg_attr(feature = "clap", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum WarningKind {
+    /// Material has no base color texture and default texture is used
+    MissingTexture,
+    /// Texture wrapping mode is not supported (mirrored or different for S and T)
+    WrapMode,
+    /// Mesh node is ignored because of its position in node hierarchy
+    IgnoredHierarchy,
+    /// Child mesh node has no LOD_distance property (distance is computed automatically)
+    MissingLodDistance,
+    /// More than one skin is defined
+    MultipleSkins,
+    /// Animation uses unsupported channels (e.g. scale)
+    AnimChannel,
+    /// Light type is not supported
+    UnsupportedLight,
+    /// Input has no meshes
+    NoMeshes,
+    /// Extras (custom properties) have unknown keys or invalid values
+    InvalidExtras,
+    /// Texture coordinates are outside of 0-1 range but texture is clamped
+    ClampedUvRange,
+    /// Material uses a texture that cannot be stored in the output format (e.g. ambient occlusion)
+    UnsupportedTexture,
+    /// Material setting cannot be stored in the output format (e.g. UV scrolling of meshes)
+    UnsupportedMaterial,
+    /// Material base color factor is not white so its tint is lost
+    BaseColorFactor,
+    /// Submesh name is too long or used by another submesh and it was changed
+    SubmeshName,
+    /// Bone name is too long or used by another bone and it was changed
+    BoneName,
+    /// Inverse bind matrix of a joint is slightly scaled or skewed and it was orthonormalized
+    BindMatrix,
+    /// Node, material or texture name contains non-ASCII characters and it was converted to ASCII
+    NonAsciiName,
+    /// Mesh has no vertices so its node is skipped
+    EmptyMesh,
+    /// Skeleton has no left/right bone pairs so mirrored animation only reflects the motion
+    MirrorPairs,
+}
+
+pub fn is_strict(strict: &Option<Vec<WarningKind>>, kind: WarningKind) -> bool {
+    // empty list enables all warning classes
+    strict
+        .as_ref()
+        .is_some_and(|kinds| kinds.is_empty() || kinds.contains(&kind))
+}
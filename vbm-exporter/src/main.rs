@@ -7,7 +7,11 @@ use std::{
 };
 
 use binrw::{BinRead, BinReaderExt, BinWrite, BinWriterExt};
-use clap::Parser;
+use clap::{ArgAction, Parser};
+use log::{Level, LevelFilter};
+
+#[macro_use]
+extern crate log;
 
 type Result<T> = std::result::Result<T, Box<dyn Error>>;
 
@@ -43,14 +47,13 @@ impl Display for VbmColorFormat {
 }
 
 fn print_vbm_metadata(hdr: &VbmHeader) {
-    println!("VBM Metadata:");
-    println!("Version: {}", hdr.version);
-    println!("Size: {}x{}", hdr.width, hdr.height);
-    println!("Format: {}", hdr.format);
-    println!("FPS: {}", hdr.fps);
-    println!("Number of frames: {}", hdr.num_frames);
-    println!("Number of mipmaps: {}", hdr.num_mipmaps);
-    println!();
+    info!("VBM Metadata:");
+    info!("Version: {}", hdr.version);
+    info!("Size: {}x{}", hdr.width, hdr.height);
+    info!("Format: {}", hdr.format);
+    info!("FPS: {}", hdr.fps);
+    info!("Number of frames: {}", hdr.num_frames);
+    info!("Number of mipmaps: {}", hdr.num_mipmaps);
 }
 
 #[derive(BinWrite)]
@@ -153,14 +156,12 @@ fn build_output_filename(prefix: &Path, mipmap_idx: u32, frame_idx: u32) -> Path
     prefix.with_file_name(file_name)
 }
 
-fn export_vbm(vbm_filename: &Path, output_dir: &Path, verbose: bool) -> Result<()> {
+fn export_vbm(vbm_filename: &Path, output_dir: &Path) -> Result<()> {
     let file = File::open(vbm_filename)?;
     let mut vbm_reader = BufReader::new(file);
     let hdr: VbmHeader = vbm_reader.read_le()?;
-    if verbose {
-        println!("Processing {}...", vbm_filename.display());
-        print_vbm_metadata(&hdr);
-    }
+    info!("Processing {}...", vbm_filename.display());
+    print_vbm_metadata(&hdr);
     let prefix = output_dir.join(vbm_filename.file_stem().unwrap());
     let mut pixel_data = vec![0u8; (hdr.width * hdr.height * 2) as usize]; // 16 bit
 
@@ -200,20 +201,43 @@ pub struct Args {
     #[clap(short = 'O', default_value = ".")]
     output_dir: PathBuf,
 
-    /// Verbose output
-    #[clap(short, long)]
-    verbose: bool,
+    /// Enable verbose output. Can be used up to 3 times to increase verbosity
+    #[clap(short, long, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Do not print warnings (errors are still printed)
+    #[clap(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+}
+
+fn init_logger(verbose: u8, quiet: bool) {
+    let level = if quiet {
+        LevelFilter::Error
+    } else {
+        match verbose {
+            0 => LevelFilter::Warn,
+            1 => LevelFilter::Info,
+            2 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        }
+    };
+    env_logger::Builder::new()
+        .filter_module("vbm_exporter", level)
+        .format(|buf, record| match record.level() {
+            Level::Error => writeln!(buf, "Error: {}", record.args()),
+            Level::Warn => writeln!(buf, "Warning! {}", record.args()),
+            _ => writeln!(buf, "{}", record.args()),
+        })
+        .init();
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
-
-    if args.verbose {
-        println!("vbm-exporter {}", env!("CARGO_PKG_VERSION"));
-    }
+    init_logger(args.verbose, args.quiet);
+    info!("vbm-exporter {}", env!("CARGO_PKG_VERSION"));
 
     for input_file in &args.vbm_files {
-        export_vbm(input_file, &args.output_dir, args.verbose)?;
+        export_vbm(input_file, &args.output_dir)?;
     }
 
     Ok(())
@@ -2,7 +2,8 @@ mod tga;
 mod vf;
 
 use binrw::{BinReaderExt, BinWriterExt};
-use clap::Parser;
+use clap::{ArgAction, Parser};
+use log::{Level, LevelFilter};
 use std::{
     error::Error,
     fs::File,
@@ -14,17 +15,19 @@ use vf::{VfCharDesc, VfFormat, VfHeader, VfPalette};
 
 use crate::vf::VfKernPair;
 
+#[macro_use]
+extern crate log;
+
 type Result<T> = std::result::Result<T, Box<dyn Error>>;
 
 fn print_vf_metadata(hdr: &VfHeader) {
-    println!("VF Metadata:");
-    println!("Version: {}", hdr.version);
-    println!("Format: {:?}", hdr.format);
-    println!("First ASCII: {}", hdr.first_ascii);
-    println!("Character size: {}", hdr.default_spacing);
-    println!("Number of chars: {}", hdr.num_chars);
-    println!("Number of kerning pairs: {}", hdr.num_kern_pairs);
-    println!();
+    info!("VF Metadata:");
+    info!("Version: {}", hdr.version);
+    info!("Format: {:?}", hdr.format);
+    info!("First ASCII: {}", hdr.first_ascii);
+    info!("Character size: {}", hdr.default_spacing);
+    info!("Number of chars: {}", hdr.num_chars);
+    info!("Number of kerning pairs: {}", hdr.num_kern_pairs);
 }
 
 fn determine_output_image_size(num_pixels: u32) -> (u32, u32) {
@@ -129,7 +132,7 @@ fn write_font_tga(
 }
 
 fn export_font(vf_filename: &Path, output_dir: &Path) -> Result<()> {
-    println!("Processing {}...", vf_filename.display());
+    info!("Processing {}...", vf_filename.display());
     let file = File::open(vf_filename)?;
     let mut reader = BufReader::new(file);
 
@@ -178,17 +181,40 @@ pub struct Args {
     #[clap(short = 'O', default_value = ".")]
     output_dir: PathBuf,
 
-    /// Verbose output
-    #[clap(short, long)]
-    verbose: bool,
+    /// Enable verbose output. Can be used up to 3 times to increase verbosity
+    #[clap(short, long, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Do not print warnings (errors are still printed)
+    #[clap(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+}
+
+fn init_logger(verbose: u8, quiet: bool) {
+    let level = if quiet {
+        LevelFilter::Error
+    } else {
+        match verbose {
+            0 => LevelFilter::Warn,
+            1 => LevelFilter::Info,
+            2 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        }
+    };
+    env_logger::Builder::new()
+        .filter_module("vf_exporter", level)
+        .format(|buf, record| match record.level() {
+            Level::Error => writeln!(buf, "Error: {}", record.args()),
+            Level::Warn => writeln!(buf, "Warning! {}", record.args()),
+            _ => writeln!(buf, "{}", record.args()),
+        })
+        .init();
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
-
-    if args.verbose {
-        println!("vf-exporter {}", env!("CARGO_PKG_VERSION"));
-    }
+    init_logger(args.verbose, args.quiet);
+    info!("vf-exporter {}", env!("CARGO_PKG_VERSION"));
 
     for input_file in &args.vf_files {
         export_font(input_file, &args.output_dir)?;
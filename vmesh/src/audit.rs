@@ -0,0 +1,277 @@
+//! Aggregate statistics of converted meshes in mod directories and packfiles.
+
+use crate::limits::{format_usage, pass_fail, Limits, LimitsArgs};
+use crate::BoxResult;
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use v3d::v3mc;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct AuditArgs {
+    /// Directories (searched recursively, including packfiles inside) or VPP packfiles to scan
+    #[clap(required = true)]
+    inputs: Vec<PathBuf>,
+
+    /// Number of most used textures to print
+    #[clap(long, default_value_t = 20)]
+    top_textures: usize,
+
+    #[clap(flatten)]
+    limits: LimitsArgs,
+}
+
+const MESH_EXTENSIONS: [&str; 2] = ["v3m", "v3c"];
+const TEXTURE_EXTENSIONS: [&str; 2] = ["tga", "vbm"];
+
+fn has_extension(name: &str, extensions: &[&str]) -> bool {
+    Path::new(name)
+        .extension()
+        .and_then(OsStr::to_str)
+        .is_some_and(|ext| extensions.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+}
+
+/// Statistics of a single mesh file
+struct MeshStats {
+    /// File name prefixed by the packfile name if it is stored in a packfile
+    location: String,
+    size: usize,
+    submeshes: usize,
+    /// Vertices and triangles of the most detailed LODs
+    vertices: usize,
+    triangles: usize,
+    /// Vertices of all LODs
+    all_lod_vertices: usize,
+    textures: Vec<String>,
+    bones: usize,
+    /// Limit violations (chunk geometry, textures per submesh, bones)
+    problems: Vec<String>,
+}
+
+fn analyze_mesh(location: String, data: &[u8], limits: &Limits) -> BoxResult<MeshStats> {
+    let file = v3mc::File::read(&mut Cursor::new(data))?;
+    let mut stats = MeshStats {
+        location,
+        size: data.len(),
+        submeshes: file.lod_meshes.len(),
+        vertices: 0,
+        triangles: 0,
+        all_lod_vertices: 0,
+        textures: Vec::new(),
+        bones: file.bones.len(),
+        problems: Vec::new(),
+    };
+    for lod_mesh in &file.lod_meshes {
+        for (lod_index, mesh) in lod_mesh.meshes.iter().enumerate() {
+            let num_vertices: usize = mesh.chunks.iter().map(|c| usize::from(c.num_vecs)).sum();
+            stats.all_lod_vertices += num_vertices;
+            if lod_index == 0 {
+                stats.vertices += num_vertices;
+                stats.triangles += mesh
+                    .chunks
+                    .iter()
+                    .map(|c| usize::from(c.num_faces))
+                    .sum::<usize>();
+            }
+            if mesh.textures.len() > limits.max_textures {
+                stats.problems.push(format!(
+                    "{} LOD{}: textures {}",
+                    lod_mesh.name,
+                    lod_index,
+                    format_usage(mesh.textures.len(), limits.max_textures)
+                ));
+            }
+            for (chunk_index, chunk) in mesh.chunks.iter().enumerate() {
+                let vertices = usize::from(chunk.num_vecs);
+                let indices = usize::from(chunk.num_faces) * 3;
+                if vertices > limits.max_vertices || indices > limits.max_indices {
+                    stats.problems.push(format!(
+                        "{} LOD{} batch #{}: vertices {}, indices {}",
+                        lod_mesh.name,
+                        lod_index,
+                        chunk_index,
+                        format_usage(vertices, limits.max_vertices),
+                        format_usage(indices, limits.max_indices)
+                    ));
+                }
+            }
+            for texture in &mesh.textures {
+                if !stats
+                    .textures
+                    .iter()
+                    .any(|t| t.eq_ignore_ascii_case(&texture.tex_name))
+                {
+                    stats.textures.push(texture.tex_name.clone());
+                }
+            }
+        }
+    }
+    if stats.bones > limits.max_bones {
+        stats.problems.push(format!(
+            "skeleton: bones {}",
+            format_usage(stats.bones, limits.max_bones)
+        ));
+    }
+    Ok(stats)
+}
+
+#[derive(Default)]
+struct Audit {
+    meshes: Vec<MeshStats>,
+    /// Texture files found (lowercase name -> locations)
+    texture_files: BTreeMap<String, Vec<String>>,
+    /// Files that could not be parsed
+    errors: Vec<String>,
+}
+
+impl Audit {
+    fn add_file(&mut self, location: String, name: &str, data: &[u8], limits: &Limits) {
+        if has_extension(name, &TEXTURE_EXTENSIONS) {
+            self.texture_files
+                .entry(name.to_ascii_lowercase())
+                .or_default()
+                .push(location);
+        } else if has_extension(name, &MESH_EXTENSIONS) {
+            match analyze_mesh(location.clone(), data, limits) {
+                Ok(stats) => self.meshes.push(stats),
+                Err(e) => self.errors.push(format!("{}: {}", location, e)),
+            }
+        }
+    }
+
+    fn scan_packfile(&mut self, path: &Path, limits: &Limits) -> BoxResult<()> {
+        debug!("Scanning packfile {}", path.display());
+        for file in vpp::read_vpp(&path.to_string_lossy())? {
+            let location = format!("{}:{}", path.display(), file.name);
+            self.add_file(location, &file.name, &file.data, limits);
+        }
+        Ok(())
+    }
+
+    fn scan_dir(&mut self, dir: &Path, limits: &Limits) -> BoxResult<()> {
+        debug!("Scanning directory {}", dir.display());
+        let mut paths = std::fs::read_dir(dir)?
+            .map(|e| e.map(|e| e.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        paths.sort();
+        for path in paths {
+            self.scan(&path, limits)?;
+        }
+        Ok(())
+    }
+
+    fn scan(&mut self, path: &Path, limits: &Limits) -> BoxResult<()> {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if path.is_dir() {
+            self.scan_dir(path, limits)
+        } else if has_extension(&name, &["vpp"]) {
+            self.scan_packfile(path, limits)
+        } else if has_extension(&name, &MESH_EXTENSIONS) {
+            let data = std::fs::read(path)?;
+            self.add_file(path.display().to_string(), &name, &data, limits);
+            Ok(())
+        } else {
+            // content of other files is not needed (texture files are only counted by name)
+            self.add_file(path.display().to_string(), &name, &[], limits);
+            Ok(())
+        }
+    }
+
+    fn print(&self, top_textures: usize) {
+        println!("Meshes:");
+        for mesh in &self.meshes {
+            println!(
+                "  [{}] {}: {} submeshes, {} vertices ({} in all LODs), {} triangles, {} textures, {} bones, {} KB",
+                pass_fail(mesh.problems.is_empty()),
+                mesh.location,
+                mesh.submeshes,
+                mesh.vertices,
+                mesh.all_lod_vertices,
+                mesh.triangles,
+                mesh.textures.len(),
+                mesh.bones,
+                mesh.size.div_ceil(1024)
+            );
+            for problem in &mesh.problems {
+                println!("    {}", problem);
+            }
+        }
+
+        let mut texture_usage: BTreeMap<String, (String, usize)> = BTreeMap::new();
+        for texture in self.meshes.iter().flat_map(|m| &m.textures) {
+            texture_usage
+                .entry(texture.to_ascii_lowercase())
+                .or_insert_with(|| (texture.clone(), 0))
+                .1 += 1;
+        }
+        let mut texture_usage: Vec<_> = texture_usage.into_values().collect();
+        texture_usage.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        println!("Most used textures:");
+        for (name, count) in texture_usage.iter().take(top_textures) {
+            println!("  {:40} {} meshes", name, count);
+        }
+
+        let duplicates: Vec<_> = self
+            .texture_files
+            .iter()
+            .filter(|(_, locations)| locations.len() > 1)
+            .collect();
+        if !duplicates.is_empty() {
+            println!("Duplicate texture names (only one of the files is used by the game):");
+            for (name, locations) in duplicates {
+                println!("  {}: {}", name, locations.join(", "));
+            }
+        }
+
+        if !self.errors.is_empty() {
+            println!("Unreadable files:");
+            for error in &self.errors {
+                println!("  {}", error);
+            }
+        }
+
+        let num_failed = self
+            .meshes
+            .iter()
+            .filter(|m| !m.problems.is_empty())
+            .count();
+        println!("Total:");
+        println!(
+            "  Meshes: {} ({} exceeding limits)",
+            self.meshes.len(),
+            num_failed
+        );
+        println!(
+            "  Vertices: {} (most detailed LODs), {} (all LODs)",
+            self.meshes.iter().map(|m| m.vertices).sum::<usize>(),
+            self.meshes
+                .iter()
+                .map(|m| m.all_lod_vertices)
+                .sum::<usize>()
+        );
+        println!(
+            "  Triangles: {}",
+            self.meshes.iter().map(|m| m.triangles).sum::<usize>()
+        );
+        println!("  Distinct textures: {}", texture_usage.len());
+        println!(
+            "  Size: {} KB",
+            self.meshes
+                .iter()
+                .map(|m| m.size)
+                .sum::<usize>()
+                .div_ceil(1024)
+        );
+    }
+}
+
+pub(crate) fn run_audit(args: &AuditArgs) -> BoxResult<()> {
+    let limits = args.limits.resolve()?;
+    let mut audit = Audit::default();
+    for input in &args.inputs {
+        audit.scan(input, &limits)?;
+    }
+    audit.print(args.top_textures);
+    Ok(())
+}
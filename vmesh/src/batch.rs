@@ -0,0 +1,113 @@
+use crate::errors::{categorize_error, CategorizedError};
+use crate::progress::ProgressMode;
+use crate::scene::SceneCost;
+use crate::{do_convert, Args, BoxResult};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+fn is_glob_pattern(s: &str) -> bool {
+    s.contains(['*', '?', '['])
+}
+
+pub(crate) fn expand_input_patterns(patterns: &[String]) -> BoxResult<Vec<PathBuf>> {
+    // Windows shell does not expand wildcards so it is done here
+    let mut files = Vec::new();
+    for pattern in patterns {
+        if !is_glob_pattern(pattern) {
+            files.push(PathBuf::from(pattern));
+            continue;
+        }
+        let num_files_before = files.len();
+        for entry in glob::glob(pattern)? {
+            files.push(entry?);
+        }
+        if files.len() == num_files_before {
+            return Err(format!("no files matching pattern {}", pattern).into());
+        }
+    }
+    Ok(files)
+}
+
+fn determine_num_workers(args: &Args, num_files: usize) -> usize {
+    let num_workers = args.jobs.unwrap_or_else(|| {
+        thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+    });
+    num_workers.clamp(1, num_files.max(1))
+}
+
+pub(crate) fn convert_batch(args: &Args, input_files: &[PathBuf]) -> BoxResult<()> {
+    let num_workers = determine_num_workers(args, input_files.len());
+    let next_index = AtomicUsize::new(0);
+    let failed_files = Mutex::new(Vec::new());
+    let scene_cost = Mutex::new(SceneCost::default());
+    // progress bars of files converted in parallel would overwrite each other
+    let progress = match args.progress {
+        ProgressMode::Auto => ProgressMode::Never,
+        mode => mode,
+    };
+
+    thread::scope(|scope| {
+        for _ in 0..num_workers {
+            scope.spawn(|| loop {
+                let index = next_index.fetch_add(1, Ordering::Relaxed);
+                let Some(input_file) = input_files.get(index) else {
+                    break;
+                };
+                let file_args = Args {
+                    input_file: input_file.clone(),
+                    progress,
+                    ..args.clone()
+                };
+                match do_convert(file_args) {
+                    Ok(output) => {
+                        scene_cost
+                            .lock()
+                            .unwrap()
+                            .add(input_file, &output.node_reports);
+                        println!(
+                            "[{}/{}] OK: {}",
+                            index + 1,
+                            input_files.len(),
+                            input_file.display()
+                        );
+                    }
+                    Err(e) => {
+                        error!(
+                            "[{}/{}] {}: {}",
+                            index + 1,
+                            input_files.len(),
+                            input_file.display(),
+                            e
+                        );
+                        let category = categorize_error(e.as_ref());
+                        failed_files.lock().unwrap().push((index, category));
+                    }
+                }
+            });
+        }
+    });
+
+    scene_cost.into_inner().unwrap().warn_if_expensive();
+    let mut failed_files = failed_files.into_inner().unwrap();
+    failed_files.sort_unstable_by_key(|&(i, _)| i);
+    println!(
+        "Converted {} of {} file(s)",
+        input_files.len() - failed_files.len(),
+        input_files.len()
+    );
+    if !failed_files.is_empty() {
+        eprintln!("Failed files:");
+        for &(index, _) in &failed_files {
+            eprintln!("  {}", input_files[index].display());
+        }
+        // exit code is based on the first failed file
+        let category = failed_files[0].1;
+        let message = format!("conversion of {} file(s) failed", failed_files.len());
+        return Err(CategorizedError::new(category, message).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,243 @@
+use crate::errors::{CategorizedError, ErrorCategory};
+use crate::manifest::Manifest;
+use crate::scene::SceneCost;
+use crate::{append_to_file_name, batch, do_convert, is_stdio_path, Args, BoxResult};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+/// Extensions of images that can be converted into TGA textures
+const TEXTURE_SOURCE_EXTENSIONS: [&str; 5] = ["tga", "png", "jpg", "jpeg", "bmp"];
+
+/// Maximal length of file name stored in a packfile
+const MAX_PACKED_NAME_LEN: usize = 59;
+
+#[derive(clap::Args, Debug)]
+pub struct BuildArgs {
+    /// Target packfile (path ending with .vpp) or mod directory
+    #[clap(long, value_name = "PATH")]
+    target: PathBuf,
+
+    /// Additional directory searched for textures. Can be used multiple times.
+    /// Directories of input files are always searched
+    #[clap(long = "texture-dir", value_name = "DIR")]
+    texture_dirs: Vec<PathBuf>,
+
+    /// Write a manifest with SHA-256 hashes and sizes of all produced files (and packfile entries) next to the
+    /// target (<target>.sha256)
+    #[clap(long)]
+    manifest: bool,
+
+    #[clap(flatten)]
+    convert: Args,
+}
+
+fn usage_error(message: &str) -> Box<dyn std::error::Error> {
+    CategorizedError::new(ErrorCategory::Usage, message).into()
+}
+
+fn is_vpp_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("vpp"))
+}
+
+fn add_texture_name(textures: &mut Vec<String>, name: String) {
+    if !textures.iter().any(|t| t.eq_ignore_ascii_case(&name)) {
+        textures.push(name);
+    }
+}
+
+/// Finds texture file in given directories. File with the exact name is preferred but an image with the same stem
+/// and a different extension (e.g. PNG exported by a 3D editor) can be used as well
+pub(crate) fn find_texture_source(
+    name: &str,
+    dirs: &[PathBuf],
+) -> std::io::Result<Option<PathBuf>> {
+    let stem = Path::new(name).file_stem().unwrap_or_default();
+    let mut other_format = None;
+    for dir in dirs {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+            if file_name.eq_ignore_ascii_case(name) {
+                return Ok(Some(path));
+            }
+            let same_stem = path
+                .file_stem()
+                .is_some_and(|s| s.eq_ignore_ascii_case(stem));
+            let is_image = path.extension().and_then(OsStr::to_str).is_some_and(|ext| {
+                TEXTURE_SOURCE_EXTENSIONS
+                    .iter()
+                    .any(|e| ext.eq_ignore_ascii_case(e))
+            });
+            if same_stem && is_image && other_format.is_none() {
+                other_format = Some(path);
+            }
+        }
+    }
+    Ok(other_format)
+}
+
+/// Copies texture into the output directory converting it to TGA if needed
+fn export_texture(name: &str, dirs: &[PathBuf], output_dir: &Path) -> BoxResult<()> {
+    let Some(source) = find_texture_source(name, dirs)? else {
+        warn!(
+            "Texture {} not found - it has to be provided by the game or another packfile",
+            name
+        );
+        return Ok(());
+    };
+    let output_path = output_dir.join(name);
+    let is_tga = source
+        .extension()
+        .and_then(OsStr::to_str)
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("tga"));
+    if is_tga {
+        info!(
+            "Copying texture: {} -> {}",
+            source.display(),
+            output_path.display()
+        );
+        std::fs::copy(&source, &output_path)?;
+    } else {
+        info!(
+            "Converting texture: {} -> {}",
+            source.display(),
+            output_path.display()
+        );
+        image::open(&source)?.save_with_format(&output_path, image::ImageFormat::Tga)?;
+    }
+    Ok(())
+}
+
+fn get_texture_dirs(args: &BuildArgs, input_files: &[PathBuf]) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    let input_dirs = input_files.iter().map(|p| match p.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_owned(),
+        _ => PathBuf::from("."),
+    });
+    for dir in input_dirs.chain(args.texture_dirs.iter().cloned()) {
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    dirs
+}
+
+fn pack_directory(dir: &Path, packfile: &Path) -> BoxResult<()> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if !path.is_file() {
+            continue;
+        }
+        let name_len = path.file_name().unwrap_or_default().len();
+        if name_len > MAX_PACKED_NAME_LEN {
+            return Err(format!(
+                "file name {} is too long to be stored in a packfile (max {} characters)",
+                path.display(),
+                MAX_PACKED_NAME_LEN
+            )
+            .into());
+        }
+        files.push(path.to_string_lossy().into_owned());
+    }
+    files.sort();
+    info!(
+        "Packing {} file(s) into {}",
+        files.len(),
+        packfile.display()
+    );
+    vpp::create_vpp(&packfile.to_string_lossy(), &files)?;
+    Ok(())
+}
+
+/// Converts all input files, exports textures they use and packs everything into a packfile or a mod directory
+pub(crate) fn run_build(args: BuildArgs) -> BoxResult<()> {
+    info!("vmesh {}", env!("CARGO_PKG_VERSION"));
+    if args.convert.output_file.is_some() || args.convert.out_dir.is_some() {
+        return Err(usage_error(
+            "output location of build command is determined by --target",
+        ));
+    }
+    if args.convert.watch {
+        return Err(usage_error("watch mode is not supported by build command"));
+    }
+    let input_files = batch::expand_input_patterns(&args.convert.inputs)?;
+    if input_files.iter().any(|p| is_stdio_path(p)) {
+        return Err(usage_error(
+            "standard input cannot be used by build command",
+        ));
+    }
+
+    let is_packfile = is_vpp_path(&args.target);
+    // packfile content is prepared in a temporary directory next to the packfile
+    let output_dir = if is_packfile {
+        append_to_file_name(&args.target, ".build")
+    } else {
+        args.target.clone()
+    };
+    let check = args.convert.check;
+    if is_packfile && output_dir.exists() {
+        // remove leftovers of a failed build so they do not end up in the packfile
+        std::fs::remove_dir_all(&output_dir)?;
+    }
+    if !check {
+        std::fs::create_dir_all(&output_dir)?;
+    }
+
+    let mut textures = Vec::new();
+    let mut generated_textures = Vec::new();
+    let mut scene_cost = SceneCost::default();
+    for input_file in &input_files {
+        let file_args = Args {
+            input_file: input_file.clone(),
+            out_dir: Some(output_dir.clone()),
+            ..args.convert.clone()
+        };
+        let output = do_convert(file_args)?;
+        scene_cost.add(input_file, &output.node_reports);
+        for name in output.node_reports.into_iter().flat_map(|r| r.textures) {
+            add_texture_name(&mut textures, name);
+        }
+        generated_textures.extend(output.generated_textures);
+    }
+    scene_cost.warn_if_expensive();
+    if check {
+        println!("Check passed: {} file(s)", input_files.len());
+        return Ok(());
+    }
+
+    let texture_dirs = get_texture_dirs(&args, &input_files);
+    for name in &textures {
+        if generated_textures
+            .iter()
+            .any(|g| g.eq_ignore_ascii_case(name))
+        {
+            // solid color texture generated by the conversion (see --color-textures)
+            continue;
+        }
+        export_texture(name, &texture_dirs, &output_dir)?;
+    }
+
+    if is_packfile {
+        pack_directory(&output_dir, &args.target)?;
+        std::fs::remove_dir_all(&output_dir)?;
+    }
+    if args.manifest {
+        let mut manifest = Manifest::default();
+        if is_packfile {
+            manifest.add_packfile(&args.target)?;
+        } else {
+            manifest.add_dir(&args.target)?;
+        }
+        manifest.write(&append_to_file_name(&args.target, ".sha256"))?;
+    }
+    println!(
+        "Built {} from {} file(s) and {} texture(s)",
+        args.target.display(),
+        input_files.len(),
+        textures.len()
+    );
+    Ok(())
+}
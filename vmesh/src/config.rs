@@ -0,0 +1,105 @@
+use crate::errors::{CategorizedError, ErrorCategory};
+use crate::limits::Profile;
+use crate::{Args, BoxResult, Format};
+use serde_derive::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use v3d::warnings::WarningKind;
+use v3d::{ClampedUvFix, MaterialOverride, PropAxis};
+
+/// Name of the configuration file looked up in the input file directory
+pub(crate) const CONFIG_FILE_NAME: &str = "vmesh.toml";
+
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum StrictConfig {
+    All(bool),
+    Kinds(Vec<WarningKind>),
+}
+
+/// Per-project defaults. Options given in the command line take precedence.
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct Config {
+    format: Option<Format>,
+    out_dir: Option<PathBuf>,
+    materials: Option<PathBuf>,
+    name_template: Option<String>,
+    anim_name_template: Option<String>,
+    profile: Option<Profile>,
+    limits: Option<String>,
+    strict: Option<StrictConfig>,
+    anim_weight: Option<f32>,
+    ramp_in_time: Option<f32>,
+    ramp_out_time: Option<f32>,
+    prop_axis: Option<PropAxis>,
+    clamped_uv_fix: Option<ClampedUvFix>,
+    crease_angle: Option<f32>,
+    /// Texture names mapping (glTF image name or URI -> RF texture name)
+    #[serde(default)]
+    textures: BTreeMap<String, String>,
+}
+
+fn find_config_file(args: &Args) -> Option<PathBuf> {
+    if args.config.is_some() {
+        return args.config.clone();
+    }
+    let dir = args.input_file.parent().unwrap_or_else(|| Path::new(""));
+    let path = dir.join(CONFIG_FILE_NAME);
+    path.is_file().then_some(path)
+}
+
+fn load_config(path: &Path) -> BoxResult<Config> {
+    let text = std::fs::read_to_string(path)?;
+    toml::from_str(&text).map_err(|e| {
+        let message = format!("invalid configuration file {}: {}", path.display(), e);
+        CategorizedError::new(ErrorCategory::Usage, message).into()
+    })
+}
+
+/// Loads material settings overrides (material or texture name -> RF material settings)
+pub(crate) fn load_material_overrides(
+    path: &Path,
+) -> BoxResult<BTreeMap<String, MaterialOverride>> {
+    let text = std::fs::read_to_string(path)?;
+    toml::from_str(&text).map_err(|e| {
+        let message = format!("invalid materials file {}: {}", path.display(), e);
+        CategorizedError::new(ErrorCategory::Usage, message).into()
+    })
+}
+
+/// Fills options not given in the command line with values from the configuration file
+pub(crate) fn apply_config(mut args: Args) -> BoxResult<Args> {
+    let Some(path) = find_config_file(&args) else {
+        return Ok(args);
+    };
+    info!("Using configuration file {}", path.display());
+    let config = load_config(&path)?;
+    // relative paths in the configuration file are relative to the file location
+    let config_dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    args.format = args.format.or(config.format);
+    args.out_dir = args
+        .out_dir
+        .or_else(|| config.out_dir.map(|dir| config_dir.join(dir)));
+    args.materials = args
+        .materials
+        .or_else(|| config.materials.map(|path| config_dir.join(path)));
+    args.name_template = args.name_template.or(config.name_template);
+    args.anim_name_template = args.anim_name_template.or(config.anim_name_template);
+    args.limits.profile = args.limits.profile.or(config.profile);
+    args.limits.limits = args.limits.limits.or(config.limits);
+    args.strict = args.strict.or(match config.strict {
+        Some(StrictConfig::All(true)) => Some(Vec::new()),
+        Some(StrictConfig::Kinds(kinds)) => Some(kinds),
+        Some(StrictConfig::All(false)) | None => None,
+    });
+    args.anim_weight = args.anim_weight.or(config.anim_weight);
+    args.ramp_in_time = args.ramp_in_time.or(config.ramp_in_time);
+    args.ramp_out_time = args.ramp_out_time.or(config.ramp_out_time);
+    args.prop_axis = args.prop_axis.or(config.prop_axis);
+    args.clamped_uv_fix = args.clamped_uv_fix.or(config.clamped_uv_fix);
+    args.crease_angle = args.crease_angle.or(config.crease_angle);
+    args.texture_map = config.textures;
+    Ok(args)
+}
@@ -0,0 +1,37 @@
+use clap::ValueEnum;
+use env_logger::fmt::{Color, Formatter};
+use env_logger::WriteStyle;
+use log::{Level, Record};
+use std::io::Write;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum ColorMode {
+    /// Use colors if standard error is a terminal
+    Auto,
+    /// Always use colors
+    Always,
+    /// Never use colors
+    Never,
+}
+
+impl ColorMode {
+    pub(crate) fn write_style(self) -> WriteStyle {
+        match self {
+            Self::Auto => WriteStyle::Auto,
+            Self::Always => WriteStyle::Always,
+            Self::Never => WriteStyle::Never,
+        }
+    }
+}
+
+/// Formats a log record as an error/warning message with a colored label
+pub(crate) fn format_record(buf: &mut Formatter, record: &Record) -> std::io::Result<()> {
+    let (label, color) = match record.level() {
+        Level::Error => ("Error:", Color::Red),
+        Level::Warn => ("Warning!", Color::Yellow),
+        _ => return writeln!(buf, "{}", record.args()),
+    };
+    let mut style = buf.style();
+    style.set_color(color).set_bold(true);
+    writeln!(buf, "{} {}", style.value(label), record.args())
+}
@@ -0,0 +1,85 @@
+//! Generating `entity.tbl` entries for exported characters.
+//!
+//! The entry references the exported V3C file and lists exported animations so it can be pasted or merged into game
+//! tables. Gameplay fields (hit points, AI, weapons, etc.) are not generated.
+
+use std::fmt::Write as _;
+use v3d::v3mc;
+
+/// Exported animation listed in the table
+pub(crate) struct ExportedAnim {
+    /// Animation (clip) name
+    pub name: String,
+    /// RFA file name
+    pub file_name: String,
+    /// `Some(true)` for looping animations, `Some(false)` for one-shot animations, `None` if not specified
+    pub looping: Option<bool>,
+}
+
+/// Suffix of looping animation names removed from animation names in the table
+const LOOP_SUFFIX: &str = "_loop";
+
+/// Substrings of animation names used for actions (animations played once on top of a state animation)
+const ACTION_NAME_PARTS: [&str; 9] = [
+    "attack", "death", "fire", "flinch", "hit", "reload", "shoot", "throw", "use",
+];
+
+fn is_action_anim(name: &str) -> bool {
+    let name = name.to_lowercase();
+    ACTION_NAME_PARTS.iter().any(|part| name.contains(part))
+}
+
+/// Returns animation name used in the table. Character name prefix (e.g. `miner_` in `miner_walk`) and `_loop` suffix
+/// are removed.
+fn get_anim_tag<'a>(anim_name: &'a str, character: &str) -> &'a str {
+    let tag = anim_name
+        .get(..character.len() + 1)
+        .filter(|prefix| prefix.eq_ignore_ascii_case(&format!("{}_", character)))
+        .map_or(anim_name, |prefix| &anim_name[prefix.len()..]);
+    let end = tag.len().saturating_sub(LOOP_SUFFIX.len());
+    match tag.get(end..) {
+        Some(suffix) if end > 0 && suffix.eq_ignore_ascii_case(LOOP_SUFFIX) => &tag[..end],
+        _ => tag,
+    }
+}
+
+/// Creates text of `entity.tbl` entry. `character` is the entity name, `v3c_name` is the file name of the exported
+/// mesh. Looping animations are listed as states and one-shot animations as actions. If looping is not specified it
+/// is guessed from the animation name.
+pub(crate) fn create_entity_tbl_entry(
+    character: &str,
+    v3c_name: &str,
+    v3c: &v3mc::File,
+    anims: &[ExportedAnim],
+) -> String {
+    let mut text = String::new();
+    let _ = writeln!(
+        text,
+        "// Generated by vmesh - review and merge into entity.tbl"
+    );
+    let _ = writeln!(text, "$Name:                \"{}\"", character);
+    let _ = writeln!(text, "$V3D Filename:        \"{}\"", v3c_name);
+    for lod_mesh in &v3c.lod_meshes {
+        let distances: Vec<_> = lod_mesh.distances.iter().map(f32::to_string).collect();
+        let _ = writeln!(
+            text,
+            "// LOD distances of submesh {}: {}",
+            lod_mesh.name,
+            distances.join(", ")
+        );
+    }
+    for anim in anims {
+        let is_action = anim
+            .looping
+            .map_or_else(|| is_action_anim(&anim.name), |looping| !looping);
+        let field = if is_action { "$Action:" } else { "$State:" };
+        let _ = writeln!(
+            text,
+            "{:<22}\"{}\" \"{}\"",
+            field,
+            get_anim_tag(&anim.name, character),
+            anim.file_name
+        );
+    }
+    text
+}
@@ -0,0 +1,38 @@
+use std::error::Error;
+use std::io::ErrorKind;
+pub(crate) use v3d::errors::{CategorizedError, ConvertError, ErrorCategory};
+
+pub(crate) fn categorize_error(err: &(dyn Error + 'static)) -> ErrorCategory {
+    if let Some(e) = err.downcast_ref::<CategorizedError>() {
+        return e.category;
+    }
+    if let Some(e) = err.downcast_ref::<ConvertError>() {
+        return match e {
+            ConvertError::Io(e) => categorize_error(e),
+            _ => e.category(),
+        };
+    }
+    if let Some(e) = err.downcast_ref::<gltf::Error>() {
+        return match e {
+            gltf::Error::Io(_) => ErrorCategory::Io,
+            _ => ErrorCategory::Input,
+        };
+    }
+    if let Some(e) = err.downcast_ref::<std::io::Error>() {
+        // ErrorKind::Other is used for conversion errors (see new_custom_error)
+        return match e.kind() {
+            ErrorKind::Other | ErrorKind::InvalidData | ErrorKind::UnexpectedEof => {
+                ErrorCategory::Input
+            }
+            _ => ErrorCategory::Io,
+        };
+    }
+    if err.is::<glob::PatternError>() {
+        return ErrorCategory::Usage;
+    }
+    if err.is::<glob::GlobError>() {
+        return ErrorCategory::Io;
+    }
+    // Other errors are reported by the conversion code when input content cannot be handled
+    ErrorCategory::Input
+}
@@ -0,0 +1,131 @@
+use crate::errors::{CategorizedError, ErrorCategory};
+use crate::limits::{Limits, LimitsArgs};
+use crate::BoxResult;
+use std::path::PathBuf;
+use v3d::{get_mesh_materials, get_primitive_vertex_count, get_submesh_nodes, is_collision_only};
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct InfoArgs {
+    /// Input GLTF filename
+    input_file: PathBuf,
+
+    #[clap(flatten)]
+    limits: LimitsArgs,
+}
+
+fn limit_marker(value: usize, limit: usize) -> &'static str {
+    if value > limit {
+        " (limit exceeded!)"
+    } else {
+        ""
+    }
+}
+
+fn print_mesh_info(node: &gltf::Node, limits: &Limits) -> usize {
+    let mesh = node.mesh().unwrap();
+    let materials = get_mesh_materials(&mesh);
+    let mut num_problems = 0;
+    println!(
+        "  {} ({} primitives, {}/{} materials){}",
+        node.name().unwrap_or("<unnamed>"),
+        mesh.primitives().len(),
+        materials.len(),
+        limits.max_textures,
+        limit_marker(materials.len(), limits.max_textures)
+    );
+    if materials.len() > limits.max_textures {
+        num_problems += 1;
+    }
+    for (i, prim) in mesh.primitives().enumerate() {
+        if is_collision_only(&prim) {
+            println!("    Primitive #{}: collision only (not rendered)", i);
+            continue;
+        }
+        let vertex_count = get_primitive_vertex_count(&prim);
+        let index_count = prim.indices().map_or(0, |a| a.count());
+        println!(
+            "    Primitive #{}: vertices {}/{}{}, indices {}/{}{}",
+            i,
+            vertex_count,
+            limits.max_vertices,
+            limit_marker(vertex_count, limits.max_vertices),
+            index_count,
+            limits.max_indices,
+            limit_marker(index_count, limits.max_indices)
+        );
+        if vertex_count > limits.max_vertices || index_count > limits.max_indices {
+            num_problems += 1;
+        }
+        if prim.mode() != gltf::mesh::Mode::Triangles || prim.indices().is_none() {
+            println!(
+                "    Primitive #{}: only indexed triangle lists are supported",
+                i
+            );
+            num_problems += 1;
+        }
+    }
+    num_problems
+}
+
+pub(crate) fn print_info(args: &InfoArgs) -> BoxResult<()> {
+    let limits = args.limits.resolve()?;
+    let gltf = gltf::Gltf::open(&args.input_file)?;
+    let doc = &gltf.document;
+    let mut num_problems = 0;
+
+    println!("File: {}", args.input_file.display());
+    println!("Nodes: {}", doc.nodes().len());
+
+    let submesh_nodes = get_submesh_nodes(doc);
+    println!("Submeshes: {}", submesh_nodes.len());
+    for node in &submesh_nodes {
+        num_problems += print_mesh_info(node, &limits);
+    }
+
+    println!("Materials: {}", doc.materials().len());
+    for material in doc.materials() {
+        println!("  {}", material.name().unwrap_or("<unnamed>"));
+    }
+
+    let skins = doc.skins().collect::<Vec<_>>();
+    println!("Skins: {}", skins.len());
+    for skin in &skins {
+        let num_joints = skin.joints().len();
+        println!(
+            "  {}: bones {}/{}{}",
+            skin.name().unwrap_or("<unnamed>"),
+            num_joints,
+            limits.max_bones,
+            limit_marker(num_joints, limits.max_bones)
+        );
+        if num_joints > limits.max_bones {
+            num_problems += 1;
+        }
+    }
+    if skins.len() > 1 {
+        println!("  Only one skin is supported");
+        num_problems += 1;
+    }
+
+    println!("Animations: {}", doc.animations().len());
+    for anim in doc.animations() {
+        println!(
+            "  {} ({} channels)",
+            anim.name().unwrap_or("<unnamed>"),
+            anim.channels().count()
+        );
+    }
+
+    let num_cameras = doc.nodes().filter(|n| n.camera().is_some()).count();
+    let num_lights = doc.nodes().filter(|n| n.light().is_some()).count();
+    println!("Cameras: {}", num_cameras);
+    println!("Lights: {}", num_lights);
+
+    let output_format = if skins.is_empty() { "V3M" } else { "V3C" };
+    println!("Default output format: {}", output_format);
+    if num_problems > 0 {
+        let message = format!("found {} problem(s)", num_problems);
+        return Err(CategorizedError::new(ErrorCategory::Validation, message).into());
+    }
+    Ok(())
+}
@@ -0,0 +1,82 @@
+//! Printing content of V3M/V3C files with decoded render modes.
+
+use crate::BoxResult;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use v3d::v3mc;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct InspectArgs {
+    /// Input V3M or V3C filename
+    input_file: PathBuf,
+}
+
+fn print_mesh(lod_index: usize, distance: f32, mesh: &v3mc::Mesh) {
+    println!(
+        "    LOD{} (distance {}): {} vertices, flags {:#x}",
+        lod_index, distance, mesh.num_vecs, mesh.flags
+    );
+    for (i, (chunk, info)) in mesh.chunks.iter().zip(&mesh.data_block.chunks).enumerate() {
+        let texture = usize::try_from(info.texture_index)
+            .ok()
+            .and_then(|index| mesh.textures.get(index))
+            .map_or("<none>", |t| t.tex_name.as_str());
+        println!(
+            "      Batch #{}: texture {}, {} vertices, {} triangles",
+            i, texture, chunk.num_vecs, chunk.num_faces
+        );
+        println!(
+            "        render mode {:#08x}: {}",
+            chunk.render_mode,
+            v3mc::decode_render_mode(chunk.render_mode)
+        );
+    }
+}
+
+pub(crate) fn print_inspect(args: &InspectArgs) -> BoxResult<()> {
+    let mut rdr = BufReader::new(File::open(&args.input_file)?);
+    let file = v3mc::File::read(&mut rdr)?;
+    let kind = if file.header.signature == v3mc::V3C_SIGNATURE {
+        "V3C"
+    } else {
+        "V3M"
+    };
+    println!("File: {} ({})", args.input_file.display(), kind);
+    println!(
+        "Submeshes: {}, collision spheres: {}, bones: {}",
+        file.lod_meshes.len(),
+        file.cspheres.len(),
+        file.bones.len()
+    );
+    for lod_mesh in &file.lod_meshes {
+        println!(
+            "  Submesh {}: {} LODs, radius {:.2}",
+            lod_mesh.name,
+            lod_mesh.meshes.len(),
+            lod_mesh.radius
+        );
+        for (i, mesh) in lod_mesh.meshes.iter().enumerate() {
+            let distance = lod_mesh.distances.get(i).copied().unwrap_or_default();
+            print_mesh(i, distance, mesh);
+        }
+        for material in &lod_mesh.materials {
+            println!(
+                "    Material {}: self illumination {}, specular level {}, glossiness {}, reflection {} ({}), \
+                flags {:#x}",
+                material.tex_name,
+                material.self_illumination,
+                material.specular_level,
+                material.glossiness,
+                material.reflection_amount,
+                if material.refl_tex_name.is_empty() {
+                    "<none>"
+                } else {
+                    &material.refl_tex_name
+                },
+                material.flags
+            );
+        }
+    }
+    Ok(())
+}
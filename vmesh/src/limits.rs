@@ -0,0 +1,181 @@
+use crate::errors::{CategorizedError, ErrorCategory};
+use crate::BoxResult;
+use std::io::Write;
+use std::path::PathBuf;
+use v3d::budget::Budget;
+use v3d::diagnostics::describe_node;
+pub(crate) use v3d::limits::{Limits, Profile};
+use v3d::{
+    get_mesh_materials, get_primitive_vertex_count, get_rendered_primitives, get_submesh_nodes,
+};
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct LimitsArgs {
+    /// Engine limits profile used for validation of the output. Default is stock
+    #[clap(long, value_enum)]
+    pub(crate) profile: Option<Profile>,
+
+    /// Override engine limits, e.g. "vertices=8000,indices=20000,textures=10,bones=60"
+    #[clap(long)]
+    pub(crate) limits: Option<String>,
+}
+
+impl LimitsArgs {
+    pub fn resolve(&self) -> Result<Limits, CategorizedError> {
+        let mut limits = Limits::for_profile(self.profile.unwrap_or(Profile::Stock));
+        if let Some(overrides) = &self.limits {
+            limits
+                .apply_overrides(overrides)
+                .map_err(|e| CategorizedError::new(ErrorCategory::Usage, e))?;
+        }
+        Ok(limits)
+    }
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct LimitsCommandArgs {
+    /// Optional input GLTF filename. If specified, usage of each limit by every submesh is printed
+    input_file: Option<PathBuf>,
+
+    #[clap(flatten)]
+    limits: LimitsArgs,
+}
+
+// Usage ratio above which a value is reported as close to the limit
+const NEAR_LIMIT_RATIO: f32 = 0.9;
+
+pub(crate) fn format_usage(value: usize, limit: usize) -> String {
+    let ratio = if limit == 0 {
+        f32::INFINITY
+    } else {
+        value as f32 / limit as f32
+    };
+    let marker = if value > limit {
+        " (limit exceeded!)"
+    } else if ratio >= NEAR_LIMIT_RATIO {
+        " (close to limit)"
+    } else {
+        ""
+    };
+    format!("{}/{} ({:.0}%){}", value, limit, ratio * 100.0, marker)
+}
+
+fn print_mesh_usage(node: &gltf::Node, limits: &Limits) {
+    let mesh = node.mesh().unwrap();
+    let num_materials = get_mesh_materials(&mesh).len();
+    println!(
+        "  {}: textures {}",
+        describe_node(node),
+        format_usage(num_materials, limits.max_textures)
+    );
+    for (i, prim) in get_rendered_primitives(&mesh) {
+        let vertex_count = get_primitive_vertex_count(&prim);
+        let index_count = prim.indices().map_or(0, |a| a.count());
+        println!(
+            "    Primitive #{}: vertices {}, indices {}",
+            i,
+            format_usage(vertex_count, limits.max_vertices),
+            format_usage(index_count, limits.max_indices)
+        );
+    }
+}
+
+pub(crate) fn pass_fail(passed: bool) -> &'static str {
+    if passed {
+        "PASS"
+    } else {
+        "FAIL"
+    }
+}
+
+/// Prints result of the pre-flight analysis performed before conversion
+pub(crate) fn print_budget<W: Write>(budget: &Budget, wrt: &mut W) -> std::io::Result<()> {
+    let limits = &budget.limits;
+    writeln!(wrt, "Pre-flight check:")?;
+    for mesh in &budget.meshes {
+        writeln!(
+            wrt,
+            "  [{}] {}: textures {}, estimated size {} KB",
+            pass_fail(mesh.passed(limits)),
+            mesh.name,
+            format_usage(mesh.textures, limits.max_textures),
+            mesh.estimated_size.div_ceil(1024)
+        )?;
+        for prim in &mesh.primitives {
+            writeln!(
+                wrt,
+                "    {}: vertices {}, indices {}",
+                prim.location,
+                format_usage(prim.vertices, limits.max_vertices),
+                format_usage(prim.indices, limits.max_indices)
+            )?;
+        }
+    }
+    if budget.bones > 0 {
+        writeln!(
+            wrt,
+            "  [{}] Skeleton: bones {}",
+            pass_fail(budget.bones <= limits.max_bones),
+            format_usage(budget.bones, limits.max_bones)
+        )?;
+    }
+    writeln!(
+        wrt,
+        "  Estimated output size: {} KB - {}",
+        budget.estimated_size().div_ceil(1024),
+        pass_fail(budget.passed())
+    )
+}
+
+pub(crate) fn print_limits(args: &LimitsCommandArgs) -> BoxResult<()> {
+    let limits = args.limits.resolve()?;
+    let profiles = [
+        ("Stock", Limits::STOCK),
+        ("Dash Faction", Limits::DASH_FACTION),
+        ("Active", limits),
+    ];
+    print!("{:10}", "Limit");
+    for (name, _) in &profiles {
+        print!("  {:>12}", name);
+    }
+    println!();
+    type Row = (&'static str, fn(&Limits) -> usize);
+    let rows: [Row; 4] = [
+        ("Vertices", |l| l.max_vertices),
+        ("Indices", |l| l.max_indices),
+        ("Textures", |l| l.max_textures),
+        ("Bones", |l| l.max_bones),
+    ];
+    for (label, get_value) in rows {
+        print!("{:10}", label);
+        for (_, profile_limits) in &profiles {
+            print!("  {:>12}", get_value(profile_limits));
+        }
+        println!();
+    }
+    println!(
+        "Vertex and index limits apply to a single primitive, texture limit to a single submesh"
+    );
+
+    let Some(input_file) = &args.input_file else {
+        return Ok(());
+    };
+    let gltf = gltf::Gltf::open(input_file)?;
+    let doc = &gltf.document;
+    println!();
+    println!("Limits usage for {}:", input_file.display());
+    for node in get_submesh_nodes(doc) {
+        print_mesh_usage(&node, &limits);
+        for child in node.children().filter(|n| n.mesh().is_some()) {
+            print_mesh_usage(&child, &limits);
+        }
+    }
+    for skin in doc.skins() {
+        println!(
+            "  Skin '{}': bones {}",
+            skin.name().unwrap_or("<unnamed>"),
+            format_usage(skin.joints().count(), limits.max_bones)
+        );
+    }
+    Ok(())
+}
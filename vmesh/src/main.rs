@@ -1,119 +1,54 @@
-mod char_anim;
-mod io_utils;
-mod material;
-mod math_utils;
-mod rfa;
-mod rfg;
-mod rfg_convert;
-mod v3mc;
-mod v3mc_convert;
+mod audit;
+mod batch;
+mod build;
+mod config;
+mod diagnostics;
+mod entity_tbl;
+mod errors;
+mod info;
+mod inspect;
+mod limits;
+mod manifest;
+mod occlusion;
+mod progress;
+mod render;
+mod retarget;
+mod scene;
+mod stats;
+mod watch;
 
 use clap::ArgAction;
+use clap::CommandFactory;
 use clap::Parser;
+use clap::Subcommand;
 use clap::ValueEnum;
-use gltf::Buffer;
-use math_utils::{Matrix3, Matrix4, Vector3};
-use std::env;
-use std::error::Error;
-use std::f32;
+use errors::{CategorizedError, ErrorCategory};
+use log::LevelFilter;
+use std::collections::BTreeMap;
 use std::ffi::OsStr;
 use std::fs::File;
-use std::io::BufWriter;
+use std::io::{BufReader, Cursor, Read, Write};
 use std::path::Path;
 use std::path::PathBuf;
-use std::vec::Vec;
+use v3d::rfl_brush_convert::BrushSelection;
+use v3d::warnings::WarningKind;
+use v3d::{
+    char_anim, report, rfa, rfg_convert, rfl, rfl_brush_convert, rfl_convert, v3mc, v3mc_convert,
+    BoxResult, ClampedUvFix, Context, ConvertOptions, PropAxis,
+};
 
-type BoxResult<T> = Result<T, Box<dyn Error>>;
+#[macro_use]
+extern crate log;
 
-// glTF defines -X as right, RF defines +X as right
-// Both glTF and RF defines +Y as up, +Z as forward
-
-fn gltf_to_rf_vec(vec: [f32; 3]) -> [f32; 3] {
-    // in GLTF negative X is right, in RF positive X is right
-    [-vec[0], vec[1], vec[2]]
-}
-
-fn gltf_to_rf_quat(quat: [f32; 4]) -> [f32; 4] {
-    // convert to RF coordinate system
-    // it seems RF expects inverted quaternions...
-    [-quat[0], quat[1], quat[2], quat[3]]
-}
-
-fn gltf_to_rf_face<T: Copy>(vindices: [T; 3]) -> [T; 3] {
-    // because we convert from right-handed to left-handed order of vertices must be flipped to
-    // fix backface culling
-    [vindices[0], vindices[2], vindices[1]]
-}
-
-fn build_child_nodes_indices(doc: &gltf::Document) -> Vec<usize> {
-    let mut child_indices: Vec<usize> = doc
-        .nodes()
-        .flat_map(|n| n.children().map(|n| n.index()))
-        .collect();
-    child_indices.dedup();
-    child_indices
-}
-
-fn get_submesh_nodes(doc: &gltf::Document) -> Vec<gltf::Node> {
-    let child_indices = build_child_nodes_indices(doc);
-    doc.nodes()
-        .filter(|n| n.mesh().is_some() && !child_indices.contains(&n.index()))
-        .collect()
-}
-
-fn get_mesh_materials<'a>(mesh: &gltf::Mesh<'a>) -> Vec<gltf::Material<'a>> {
-    let mut materials = mesh
-        .primitives()
-        .map(|prim| prim.material())
-        .collect::<Vec<_>>();
-    materials.dedup_by_key(|m| m.index());
-    materials
-}
-
-fn get_primitive_vertex_count(prim: &gltf::Primitive) -> usize {
-    prim.attributes()
-        .find(|p| p.0 == gltf::mesh::Semantic::Positions)
-        .map_or(0, |a| a.1.count())
-}
-
-fn count_mesh_vertices(mesh: &gltf::Mesh) -> usize {
-    mesh.primitives()
-        .map(|p| get_primitive_vertex_count(&p))
-        .sum()
-}
-
-fn extract_translation_from_matrix(transform: &Matrix4) -> (Vector3, Matrix3) {
-    let mut translation = [0_f32; 3];
-    translation.copy_from_slice(&transform[3][0..3]);
-    let mut rot_scale_mat = [[0_f32; 3]; 3];
-    rot_scale_mat[0].copy_from_slice(&transform[0][0..3]);
-    rot_scale_mat[1].copy_from_slice(&transform[1][0..3]);
-    rot_scale_mat[2].copy_from_slice(&transform[2][0..3]);
-    (translation, rot_scale_mat)
-}
-
-fn get_node_local_transform(node: &gltf::Node) -> glam::Mat4 {
-    glam::Mat4::from_cols_array_2d(&node.transform().matrix())
-}
-
-struct Context {
-    buffers: Vec<gltf::buffer::Data>,
-    is_character: bool,
-    args: Args,
-    output_dir: PathBuf,
-}
-
-impl Context {
-    fn get_buffer_data(&self, buffer: Buffer) -> Option<&[u8]> {
-        Some(&*self.buffers[buffer.index()])
-    }
-}
-
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+#[derive(
+    Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum, serde_derive::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
 enum Format {
     V3m,
     V3c,
     Rfg,
+    Rfl,
 }
 
 fn determine_output_format(args: &Args, is_character: bool) -> Format {
@@ -127,6 +62,7 @@ fn determine_output_format(args: &Args, is_character: bool) -> Format {
             Some("v3m") => Format::V3m,
             Some("v3c") => Format::V3c,
             Some("rfg") => Format::Rfg,
+            Some("rfl") => Format::Rfl,
             _ => {
                 if is_character {
                     Format::V3c
@@ -138,78 +74,710 @@ fn determine_output_format(args: &Args, is_character: bool) -> Format {
     })
 }
 
+fn get_format_extension(format: Format) -> &'static str {
+    match format {
+        Format::V3m => "v3m",
+        Format::V3c => "v3c",
+        Format::Rfg => "rfg",
+        Format::Rfl => "rfl",
+    }
+}
+
+const DEFAULT_NAME_TEMPLATE: &str = "{stem}.{ext}";
+/// Default template used with `--split-nodes`
+const DEFAULT_SPLIT_NAME_TEMPLATE: &str = "{node}.{ext}";
+/// Default template of animation file names
+const DEFAULT_ANIM_NAME_TEMPLATE: &str = "{clip}.rfa";
+
+fn expand_name_template(template: &str, stem: &str, ext: &str, node: Option<&str>) -> String {
+    template
+        .replace("{stem}", stem)
+        .replace("{ext}", ext)
+        .replace("{node}", node.unwrap_or(stem))
+}
+
+fn expand_anim_name_template(template: &str, character: &str, clip: &str) -> String {
+    template
+        .replace("{character}", character)
+        .replace("{clip}", clip)
+}
+
+/// Returns RFA file names of animation clips. `character` is the output file name without extension
+fn determine_anim_file_names(
+    clips: &[String],
+    character: &str,
+    args: &Args,
+) -> BoxResult<Vec<String>> {
+    let template = args
+        .anim_name_template
+        .as_deref()
+        .unwrap_or(DEFAULT_ANIM_NAME_TEMPLATE);
+    let mut file_names: Vec<String> = Vec::with_capacity(clips.len());
+    for clip in clips {
+        let file_name = expand_anim_name_template(template, character, clip);
+        if file_names
+            .iter()
+            .any(|n| n.eq_ignore_ascii_case(&file_name))
+        {
+            let message = format!(
+                "animation file name template '{}' gives the same file name {} for multiple animations",
+                template, file_name
+            );
+            return Err(CategorizedError::new(ErrorCategory::Usage, message).into());
+        }
+        file_names.push(file_name);
+    }
+    Ok(file_names)
+}
+
+/// Path "-" means standard input or standard output
+fn is_stdio_path(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
 fn determine_output_file_name(args: &Args, output_format: Format) -> PathBuf {
     args.output_file.as_ref().map_or_else(
         || {
-            let ext = match output_format {
-                Format::V3m => "v3m",
-                Format::V3c => "v3c",
-                Format::Rfg => "rfg",
-            };
-            args.input_file.with_extension(ext)
+            if is_stdio_path(&args.input_file) {
+                // output name cannot be derived from standard input so standard output is used
+                return PathBuf::from("-");
+            }
+            let stem = args
+                .input_file
+                .file_stem()
+                .map(|s| s.to_string_lossy())
+                .unwrap_or_default();
+            let template = args
+                .name_template
+                .as_deref()
+                .unwrap_or(DEFAULT_NAME_TEMPLATE);
+            let name =
+                expand_name_template(template, &stem, get_format_extension(output_format), None);
+            let dir = args
+                .out_dir
+                .as_deref()
+                .or_else(|| args.input_file.parent())
+                .unwrap_or_else(|| Path::new(""));
+            dir.join(name)
         },
         |p| p.clone(),
     )
 }
 
-fn do_convert(args: Args) -> Result<(), Box<dyn Error>> {
-    if args.verbose >= 1 {
-        println!("Importing GLTF file: {}", args.input_file.display());
+/// Returns name of the file a single node is written to when splitting output by nodes
+fn determine_node_output_file_name(
+    output_dir: &Path,
+    args: &Args,
+    output_format: Format,
+    node: &str,
+) -> PathBuf {
+    let stem = args
+        .input_file
+        .file_stem()
+        .map(|s| s.to_string_lossy())
+        .unwrap_or_default();
+    let template = args
+        .name_template
+        .as_deref()
+        .unwrap_or(DEFAULT_SPLIT_NAME_TEMPLATE);
+    let name = expand_name_template(
+        template,
+        &stem,
+        get_format_extension(output_format),
+        Some(node),
+    );
+    output_dir.join(name)
+}
+
+fn create_output_dir(output_file_name: &Path, args: &Args) -> std::io::Result<()> {
+    if args.check {
+        return Ok(());
     }
-    let input_path = Path::new(&args.input_file);
-    let gltf = gltf::Gltf::open(input_path)?;
-    let gltf::Gltf { document, blob } = gltf;
+    match output_file_name.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => std::fs::create_dir_all(dir),
+        _ => Ok(()),
+    }
+}
+
+/// Serializes output file into memory and writes it to disk unless running in check mode
+fn append_to_file_name(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_owned();
+    file_name.push(suffix);
+    path.with_file_name(file_name)
+}
+
+/// Writes data into a temporary file and renames it on success so an interrupted conversion never leaves a truncated
+/// output file
+fn write_file_atomically(path: &Path, data: &[u8], backup: bool) -> std::io::Result<()> {
+    let tmp_path = append_to_file_name(path, ".tmp");
+    let result = std::fs::write(&tmp_path, data).and_then(|_| {
+        if backup && path.exists() {
+            let backup_path = append_to_file_name(path, ".bak");
+            debug!("Creating backup {}", backup_path.display());
+            std::fs::copy(path, backup_path)?;
+        }
+        std::fs::rename(&tmp_path, path)
+    });
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+/// Returns size of the output file in bytes
+fn write_output_file<F>(path: &Path, args: &Args, write_fn: F) -> std::io::Result<usize>
+where
+    F: FnOnce(&mut Cursor<Vec<u8>>) -> std::io::Result<()>,
+{
+    write_output_file_with(path, args.check, args.backup, write_fn)
+}
+
+/// Same as write_output_file but for commands that do not use conversion arguments. Returns size of the output file
+/// in bytes
+fn write_output_file_with<F>(
+    path: &Path,
+    check: bool,
+    backup: bool,
+    write_fn: F,
+) -> std::io::Result<usize>
+where
+    F: FnOnce(&mut Cursor<Vec<u8>>) -> std::io::Result<()>,
+{
+    let mut wrt = Cursor::new(Vec::new());
+    write_fn(&mut wrt)?;
+    let size = wrt.get_ref().len();
+    if check {
+        info!(
+            "Check mode: skipping writing {} ({} bytes)",
+            path.display(),
+            size
+        );
+        return Ok(size);
+    }
+    if is_stdio_path(path) {
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(wrt.get_ref())?;
+        stdout.flush()?;
+    } else {
+        write_file_atomically(path, wrt.get_ref(), backup)?;
+    }
+    Ok(size)
+}
+
+/// Writes solid color textures generated for materials without base color texture. Returns their file names
+fn write_generated_textures(
+    ctx: &Context,
+    output_dir: &Path,
+    args: &Args,
+) -> std::io::Result<Vec<String>> {
+    const SIZE: u32 = 8;
+    let textures = ctx.take_generated_textures();
+    for texture in &textures {
+        let file_name = output_dir.join(&texture.name);
+        info!("Exporting texture: {}", file_name.display());
+        let img = image::RgbaImage::from_pixel(SIZE, SIZE, image::Rgba(texture.color));
+        write_output_file(&file_name, args, |wrt| {
+            image::DynamicImage::ImageRgba8(img)
+                .write_to(wrt, image::ImageOutputFormat::Tga)
+                .map_err(std::io::Error::other)
+        })?;
+    }
+    Ok(textures.into_iter().map(|t| t.name).collect())
+}
+
+/// Writes base color textures with baked ambient occlusion. Source images are loaded relative to `base_dir`. Returns
+/// file names of written textures
+fn write_baked_textures(
+    ctx: &Context,
+    base_dir: &Path,
+    output_dir: &Path,
+    args: &Args,
+) -> BoxResult<Vec<String>> {
+    let textures = ctx.take_baked_textures();
+    for texture in &textures {
+        let file_name = output_dir.join(&texture.name);
+        info!("Exporting texture: {}", file_name.display());
+        let mut img = occlusion::load_texture_image(&texture.base_color, base_dir)?;
+        let occlusion_img = occlusion::load_texture_image(&texture.occlusion, base_dir)?;
+        occlusion::bake_occlusion(&mut img, &occlusion_img, texture.strength);
+        write_output_file(&file_name, args, |wrt| {
+            image::DynamicImage::ImageRgba8(img)
+                .write_to(wrt, image::ImageOutputFormat::Tga)
+                .map_err(std::io::Error::other)
+        })?;
+    }
+    Ok(textures.into_iter().map(|t| t.name).collect())
+}
+
+/// Writes `entity.tbl` entry of the exported character into a `.tbl` file next to the output file
+fn write_entity_tbl(
+    output_file: &Path,
+    v3c: &v3mc::File,
+    anims: &[entity_tbl::ExportedAnim],
+    args: &Args,
+) -> std::io::Result<()> {
+    if is_stdio_path(output_file) {
+        warn!("Entity table entry is not written when the mesh is written to standard output");
+        return Ok(());
+    }
+    let character = output_file
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let v3c_name = output_file
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    let text = entity_tbl::create_entity_tbl_entry(&character, &v3c_name, v3c, anims);
+    let file_name = output_file.with_extension("tbl");
+    info!("Exporting entity table entry: {}", file_name.display());
+    write_output_file(&file_name, args, |wrt| wrt.write_all(text.as_bytes()))?;
+    Ok(())
+}
+
+/// Writes index of exported animations. Lines have the form `<file>  <clip>  <duration in seconds>  <playback>`
+/// where playback is `loop`, `once` or `-` (not specified)
+fn write_anim_index(
+    path: &Path,
+    anims: &[entity_tbl::ExportedAnim],
+    rfas: &[(String, rfa::File)],
+    args: &Args,
+) -> std::io::Result<()> {
+    let mut content = String::new();
+    for (anim, (_, rfa)) in anims.iter().zip(rfas) {
+        let duration =
+            (rfa.header.end_time - rfa.header.start_time) as f32 / rfa::RFA_TIME_UNITS_PER_SEC;
+        let playback = match anim.looping {
+            Some(true) => "loop",
+            Some(false) => "once",
+            None => "-",
+        };
+        content.push_str(&format!(
+            "{}  {}  {:.2}  {}\n",
+            anim.file_name, anim.name, duration, playback
+        ));
+    }
+    info!("Exporting animation index: {}", path.display());
+    write_output_file(path, args, |wrt| wrt.write_all(content.as_bytes()))?;
+    Ok(())
+}
+
+fn print_budget(budget: &v3d::budget::Budget, output_file: &Path) -> std::io::Result<()> {
+    // do not mix the table with output file content
+    if is_stdio_path(output_file) {
+        limits::print_budget(budget, &mut std::io::stderr())
+    } else {
+        limits::print_budget(budget, &mut std::io::stdout())
+    }
+}
+
+fn print_stats(stats: &stats::Stats, input_file: &Path, output_file: &Path) -> std::io::Result<()> {
+    // do not mix statistics with output file content
+    if is_stdio_path(output_file) {
+        stats.print(input_file, &mut std::io::stderr())
+    } else {
+        stats.print(input_file, &mut std::io::stdout())
+    }
+}
 
-    if args.verbose >= 2 {
-        println!("Importing GLTF buffers");
+fn emit_report(
+    node_reports: &[report::NodeReport],
+    args: &Args,
+    output_file: &Path,
+) -> std::io::Result<()> {
+    match &args.report {
+        None => Ok(()),
+        Some(Some(report_file)) => report::write_json(node_reports, &args.input_file, report_file),
+        // do not mix the report with output file content
+        Some(None) if is_stdio_path(output_file) => report::print_table(
+            node_reports,
+            &args.input_file,
+            &mut std::io::stderr().lock(),
+        ),
+        Some(None) => report::print_table(
+            node_reports,
+            &args.input_file,
+            &mut std::io::stdout().lock(),
+        ),
     }
-    let buffers = gltf::import_buffers(&document, input_path.parent(), blob)?;
+}
+
+fn is_rfl_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("rfl"))
+}
+
+fn do_convert_rfl_brushes(args: Args) -> BoxResult<ConvertOutput> {
+    info!("Importing RFL file: {}", args.input_file.display());
+    if args.format.is_some_and(|f| f != Format::V3m) {
+        return Err(CategorizedError::new(
+            ErrorCategory::Usage,
+            "RFL brushes can only be converted to V3M format",
+        )
+        .into());
+    }
+    let mut stats = stats::Stats::default();
+    let rfl = stats.time_stage("Import", || -> BoxResult<_> {
+        let mut rdr = BufReader::new(File::open(&args.input_file)?);
+        Ok(rfl::RflBrushes::read(&mut rdr)?)
+    })?;
+
+    let output_file_name = determine_output_file_name(&args, Format::V3m);
+    create_output_dir(&output_file_name, &args)?;
+    let name = args
+        .rfl_group
+        .clone()
+        .or_else(|| {
+            output_file_name
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+        })
+        .unwrap_or_default();
+    let limits = args.limits.resolve()?;
+    let selection = BrushSelection {
+        group: args.rfl_group.clone(),
+        uids: args.rfl_brush.clone(),
+    };
+    let v3m = stats.time_stage("Convert", || {
+        rfl_brush_convert::convert_rfl_brushes_to_v3m(&rfl, &name, &selection, &limits)
+    })?;
+    stats.add_v3mc(&v3m);
+
+    info!("Exporting mesh: {}", output_file_name.display());
+    stats.output_size = stats.time_stage("Write", || {
+        write_output_file(&output_file_name, &args, |wrt| v3m.write(wrt))
+    })?;
+    if args.stats {
+        print_stats(&stats, &args.input_file, &output_file_name)?;
+    }
+    let node_reports: Vec<_> = v3m
+        .lod_meshes
+        .iter()
+        .map(report::NodeReport::from_lod_mesh)
+        .collect();
+    emit_report(&node_reports, &args, &output_file_name)?;
+    Ok(ConvertOutput {
+        node_reports,
+        generated_textures: Vec::new(),
+    })
+}
+
+/// Result of converting a single input file
+struct ConvertOutput {
+    /// Report entries of converted nodes
+    node_reports: Vec<report::NodeReport>,
+    /// File names of solid color textures generated by the conversion (see --color-textures)
+    generated_textures: Vec<String>,
+}
+
+/// Converts a single input file
+fn do_convert(args: Args) -> BoxResult<ConvertOutput> {
+    let args = config::apply_config(args)?;
+    if is_rfl_file(&args.input_file) {
+        return do_convert_rfl_brushes(args);
+    }
+    info!("Importing GLTF file: {}", args.input_file.display());
+    let input_path = Path::new(&args.input_file);
+    let limits = args.limits.resolve()?;
+    let mut stats = stats::Stats::default();
+    // external files referenced by glTF from standard input are loaded relative to the current directory
+    let base_dir = input_path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let (document, buffers) = stats.time_stage("Import", || -> BoxResult<_> {
+        let gltf = if is_stdio_path(input_path) {
+            let mut data = Vec::new();
+            std::io::stdin().lock().read_to_end(&mut data)?;
+            gltf::Gltf::from_slice(&data)?
+        } else {
+            gltf::Gltf::open(input_path)?
+        };
+        Ok(v3d::import_gltf(gltf, base_dir)?)
+    })?;
     let skin_opt = document.skins().next();
     let is_character = skin_opt.is_some();
 
     let output_format = determine_output_format(&args, is_character);
+    if args.split_nodes && output_format != Format::V3m {
+        return Err(CategorizedError::new(
+            ErrorCategory::Usage,
+            "--split-nodes can only be used with V3M output (static meshes)",
+        )
+        .into());
+    }
     let output_file_name = determine_output_file_name(&args, output_format);
+    create_output_dir(&output_file_name, &args)?;
     let output_dir = output_file_name.parent().unwrap().to_owned();
 
-    if args.verbose >= 1 {
-        println!("Exporting mesh: {}", output_file_name.display());
-    }
-    let ctx = Context {
-        buffers,
-        is_character,
-        args,
-        output_dir,
+    let material_overrides = match &args.materials {
+        Some(path) => config::load_material_overrides(path)?,
+        None => BTreeMap::new(),
+    };
+
+    info!("Exporting mesh: {}", output_file_name.display());
+    let options = ConvertOptions {
+        limits,
+        strict: args.strict.clone(),
+        texture_map: args.texture_map.clone(),
+        anim_weight: args.anim_weight,
+        ramp_in_time: args.ramp_in_time,
+        ramp_out_time: args.ramp_out_time,
+        auto_lod: args.auto_lod.clone(),
+        lod_distance_scale: args.lod_distance_scale,
+        lod_texture_suffix: args.lod_texture_suffix.clone(),
+        prop_axis: args.prop_axis.unwrap_or_default(),
+        clamped_uv_fix: args.clamped_uv_fix.unwrap_or_default(),
+        color_textures: args.color_textures,
+        bake_occlusion: args.bake_occlusion,
+        material_overrides,
+        face_planes: !args.no_face_planes,
+        crease_angle: args.crease_angle,
+        force_texture: args.force_texture.clone(),
+        auto_cspheres: args.auto_cspheres,
     };
+    let ctx = Context::new(buffers, is_character, options)
+        .with_progress(progress::Progress::new(args.progress));
     if output_format == Format::Rfg {
-        let rfg = rfg_convert::convert_gltf_to_rfg(&document, &ctx)?;
-        let file = File::create(output_file_name)?;
-        let mut wrt = BufWriter::new(file);
-        rfg.write(&mut wrt)?;
+        let rfg = stats.time_stage("Convert", || {
+            rfg_convert::convert_gltf_to_rfg(&document, &ctx)
+        })?;
+        ctx.check_errors()?;
+        stats.add_brushes(rfg.groups.iter().flat_map(|g| &g.brushes));
+        stats.output_size = stats.time_stage("Write", || {
+            write_output_file(&output_file_name, &args, |wrt| rfg.write(wrt))
+        })?;
+    } else if output_format == Format::Rfl {
+        let level_name = args
+            .input_file
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let rfl = stats.time_stage("Convert", || {
+            rfl_convert::convert_gltf_to_rfl(&document, &level_name, &ctx)
+        })?;
+        ctx.check_errors()?;
+        stats.add_brushes(&rfl.brushes);
+        stats.output_size = stats.time_stage("Write", || {
+            write_output_file(&output_file_name, &args, |wrt| rfl.write(wrt))
+        })?;
     } else {
-        let v3m = v3mc_convert::convert_gltf_to_v3mc(&document, &ctx)?;
-        let file = File::create(output_file_name)?;
-        let mut wrt = BufWriter::new(file);
-        v3m.write(&mut wrt)?;
-
-        if let Some(skin) = skin_opt {
-            for (i, anim) in document.animations().enumerate() {
-                char_anim::convert_animation_to_rfa(&anim, i, &skin, &ctx)?;
+        // give an overview of all limit problems before anything is converted or written
+        let budget = v3d::budget::analyze_budget(&document, &ctx.options().limits);
+        if args.stats || !budget.passed() {
+            print_budget(&budget, &output_file_name)?;
+        }
+        let v3m = stats.time_stage("Convert", || {
+            v3mc_convert::convert_gltf_to_v3mc(&document, &ctx)
+        })?;
+        // animations are converted before writing so a failure does not leave a partial set of output files
+        let rfas = match skin_opt {
+            Some(skin) => stats.time_stage("Animations", || {
+                document
+                    .animations()
+                    .enumerate()
+                    .map(|(i, anim)| char_anim::convert_animation_to_rfa(&anim, i, &skin, &ctx))
+                    .collect::<Result<Vec<_>, _>>()
+            })?,
+            None => Vec::new(),
+        };
+        ctx.check_errors()?;
+        let outputs = if args.split_nodes {
+            v3mc_convert::split_v3mc_file(v3m)
+                .into_iter()
+                .map(|file| {
+                    let node = &file.lod_meshes[0].name;
+                    let file_name =
+                        determine_node_output_file_name(&output_dir, &args, output_format, node);
+                    (file_name, file)
+                })
+                .collect()
+        } else {
+            vec![(output_file_name.clone(), v3m)]
+        };
+        for (_, file) in &outputs {
+            stats.add_v3mc(file);
+        }
+        let character = output_file_name
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let clips: Vec<_> = rfas.iter().map(|(clip, _)| clip.clone()).collect();
+        let anim_file_names = determine_anim_file_names(&clips, &character, &args)?;
+        stats.output_size = stats.time_stage("Write", || -> std::io::Result<usize> {
+            let mut size = 0;
+            for (file_name, file) in &outputs {
+                if args.split_nodes {
+                    info!("Exporting node: {}", file_name.display());
+                }
+                size += write_output_file(file_name, &args, |wrt| file.write(wrt))?;
+            }
+            for ((clip, rfa), anim_file_name) in rfas.iter().zip(&anim_file_names) {
+                let file_name = output_dir.join(anim_file_name);
+                info!("Exporting animation: {} -> {}", clip, file_name.display());
+                write_output_file(&file_name, &args, |wrt| rfa.write(wrt))?;
+            }
+            Ok(size)
+        })?;
+        let anims: Vec<_> = clips
+            .into_iter()
+            .zip(anim_file_names)
+            .zip(document.animations())
+            .map(|((name, file_name), anim)| entity_tbl::ExportedAnim {
+                name,
+                file_name,
+                looping: char_anim::get_anim_looping(&anim),
+            })
+            .collect();
+        if args.anim_index && !rfas.is_empty() {
+            let index_file_name = output_dir.join(format!("{}_anims.txt", character));
+            write_anim_index(&index_file_name, &anims, &rfas, &args)?;
+        }
+        if args.entity_tbl {
+            if is_character {
+                write_entity_tbl(&output_file_name, &outputs[0].1, &anims, &args)?;
+            } else {
+                warn!("--entity-tbl is ignored because the input has no skin (not a character)");
             }
         }
     }
+    let mut generated_textures = write_generated_textures(&ctx, &output_dir, &args)?;
+    generated_textures.extend(write_baked_textures(&ctx, base_dir, &output_dir, &args)?);
 
-    Ok(())
+    if args.stats {
+        print_stats(&stats, &args.input_file, &output_file_name)?;
+    }
+    let node_reports = ctx.take_node_reports();
+    emit_report(&node_reports, &args, &output_file_name)?;
+    Ok(ConvertOutput {
+        node_reports,
+        generated_textures,
+    })
 }
 
 #[derive(Parser, Debug)]
-#[clap(author, version, about, about = "GLTF to V3M/V3C/RFG converter")]
+#[clap(author, version, about, about = "GLTF to V3M/V3C/RFG/RFL converter")]
+struct Cli {
+    #[clap(subcommand)]
+    command: Command,
+
+    /// Enable verbose output. Can be used up to 3 times to increase verbosity
+    #[clap(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+
+    /// Do not print warnings (errors are still printed)
+    #[clap(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// When to use colors in error and warning messages
+    #[clap(long, global = true, value_enum, default_value_t = diagnostics::ColorMode::Auto)]
+    color: diagnostics::ColorMode,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Convert GLTF file (or RFL brushes) into RF format
+    Convert(Args),
+    /// Convert GLTF files, export textures used by them and pack everything into a packfile (or a mod directory)
+    Build(build::BuildArgs),
+    /// Print information about GLTF file content and check it against engine limits
+    Info(info::InfoArgs),
+    /// Print engine limits and, if input GLTF file is given, how close each submesh comes to them
+    Limits(limits::LimitsCommandArgs),
+    /// Print aggregate statistics of V3M/V3C files in directories or VPP packfiles (vertices, limit usage, texture
+    /// usage and duplicate texture names)
+    Audit(audit::AuditArgs),
+    /// Print content of a V3M or V3C file (submeshes, LODs, batches with decoded render modes and materials)
+    Inspect(inspect::InspectArgs),
+    /// Render a shaded preview image (PNG) of a V3M or V3C file
+    Render(render::RenderArgs),
+    /// Retarget animations authored on another skeleton (e.g. a Mixamo rig) onto RF character skeleton and export
+    /// them as RFA files
+    Retarget(retarget::RetargetArgs),
+    /// Generate left/right mirrored copies of character animations (e.g. a left-handed attack from a right-handed one)
+    /// and export them as RFA files
+    Mirror(retarget::MirrorArgs),
+    /// Generate shell completion script and print it to the standard output
+    Completions {
+        /// Shell to generate completions for
+        #[clap(value_enum)]
+        shell: clap_complete::Shell,
+    },
+}
+
+#[derive(clap::Args, Debug, Clone)]
 pub struct Args {
-    /// Input GLTF filename
+    /// Input GLTF filenames or glob patterns (e.g. "assets/*.gltf"). RFL file can be used to convert level brushes
+    /// into V3M
+    #[clap(required = true)]
+    inputs: Vec<String>,
+
+    /// Currently converted input file (one of files matching input patterns)
+    #[clap(skip)]
     input_file: PathBuf,
 
-    /// Output filename
+    /// Output filename. Can be used only if there is a single input file. By default output filename is based on
+    /// input filename
+    #[clap(short, long = "output")]
     output_file: Option<PathBuf>,
 
+    /// Output directory. By default output files are created next to input files
+    #[clap(long)]
+    out_dir: Option<PathBuf>,
+
+    /// Output filename template. Supported placeholders: {stem} - input filename without extension,
+    /// {ext} - output format extension, {node} - node name (when splitting, otherwise same as {stem}).
+    /// Default is "{stem}.{ext}"
+    #[clap(long)]
+    name_template: Option<String>,
+
+    /// Write every submesh node into a separate V3M file named after the node (see --name-template, default is
+    /// "{node}.{ext}")
+    #[clap(long, conflicts_with = "output_file")]
+    split_nodes: bool,
+
+    /// Run the conversion and validation without writing any output files
+    #[clap(long)]
+    check: bool,
+
+    /// Show conversion progress. By default progress bar is shown if output is a terminal and a single file is
+    /// converted
+    #[clap(long, value_enum, default_value_t = progress::ProgressMode::Auto)]
+    progress: progress::ProgressMode,
+
+    /// Keep previous version of the output file with .bak suffix
+    #[clap(long)]
+    backup: bool,
+
+    /// Print statistics (number of submeshes, vertices, triangles, etc.) and time spent in each conversion stage
+    #[clap(long)]
+    stats: bool,
+
+    /// Print a table listing vertex, triangle and batch counts, textures, bounding radius and applied fixes for every
+    /// converted node. If FILE is given, the report is saved in JSON format instead
+    #[clap(long, value_name = "FILE", num_args = 0..=1, require_equals = true)]
+    report: Option<Option<PathBuf>>,
+
+    /// Maximal number of files converted in parallel. Default is number of CPU cores
+    #[clap(short, long)]
+    jobs: Option<usize>,
+
+    /// Configuration file with default options. By default vmesh.toml file from input file directory is used
+    /// if it exists
+    #[clap(long)]
+    config: Option<PathBuf>,
+
+    /// TOML file with RF material settings overrides. Keys are material or texture names, values are tables with
+    /// texture, tex_src, color_op, alpha_op, alpha_blend, zbuffer_type, fog, self_illumination, specular_level,
+    /// glossiness, reflection_amount, refl_tex_name and flags fields
+    #[clap(long, value_name = "FILE")]
+    materials: Option<PathBuf>,
+
+    /// Texture names mapping loaded from the configuration file
+    #[clap(skip)]
+    texture_map: BTreeMap<String, String>,
+
     /// Output file format. If not specified format is detected from output file extension and input file content.
     #[clap(short, long)]
     format: Option<Format>,
@@ -229,20 +797,235 @@ pub struct Args {
     #[clap(long)]
     ramp_out_time: Option<f32>,
 
-    /// Enable verbose output. Can be used 2 times to increase verbosity
-    #[clap(short, long, action = ArgAction::Count)]
-    verbose: u8,
+    #[clap(flatten)]
+    limits: limits::LimitsArgs,
+
+    /// Generate simplified LOD meshes for submeshes that have no LODs. Value is a comma separated list of triangle
+    /// count ratios, e.g. "0.5,0.25" creates two LODs with half and a quarter of the original triangles
+    #[clap(long, value_delimiter = ',', value_parser = parse_lod_ratio)]
+    auto_lod: Vec<f32>,
+
+    /// Multiplier of automatically computed LOD switch distances. Distances are computed from the bounding radius
+    /// and triangle counts for generated LODs and LOD meshes without LOD_distance property
+    #[clap(long, value_name = "FACTOR", default_value_t = 1.0)]
+    lod_distance_scale: f32,
+
+    /// Use lower resolution textures in less detailed LODs. Suffix is added to texture names ({lod} is replaced by
+    /// the LOD index), e.g. "_lod{lod}" makes LOD1 use wall_lod1.tga instead of wall.tga
+    #[clap(long, value_name = "SUFFIX")]
+    lod_texture_suffix: Option<String>,
+
+    /// Local axis of nodes converted to prop points that should become the prop point direction (Z axis in RF).
+    /// Default is z
+    #[clap(long, value_enum)]
+    prop_axis: Option<PropAxis>,
+
+    /// Do not store triangle planes in static meshes (V3M). Makes files noticeably smaller but clients relying on the
+    /// planes (for backface culling) may render the mesh incorrectly
+    #[clap(long)]
+    no_face_planes: bool,
+
+    /// Recompute normals from geometry: edges between faces meeting at a bigger angle (in degrees) become hard, the
+    /// rest is smoothed. Useful for hard-surface models exported fully smoothed, e.g. "--crease-angle 30"
+    #[clap(long, value_name = "DEGREES", value_parser = parse_crease_angle)]
+    crease_angle: Option<f32>,
+
+    /// Generate solid color textures from base color factor for materials without base color texture (named after
+    /// the material) instead of using Rck_Default.tga
+    #[clap(long)]
+    color_textures: bool,
+
+    /// Bake occlusion textures into base color textures. Baked textures are named after the base color texture with
+    /// "_ao" suffix (e.g. wall_ao.tga) and written next to the output file
+    #[clap(long)]
+    bake_occlusion: bool,
+
+    /// Use a single placeholder texture for all materials (batches are kept separate), e.g. for testing scale and
+    /// collision of greybox models in game
+    #[clap(long, value_name = "NAME")]
+    force_texture: Option<String>,
+
+    /// Generate collision spheres from the skeleton (bone positions and lengths) for characters without hand-made
+    /// csphere nodes
+    #[clap(long)]
+    auto_cspheres: bool,
+
+    /// Write entity.tbl entry of the exported character (V3C file name, animation states and actions, LOD
+    /// distances) into a .tbl file next to the output file, ready to be merged into game tables
+    #[clap(long)]
+    entity_tbl: bool,
+
+    /// Animation filename template. Supported placeholders: {character} - output filename without extension,
+    /// {clip} - animation name. Default is "{clip}.rfa"
+    #[clap(long, value_name = "TEMPLATE")]
+    anim_name_template: Option<String>,
+
+    /// Write index of exported animations (file name, animation name and duration) into {character}_anims.txt
+    #[clap(long)]
+    anim_index: bool,
+
+    /// Fix applied when a clamped (ClampToEdge) texture is used by a primitive with texture coordinates outside of
+    /// 0-1 range: warn only, clamp texture coordinates or switch to wrapping texture mode. Default is warn
+    #[clap(long, value_enum, value_name = "FIX")]
+    clamped_uv_fix: Option<ClampedUvFix>,
+
+    /// Name of RFL group containing brushes to be converted (RFL input only)
+    #[clap(long)]
+    rfl_group: Option<String>,
+
+    /// UID of RFL brush to be converted. Can be used multiple times (RFL input only).
+    /// All brushes are converted if neither brush UID nor group is specified
+    #[clap(long)]
+    rfl_brush: Vec<i32>,
+
+    /// Treat warnings as errors. Optionally a comma separated list of warning classes can be given
+    /// (e.g. "--strict=missing-texture,wrap-mode"), otherwise all warnings are treated as errors
+    #[clap(long, value_enum, value_delimiter = ',', num_args = 0.., require_equals = true)]
+    strict: Option<Vec<WarningKind>>,
+
+    /// Watch input file and files referenced by it for changes and convert again when they are modified
+    #[clap(short, long)]
+    watch: bool,
 }
 
-fn main() {
-    let args = Args::parse();
+fn parse_lod_ratio(value: &str) -> Result<f32, String> {
+    let ratio: f32 = value
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid LOD ratio: {}", value))?;
+    if ratio > 0.0 && ratio < 1.0 {
+        Ok(ratio)
+    } else {
+        Err(format!("LOD ratio must be between 0 and 1: {}", value))
+    }
+}
 
-    if args.verbose >= 1 {
-        println!("vmesh {}", env!("CARGO_PKG_VERSION"));
+fn parse_crease_angle(value: &str) -> Result<f32, String> {
+    let angle: f32 = value
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid crease angle: {}", value))?;
+    if (0.0..=180.0).contains(&angle) {
+        Ok(angle)
+    } else {
+        Err(format!(
+            "crease angle must be between 0 and 180 degrees: {}",
+            value
+        ))
     }
+}
+
+fn run_convert(args: Args) -> BoxResult<()> {
+    info!("vmesh {}", env!("CARGO_PKG_VERSION"));
 
-    if let Err(e) = do_convert(args) {
-        eprintln!("Error: {}", e);
-        std::process::exit(1);
+    let input_files = batch::expand_input_patterns(&args.inputs)?;
+    if input_files.len() > 1 && args.output_file.is_some() {
+        return Err(CategorizedError::new(
+            ErrorCategory::Usage,
+            "output filename cannot be specified when converting multiple files",
+        )
+        .into());
+    }
+    if input_files.len() > 1 && matches!(args.report, Some(Some(_))) {
+        return Err(CategorizedError::new(
+            ErrorCategory::Usage,
+            "report filename cannot be specified when converting multiple files",
+        )
+        .into());
+    }
+    if input_files.len() > 1 && input_files.iter().any(|p| is_stdio_path(p)) {
+        return Err(CategorizedError::new(
+            ErrorCategory::Usage,
+            "standard input cannot be used when converting multiple files",
+        )
+        .into());
+    }
+    if args.watch && input_files.iter().any(|p| is_stdio_path(p)) {
+        return Err(CategorizedError::new(
+            ErrorCategory::Usage,
+            "standard input cannot be watched for changes",
+        )
+        .into());
+    }
+    if input_files.len() > 1 && args.watch {
+        return Err(CategorizedError::new(
+            ErrorCategory::Usage,
+            "watch mode supports only a single input file",
+        )
+        .into());
+    }
+
+    if input_files.len() == 1 {
+        let args = Args {
+            input_file: input_files[0].clone(),
+            ..args
+        };
+        if args.watch {
+            watch::watch_and_convert(args)
+        } else {
+            let check = args.check;
+            do_convert(args)?;
+            if check {
+                println!("Check passed: {}", input_files[0].display());
+            }
+            Ok(())
+        }
+    } else {
+        batch::convert_batch(&args, &input_files)
+    }
+}
+
+fn print_completions(shell: clap_complete::Shell) {
+    let mut cmd = Cli::command();
+    let bin_name = cmd.get_name().to_owned();
+    clap_complete::generate(shell, &mut cmd, bin_name, &mut std::io::stdout());
+}
+
+fn init_logger(cli: &Cli) {
+    let level = if cli.quiet {
+        LevelFilter::Error
+    } else {
+        match cli.verbose {
+            0 => LevelFilter::Warn,
+            1 => LevelFilter::Info,
+            2 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        }
+    };
+    env_logger::Builder::new()
+        .filter_module("vmesh", level)
+        .write_style(cli.color.write_style())
+        .format(diagnostics::format_record)
+        .init();
+}
+
+fn main() {
+    let cli = Cli::parse();
+    init_logger(&cli);
+
+    // panic = "abort" is used so panics cannot be caught - report them from the hook before the process is aborted
+    std::panic::set_hook(Box::new(|info| {
+        error!("internal error: {}", info);
+        std::process::exit(ErrorCategory::Internal.exit_code());
+    }));
+
+    let result = match cli.command {
+        Command::Convert(args) => run_convert(args),
+        Command::Build(args) => build::run_build(args),
+        Command::Info(args) => info::print_info(&args),
+        Command::Limits(args) => limits::print_limits(&args),
+        Command::Inspect(args) => inspect::print_inspect(&args),
+        Command::Render(args) => render::render(&args),
+        Command::Retarget(args) => retarget::run_retarget(&args),
+        Command::Mirror(args) => retarget::run_mirror(&args),
+        Command::Audit(args) => audit::run_audit(&args),
+        Command::Completions { shell } => {
+            print_completions(shell);
+            Ok(())
+        }
+    };
+    if let Err(e) = result {
+        error!("{}", e);
+        std::process::exit(errors::categorize_error(e.as_ref()).exit_code());
     }
 }
@@ -0,0 +1,71 @@
+//! Checksum manifests of built mods.
+//!
+//! Manifest is a text file listing SHA-256 hash, size and name of every produced file (and of every entry of a
+//! produced packfile) so server admins can verify files downloaded by clients. Lines have the form
+//! `<sha256>  <size>  <name>`, packfile entries are named `<packfile>:<entry>`.
+
+use crate::BoxResult;
+use sha2::{Digest, Sha256};
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// Computes SHA-256 hash of data and returns it as a lowercase hex string
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .fold(String::new(), |mut s, byte| {
+            let _ = write!(s, "{:02x}", byte);
+            s
+        })
+}
+
+#[derive(Default)]
+pub(crate) struct Manifest {
+    lines: Vec<String>,
+}
+
+impl Manifest {
+    fn add(&mut self, name: &str, data: &[u8]) {
+        self.lines
+            .push(format!("{}  {}  {}", sha256_hex(data), data.len(), name));
+    }
+
+    /// Adds all files in a directory (not recursive) sorted by name
+    pub(crate) fn add_dir(&mut self, dir: &Path) -> BoxResult<()> {
+        let mut paths = std::fs::read_dir(dir)?
+            .map(|e| e.map(|e| e.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        paths.retain(|p| p.is_file());
+        paths.sort();
+        for path in paths {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            self.add(&name, &std::fs::read(&path)?);
+        }
+        Ok(())
+    }
+
+    /// Adds a packfile and all its entries
+    pub(crate) fn add_packfile(&mut self, path: &Path) -> BoxResult<()> {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        self.add(&name, &std::fs::read(path)?);
+        for file in vpp::read_vpp(&path.to_string_lossy())? {
+            self.add(&format!("{}:{}", name, file.name), &file.data);
+        }
+        Ok(())
+    }
+
+    pub(crate) fn write(&self, path: &Path) -> BoxResult<()> {
+        let mut content = String::new();
+        for line in &self.lines {
+            content.push_str(line);
+            content.push('\n');
+        }
+        std::fs::write(path, content)?;
+        info!(
+            "Written manifest of {} file(s) into {}",
+            self.lines.len(),
+            path.display()
+        );
+        Ok(())
+    }
+}
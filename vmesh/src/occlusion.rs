@@ -0,0 +1,62 @@
+//! Baking ambient occlusion into base color textures (see `--bake-occlusion`).
+
+use crate::BoxResult;
+use std::path::Path;
+
+pub(crate) fn load_texture_image(
+    image: &v3d::TextureImage,
+    base_dir: &Path,
+) -> BoxResult<image::RgbaImage> {
+    let img = match image {
+        v3d::TextureImage::Uri(uri) => image::open(base_dir.join(uri))?,
+        v3d::TextureImage::Data(data) => image::load_from_memory(data)?,
+    };
+    Ok(img.to_rgba8())
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Multiplies base color by occlusion from the red channel of the occlusion image as defined by GLTF:
+/// `color * (1 + strength * (occlusion - 1))`. Occlusion image is resized to the base color image size if needed.
+pub(crate) fn bake_occlusion(
+    base_color: &mut image::RgbaImage,
+    occlusion: &image::RgbaImage,
+    strength: f32,
+) {
+    let resized;
+    let occlusion = if occlusion.dimensions() == base_color.dimensions() {
+        occlusion
+    } else {
+        let (width, height) = base_color.dimensions();
+        resized = image::imageops::resize(
+            occlusion,
+            width,
+            height,
+            image::imageops::FilterType::Triangle,
+        );
+        &resized
+    };
+    for (pixel, occlusion_pixel) in base_color.pixels_mut().zip(occlusion.pixels()) {
+        let ao = f32::from(occlusion_pixel[0]) / 255.0;
+        let factor = 1.0 + strength * (ao - 1.0);
+        for c in &mut pixel.0[..3] {
+            // occlusion is applied in linear space
+            let linear = srgb_to_linear(f32::from(*c) / 255.0) * factor;
+            *c = (linear_to_srgb(linear) * 255.0).round() as u8;
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use clap::ValueEnum;
+use std::io::{IsTerminal, Write};
+use std::sync::{Mutex, PoisonError};
+use v3d::ProgressListener;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum ProgressMode {
+    /// Show progress bar if standard error is a terminal
+    Auto,
+    /// Always show progress (one line per step if standard error is not a terminal)
+    Always,
+    /// Never show progress
+    Never,
+}
+
+const BAR_WIDTH: usize = 30;
+
+#[derive(Default)]
+struct ProgressState {
+    label: String,
+    total: usize,
+    done: usize,
+}
+
+/// Reports conversion progress on standard error
+pub(crate) struct Progress {
+    enabled: bool,
+    is_tty: bool,
+    state: Mutex<ProgressState>,
+}
+
+impl Progress {
+    pub(crate) fn new(mode: ProgressMode) -> Self {
+        let is_tty = std::io::stderr().is_terminal();
+        let enabled = match mode {
+            ProgressMode::Auto => is_tty,
+            ProgressMode::Always => true,
+            ProgressMode::Never => false,
+        };
+        Self {
+            enabled,
+            is_tty,
+            state: Mutex::default(),
+        }
+    }
+}
+
+impl ProgressListener for Progress {
+    fn start(&self, label: &str, total: usize) {
+        *self.state.lock().unwrap_or_else(PoisonError::into_inner) = ProgressState {
+            label: label.to_owned(),
+            total,
+            done: 0,
+        };
+    }
+
+    fn advance(&self, count: usize, item: &str) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.done = (state.done + count).min(state.total);
+        if !self.enabled {
+            return;
+        }
+        let mut stderr = std::io::stderr().lock();
+        if self.is_tty {
+            let filled = (state.done * BAR_WIDTH)
+                .checked_div(state.total)
+                .unwrap_or(BAR_WIDTH);
+            // \x1b[K clears the rest of the line
+            let _ = write!(
+                stderr,
+                "\r{} [{}{}] {}/{} {}\x1b[K",
+                state.label,
+                "#".repeat(filled),
+                "-".repeat(BAR_WIDTH - filled),
+                state.done,
+                state.total,
+                item
+            );
+            let _ = stderr.flush();
+        } else {
+            let _ = writeln!(
+                stderr,
+                "{} {}/{} {}",
+                state.label, state.done, state.total, item
+            );
+        }
+    }
+
+    fn finish(&self) {
+        if self.enabled && self.is_tty {
+            let _ = writeln!(std::io::stderr());
+        }
+    }
+}
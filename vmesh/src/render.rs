@@ -0,0 +1,285 @@
+//! Offline preview renderer of V3M/V3C files.
+//!
+//! The most detailed LOD of every submesh is rasterized in software with an orthographic three-quarter view, a
+//! single directional light and nearest-neighbour sampled base textures. Output is meant for thumbnails in asset
+//! browsers, not for judging in-game look (no lightmaps, alpha blending or animation).
+
+use crate::build::find_texture_source;
+use crate::BoxResult;
+use image::{Rgba, RgbaImage};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use v3d::v3mc;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct RenderArgs {
+    /// Input V3M or V3C filename
+    input_file: PathBuf,
+
+    /// Output PNG filename. By default it is based on input filename
+    #[clap(short, long = "output")]
+    output_file: Option<PathBuf>,
+
+    /// Width and height of the rendered image in pixels
+    #[clap(long, default_value_t = 256, value_parser = clap::value_parser!(u32).range(16..=4096))]
+    size: u32,
+
+    /// Directory with textures used by the mesh. Can be used multiple times. Directory of the input file is always
+    /// searched
+    #[clap(long)]
+    textures: Vec<PathBuf>,
+}
+
+/// View rotation around the vertical axis (the mesh front faces +Z so 180 degrees looks at it from the front)
+const VIEW_YAW_DEG: f32 = 145.0;
+/// View rotation around the horizontal axis (positive values look from above)
+const VIEW_PITCH_DEG: f32 = 20.0;
+/// Direction the light comes from in view space
+const LIGHT_DIR: [f32; 3] = [-0.4, 0.6, -0.7];
+const AMBIENT: f32 = 0.35;
+/// Fraction of the image left empty around the mesh
+const MARGIN: f32 = 0.05;
+/// Color used for surfaces without a texture that could be loaded
+const UNTEXTURED_COLOR: Rgba<u8> = Rgba([180, 180, 180, 255]);
+
+struct Triangle {
+    /// Screen-space positions (x and y in pixels, z is depth)
+    pos: [[f32; 3]; 3],
+    uvs: [[f32; 2]; 3],
+    /// Lambert shading factor
+    shade: f32,
+    texture: Option<usize>,
+}
+
+fn rotate_to_view(v: &[f32; 3]) -> [f32; 3] {
+    let (sy, cy) = VIEW_YAW_DEG.to_radians().sin_cos();
+    let (sp, cp) = VIEW_PITCH_DEG.to_radians().sin_cos();
+    let x = v[0] * cy + v[2] * sy;
+    let z = -v[0] * sy + v[2] * cy;
+    let y = v[1] * cp - z * sp;
+    let z = v[1] * sp + z * cp;
+    [x, y, z]
+}
+
+fn compute_shade(p: &[[f32; 3]; 3]) -> f32 {
+    let e1 = [p[1][0] - p[0][0], p[1][1] - p[0][1], p[1][2] - p[0][2]];
+    let e2 = [p[2][0] - p[0][0], p[2][1] - p[0][1], p[2][2] - p[0][2]];
+    let n = [
+        e1[1] * e2[2] - e1[2] * e2[1],
+        e1[2] * e2[0] - e1[0] * e2[2],
+        e1[0] * e2[1] - e1[1] * e2[0],
+    ];
+    let n_len = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+    let l_len = LIGHT_DIR.iter().map(|c| c * c).sum::<f32>().sqrt();
+    if n_len == 0.0 {
+        return AMBIENT;
+    }
+    let dot = (n[0] * LIGHT_DIR[0] + n[1] * LIGHT_DIR[1] + n[2] * LIGHT_DIR[2]) / (n_len * l_len);
+    // faces are lit from both sides because winding of double sided faces is arbitrary
+    AMBIENT + (1.0 - AMBIENT) * dot.abs()
+}
+
+/// Loads textures used by the mesh. Textures that cannot be found or decoded are rendered with a flat color
+fn load_textures(names: &[String], dirs: &[PathBuf]) -> Vec<Option<RgbaImage>> {
+    names
+        .iter()
+        .map(|name| {
+            let path = match find_texture_source(name, dirs) {
+                Ok(Some(path)) => path,
+                _ => {
+                    warn!(
+                        "Texture {} not found - rendering it with a flat color",
+                        name
+                    );
+                    return None;
+                }
+            };
+            match image::open(&path) {
+                Ok(img) => Some(img.to_rgba8()),
+                Err(e) => {
+                    warn!("Cannot load texture {}: {}", path.display(), e);
+                    None
+                }
+            }
+        })
+        .collect()
+}
+
+fn sample_texture(texture: &RgbaImage, uv: [f32; 2]) -> Rgba<u8> {
+    let (w, h) = texture.dimensions();
+    let x = (uv[0].rem_euclid(1.0) * w as f32) as u32;
+    let y = (uv[1].rem_euclid(1.0) * h as f32) as u32;
+    *texture.get_pixel(x.min(w - 1), y.min(h - 1))
+}
+
+/// Collects triangles of the most detailed LOD of every submesh. Vertex indices read from the file are validated so a
+/// malformed file results in an error instead of a panic
+fn collect_triangles(
+    file: &v3mc::File,
+    texture_names: &mut Vec<String>,
+) -> BoxResult<Vec<Triangle>> {
+    let mut triangles = Vec::new();
+    for lod_mesh in &file.lod_meshes {
+        let Some(mesh) = lod_mesh.meshes.first() else {
+            continue;
+        };
+        for (info, chunk) in mesh
+            .data_block
+            .chunks
+            .iter()
+            .zip(&mesh.data_block.chunks_data)
+        {
+            let texture = usize::try_from(info.texture_index)
+                .ok()
+                .and_then(|i| mesh.textures.get(i))
+                .map(|t| {
+                    texture_names
+                        .iter()
+                        .position(|n| n.eq_ignore_ascii_case(&t.tex_name))
+                        .unwrap_or_else(|| {
+                            texture_names.push(t.tex_name.clone());
+                            texture_names.len() - 1
+                        })
+                });
+            let view_vecs: Vec<_> = chunk
+                .vecs
+                .iter()
+                .map(|v| {
+                    let v = [
+                        v[0] + lod_mesh.offset[0],
+                        v[1] + lod_mesh.offset[1],
+                        v[2] + lod_mesh.offset[2],
+                    ];
+                    rotate_to_view(&v)
+                })
+                .collect();
+            if chunk.uvs.len() != chunk.vecs.len() {
+                return Err(format!(
+                    "submesh {}: number of texture coordinates ({}) does not match number of vertices ({})",
+                    lod_mesh.name,
+                    chunk.uvs.len(),
+                    chunk.vecs.len()
+                )
+                .into());
+            }
+            for face in &chunk.faces {
+                let [i, j, k] = face.vindices.map(usize::from);
+                if let Some(&index) = [i, j, k].iter().find(|&&i| i >= view_vecs.len()) {
+                    return Err(format!(
+                        "submesh {}: vertex index {} is out of range (vertex count {})",
+                        lod_mesh.name,
+                        index,
+                        view_vecs.len()
+                    )
+                    .into());
+                }
+                let pos = [view_vecs[i], view_vecs[j], view_vecs[k]];
+                triangles.push(Triangle {
+                    pos,
+                    uvs: [chunk.uvs[i], chunk.uvs[j], chunk.uvs[k]],
+                    shade: compute_shade(&pos),
+                    texture,
+                });
+            }
+        }
+    }
+    Ok(triangles)
+}
+
+/// Scales and moves triangles so the mesh fills the image (Y axis points down in the image)
+fn fit_to_image(triangles: &mut [Triangle], size: u32) {
+    let mut min = [f32::MAX; 2];
+    let mut max = [f32::MIN; 2];
+    for p in triangles.iter().flat_map(|t| &t.pos) {
+        min = [min[0].min(p[0]), min[1].min(p[1])];
+        max = [max[0].max(p[0]), max[1].max(p[1])];
+    }
+    let extent = (max[0] - min[0]).max(max[1] - min[1]).max(f32::EPSILON);
+    let scale = size as f32 * (1.0 - 2.0 * MARGIN) / extent;
+    let center = [(min[0] + max[0]) / 2.0, (min[1] + max[1]) / 2.0];
+    let half_size = size as f32 / 2.0;
+    for p in triangles.iter_mut().flat_map(|t| &mut t.pos) {
+        p[0] = (p[0] - center[0]) * scale + half_size;
+        p[1] = half_size - (p[1] - center[1]) * scale;
+    }
+}
+
+fn rasterize(triangles: &[Triangle], textures: &[Option<RgbaImage>], size: u32) -> RgbaImage {
+    let mut img = RgbaImage::new(size, size);
+    let mut depth = vec![f32::MAX; (size * size) as usize];
+    for tri in triangles {
+        let [p0, p1, p2] = tri.pos;
+        let area = (p1[0] - p0[0]) * (p2[1] - p0[1]) - (p1[1] - p0[1]) * (p2[0] - p0[0]);
+        if area.abs() < f32::EPSILON {
+            continue;
+        }
+        let min_x = p0[0].min(p1[0]).min(p2[0]).floor().max(0.0) as u32;
+        let min_y = p0[1].min(p1[1]).min(p2[1]).floor().max(0.0) as u32;
+        let max_x = (p0[0].max(p1[0]).max(p2[0]).ceil() as u32).min(size);
+        let max_y = (p0[1].max(p1[1]).max(p2[1]).ceil() as u32).min(size);
+        let texture = tri.texture.and_then(|i| textures[i].as_ref());
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let w0 = ((p1[0] - px) * (p2[1] - py) - (p1[1] - py) * (p2[0] - px)) / area;
+                let w1 = ((p2[0] - px) * (p0[1] - py) - (p2[1] - py) * (p0[0] - px)) / area;
+                let w2 = 1.0 - w0 - w1;
+                if w0 < 0.0 || w1 < 0.0 || w2 < 0.0 {
+                    continue;
+                }
+                let z = w0 * p0[2] + w1 * p1[2] + w2 * p2[2];
+                let depth_index = (y * size + x) as usize;
+                if z >= depth[depth_index] {
+                    continue;
+                }
+                let color = texture.map_or(UNTEXTURED_COLOR, |t| {
+                    let uv = [0, 1]
+                        .map(|a| w0 * tri.uvs[0][a] + w1 * tri.uvs[1][a] + w2 * tri.uvs[2][a]);
+                    sample_texture(t, uv)
+                });
+                // alpha tested textures (e.g. fences and foliage) are see-through
+                if color[3] < 128 {
+                    continue;
+                }
+                depth[depth_index] = z;
+                let [r, g, b, _] = color.0;
+                let shade = |c: u8| (f32::from(c) * tri.shade).round() as u8;
+                img.put_pixel(x, y, Rgba([shade(r), shade(g), shade(b), 255]));
+            }
+        }
+    }
+    img
+}
+
+pub fn render(args: &RenderArgs) -> BoxResult<()> {
+    let mut rdr = BufReader::new(File::open(&args.input_file)?);
+    let file = v3mc::File::read(&mut rdr)?;
+
+    let mut texture_names = Vec::new();
+    let mut triangles = collect_triangles(&file, &mut texture_names)?;
+    if triangles.is_empty() {
+        return Err("mesh has no triangles to render".into());
+    }
+    let mut dirs = args.textures.clone();
+    dirs.push(match args.input_file.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_owned(),
+        _ => PathBuf::from("."),
+    });
+    let textures = load_textures(&texture_names, &dirs);
+
+    fit_to_image(&mut triangles, args.size);
+    let img = rasterize(&triangles, &textures, args.size);
+
+    let output_file = args
+        .output_file
+        .clone()
+        .unwrap_or_else(|| args.input_file.with_extension("png"));
+    img.save(&output_file)?;
+    info!(
+        "Rendered {} triangles into {}",
+        triangles.len(),
+        output_file.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,201 @@
+//! Retargeting animations authored on other skeletons (e.g. Mixamo rigs) onto RF character skeletons and generating
+//! left/right mirrored animations.
+
+use crate::errors::{CategorizedError, ErrorCategory};
+use crate::{
+    expand_anim_name_template, write_output_file_with, BoxResult, DEFAULT_ANIM_NAME_TEMPLATE,
+};
+use std::path::{Path, PathBuf};
+use v3d::retarget::{mirror_animation, retarget_animation, BoneMap};
+use v3d::{rfa, Context, ConvertOptions};
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct RetargetArgs {
+    /// Input GLTF file with animations authored on the source skeleton
+    input_file: PathBuf,
+
+    /// GLTF file with the RF character skeleton (skin) animations are retargeted to
+    #[clap(long, value_name = "FILE")]
+    target: PathBuf,
+
+    /// TOML file mapping RF bone names to source bone names
+    #[clap(long, value_name = "FILE")]
+    bone_map: PathBuf,
+
+    /// Name of animation to be retargeted. Can be used multiple times. All animations are retargeted by default
+    #[clap(long = "anim", value_name = "NAME")]
+    anims: Vec<String>,
+
+    /// Output directory. By default RFA files are created next to the target file
+    #[clap(long)]
+    out_dir: Option<PathBuf>,
+
+    /// Animation filename template. Supported placeholders: {character} - target filename without extension,
+    /// {clip} - animation name. Default is "{clip}.rfa"
+    #[clap(long, value_name = "TEMPLATE")]
+    anim_name_template: Option<String>,
+
+    /// Run the retargeting without writing any output files
+    #[clap(long)]
+    check: bool,
+
+    /// Keep previous version of output files with .bak suffix
+    #[clap(long)]
+    backup: bool,
+}
+
+/// Default animation filename template of mirrored animations
+const DEFAULT_MIRRORED_ANIM_NAME_TEMPLATE: &str = "{clip}_mirrored.rfa";
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct MirrorArgs {
+    /// Input GLTF file with the character skeleton (skin) and its animations
+    input_file: PathBuf,
+
+    /// Name of animation to be mirrored. Can be used multiple times. All animations are mirrored by default
+    #[clap(long = "anim", value_name = "NAME")]
+    anims: Vec<String>,
+
+    /// Output directory. By default RFA files are created next to the input file
+    #[clap(long)]
+    out_dir: Option<PathBuf>,
+
+    /// Animation filename template. Supported placeholders: {character} - input filename without extension,
+    /// {clip} - animation name. Default is "{clip}_mirrored.rfa"
+    #[clap(long, value_name = "TEMPLATE")]
+    anim_name_template: Option<String>,
+
+    /// Run the mirroring without writing any output files
+    #[clap(long)]
+    check: bool,
+
+    /// Keep previous version of output files with .bak suffix
+    #[clap(long)]
+    backup: bool,
+}
+
+fn load_bone_map(path: &Path) -> BoxResult<BoneMap> {
+    let text = std::fs::read_to_string(path)?;
+    toml::from_str(&text).map_err(|e| {
+        let message = format!("invalid bone map file {}: {}", path.display(), e);
+        CategorizedError::new(ErrorCategory::Usage, message).into()
+    })
+}
+
+fn import_gltf(path: &Path) -> BoxResult<(gltf::Document, Vec<v3d::buffer::BufferData>)> {
+    let gltf = gltf::Gltf::open(path)?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    Ok(v3d::import_gltf(gltf, base_dir)?)
+}
+
+/// Where and how RFA files produced from GLTF animations are written
+struct RfaOutput<'a> {
+    /// Filename of the character file ({character} placeholder) and its directory used if out_dir is not given
+    character_file: &'a Path,
+    out_dir: Option<&'a Path>,
+    template: &'a str,
+    check: bool,
+    backup: bool,
+}
+
+/// Converts selected animations of the document into RFA files and writes them. Returns number of written files
+fn write_rfas<F>(
+    doc: &gltf::Document,
+    anims: &[String],
+    output: &RfaOutput,
+    mut convert_fn: F,
+) -> BoxResult<usize>
+where
+    F: FnMut(&gltf::Animation, usize) -> v3d::ConvertResult<(String, rfa::File)>,
+{
+    let character = output
+        .character_file
+        .file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let out_dir = output
+        .out_dir
+        .or_else(|| output.character_file.parent())
+        .unwrap_or_else(|| Path::new(""));
+    if !output.check {
+        std::fs::create_dir_all(out_dir)?;
+    }
+
+    let mut num_written = 0;
+    for (i, anim) in doc.animations().enumerate() {
+        let anim_name = anim.name().unwrap_or_default();
+        if !anims.is_empty() && !anims.iter().any(|n| n == anim_name) {
+            continue;
+        }
+        let (clip, rfa) = convert_fn(&anim, i)?;
+        let file_name = out_dir.join(expand_anim_name_template(
+            output.template,
+            &character,
+            &clip,
+        ));
+        info!("Exporting animation: {} -> {}", clip, file_name.display());
+        write_output_file_with(&file_name, output.check, output.backup, |wrt| {
+            rfa.write(wrt)
+        })?;
+        num_written += 1;
+    }
+    Ok(num_written)
+}
+
+pub(crate) fn run_retarget(args: &RetargetArgs) -> BoxResult<()> {
+    let bone_map = load_bone_map(&args.bone_map)?;
+    let (target_doc, _) = import_gltf(&args.target)?;
+    let skin = target_doc.skins().next().ok_or_else(|| {
+        let message = format!("{} has no skin", args.target.display());
+        CategorizedError::new(ErrorCategory::Usage, message)
+    })?;
+    let (source_doc, source_buffers) = import_gltf(&args.input_file)?;
+    let ctx = Context::new(source_buffers, true, ConvertOptions::default());
+
+    let output = RfaOutput {
+        character_file: &args.target,
+        out_dir: args.out_dir.as_deref(),
+        template: args
+            .anim_name_template
+            .as_deref()
+            .unwrap_or(DEFAULT_ANIM_NAME_TEMPLATE),
+        check: args.check,
+        backup: args.backup,
+    };
+    let num_retargeted = write_rfas(&source_doc, &args.anims, &output, |anim, i| {
+        retarget_animation(anim, i, &source_doc, &ctx, &skin, &target_doc, &bone_map)
+    })?;
+    if num_retargeted == 0 {
+        let message = format!("no animations to retarget in {}", args.input_file.display());
+        return Err(CategorizedError::new(ErrorCategory::Usage, message).into());
+    }
+    Ok(())
+}
+
+pub(crate) fn run_mirror(args: &MirrorArgs) -> BoxResult<()> {
+    let (doc, buffers) = import_gltf(&args.input_file)?;
+    let skin = doc.skins().next().ok_or_else(|| {
+        let message = format!("{} has no skin", args.input_file.display());
+        CategorizedError::new(ErrorCategory::Usage, message)
+    })?;
+    let ctx = Context::new(buffers, true, ConvertOptions::default());
+
+    let output = RfaOutput {
+        character_file: &args.input_file,
+        out_dir: args.out_dir.as_deref(),
+        template: args
+            .anim_name_template
+            .as_deref()
+            .unwrap_or(DEFAULT_MIRRORED_ANIM_NAME_TEMPLATE),
+        check: args.check,
+        backup: args.backup,
+    };
+    let num_mirrored = write_rfas(&doc, &args.anims, &output, |anim, i| {
+        mirror_animation(anim, i, &doc, &ctx, &skin)
+    })?;
+    if num_mirrored == 0 {
+        let message = format!("no animations to mirror in {}", args.input_file.display());
+        return Err(CategorizedError::new(ErrorCategory::Usage, message).into());
+    }
+    Ok(())
+}
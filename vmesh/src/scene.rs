@@ -0,0 +1,97 @@
+//! Heuristic checks of the total cost of all files converted in one run.
+//!
+//! Every file can be within engine limits and the scene can still be too heavy for the renderer when many modern
+//! high-poly assets are converted wholesale. The thresholds are rough guidelines, not hard limits.
+
+use crate::build::find_texture_source;
+use std::path::{Path, PathBuf};
+use v3d::report::NodeReport;
+
+/// Total vertex count of the most detailed LODs that the renderer still handles comfortably
+const COMFORTABLE_VERTICES: usize = 100_000;
+/// Estimated video memory used by textures (including mipmaps)
+const COMFORTABLE_TEXTURE_MEMORY: u64 = 64 << 20;
+/// Total size of converted mesh files
+const COMFORTABLE_OUTPUT_SIZE: usize = 16 << 20;
+/// Size assumed for textures that cannot be found (typical stock texture)
+const DEFAULT_TEXTURE_DIMENSIONS: (u32, u32) = (256, 256);
+
+/// Accumulated cost of converted files
+#[derive(Default)]
+pub(crate) struct SceneCost {
+    files: usize,
+    vertices: usize,
+    output_size: usize,
+    /// Texture names together with the directory they are looked up in
+    textures: Vec<(String, PathBuf)>,
+}
+
+impl SceneCost {
+    pub(crate) fn add(&mut self, input_file: &Path, node_reports: &[NodeReport]) {
+        let dir = match input_file.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_owned(),
+            _ => PathBuf::from("."),
+        };
+        self.files += 1;
+        for node in node_reports {
+            self.vertices += node.vertices;
+            self.output_size += node.sizes.map_or(0, |s| s.total);
+            for name in &node.textures {
+                if !self
+                    .textures
+                    .iter()
+                    .any(|(t, _)| t.eq_ignore_ascii_case(name))
+                {
+                    self.textures.push((name.clone(), dir.clone()));
+                }
+            }
+        }
+    }
+
+    /// Estimates video memory used by all textures. RF textures are uploaded as 32-bit images with mipmaps
+    fn estimate_texture_memory(&self) -> u64 {
+        self.textures
+            .iter()
+            .map(|(name, dir)| {
+                let (w, h) = find_texture_source(name, std::slice::from_ref(dir))
+                    .ok()
+                    .flatten()
+                    .and_then(|path| image::image_dimensions(path).ok())
+                    .unwrap_or(DEFAULT_TEXTURE_DIMENSIONS);
+                u64::from(w) * u64::from(h) * 4 * 4 / 3
+            })
+            .sum()
+    }
+
+    /// Warns about totals exceeding what the renderer handles comfortably
+    pub(crate) fn warn_if_expensive(&self) {
+        if self.files < 2 {
+            // single file is fully covered by per-file limits
+            return;
+        }
+        if self.vertices > COMFORTABLE_VERTICES {
+            warn!(
+                "Converted files have {} vertices in total (most detailed LODs) - more than {} can hurt rendering \
+                performance, consider simplifying the meshes or using --auto-lod",
+                self.vertices, COMFORTABLE_VERTICES
+            );
+        }
+        let texture_memory = self.estimate_texture_memory();
+        if texture_memory > COMFORTABLE_TEXTURE_MEMORY {
+            warn!(
+                "{} textures used by converted files need about {} MB of video memory - more than {} MB can hurt \
+                rendering performance, consider lowering texture resolution",
+                self.textures.len(),
+                texture_memory >> 20,
+                COMFORTABLE_TEXTURE_MEMORY >> 20
+            );
+        }
+        if self.output_size > COMFORTABLE_OUTPUT_SIZE {
+            warn!(
+                "Converted files take {} MB in total - more than {} MB makes level loading slow",
+                self.output_size >> 20,
+                COMFORTABLE_OUTPUT_SIZE >> 20
+            );
+        }
+    }
+}
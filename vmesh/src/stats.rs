@@ -0,0 +1,83 @@
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use v3d::{rfg, v3mc};
+
+/// Conversion statistics printed by `--stats`
+#[derive(Default)]
+pub(crate) struct Stats {
+    pub(crate) submeshes: usize,
+    pub(crate) batches: usize,
+    pub(crate) vertices: usize,
+    pub(crate) triangles: usize,
+    pub(crate) textures: usize,
+    pub(crate) output_size: usize,
+    stage_times: Vec<(&'static str, Duration)>,
+}
+
+impl Stats {
+    pub(crate) fn time_stage<T, F: FnOnce() -> T>(&mut self, name: &'static str, f: F) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.stage_times.push((name, start.elapsed()));
+        result
+    }
+
+    pub(crate) fn add_v3mc(&mut self, file: &v3mc::File) {
+        let mut textures = BTreeSet::new();
+        for lod_mesh in &file.lod_meshes {
+            self.submeshes += 1;
+            for mesh in &lod_mesh.meshes {
+                self.batches += mesh.chunks.len();
+                self.vertices += mesh.num_vecs as usize;
+                self.triangles += mesh
+                    .chunks
+                    .iter()
+                    .map(|c| c.num_faces as usize)
+                    .sum::<usize>();
+            }
+            textures.extend(
+                lod_mesh
+                    .materials
+                    .iter()
+                    .map(|m| m.tex_name.to_ascii_lowercase()),
+            );
+        }
+        self.textures += textures.len();
+    }
+
+    pub(crate) fn add_brushes<'a, I: IntoIterator<Item = &'a rfg::Brush>>(&mut self, brushes: I) {
+        let mut textures = BTreeSet::new();
+        for brush in brushes {
+            let solid = &brush.solid;
+            self.submeshes += 1;
+            self.batches += solid.textures.len();
+            self.vertices += solid.vertices.len();
+            // faces are convex polygons triangulated into triangle fans
+            self.triangles += solid
+                .faces
+                .iter()
+                .map(|f| f.vertices.len().saturating_sub(2))
+                .sum::<usize>();
+            textures.extend(solid.textures.iter().map(|t| t.to_ascii_lowercase()));
+        }
+        self.textures += textures.len();
+    }
+
+    pub(crate) fn print<W: Write>(&self, input_file: &Path, wrt: &mut W) -> std::io::Result<()> {
+        writeln!(wrt, "Statistics for {}:", input_file.display())?;
+        writeln!(wrt, "  Submeshes:   {}", self.submeshes)?;
+        writeln!(wrt, "  Batches:     {}", self.batches)?;
+        writeln!(wrt, "  Vertices:    {}", self.vertices)?;
+        writeln!(wrt, "  Triangles:   {}", self.triangles)?;
+        writeln!(wrt, "  Textures:    {}", self.textures)?;
+        writeln!(wrt, "  Output size: {} bytes", self.output_size)?;
+        let total_time: Duration = self.stage_times.iter().map(|(_, t)| *t).sum();
+        for (name, time) in &self.stage_times {
+            let label = format!("{}:", name);
+            writeln!(wrt, "  {:12} {:.3} s", label, time.as_secs_f32())?;
+        }
+        writeln!(wrt, "  {:12} {:.3} s", "Total:", total_time.as_secs_f32())
+    }
+}
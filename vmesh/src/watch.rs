@@ -0,0 +1,77 @@
+use crate::{do_convert, Args, BoxResult};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+fn collect_watched_files(input_file: &Path) -> Vec<PathBuf> {
+    let mut files = vec![input_file.to_owned()];
+    let base_dir = input_file.parent().unwrap_or_else(|| Path::new(""));
+    // Buffers and images are re-read on every conversion so they have to be watched too
+    if let Ok(gltf) = gltf::Gltf::open(input_file) {
+        let buffer_uris = gltf.document.buffers().filter_map(|b| match b.source() {
+            gltf::buffer::Source::Uri(uri) => Some(uri),
+            gltf::buffer::Source::Bin => None,
+        });
+        let image_uris = gltf.document.images().filter_map(|img| match img.source() {
+            gltf::image::Source::Uri { uri, .. } => Some(uri),
+            gltf::image::Source::View { .. } => None,
+        });
+        files.extend(
+            buffer_uris
+                .chain(image_uris)
+                .filter(|uri| !uri.starts_with("data:"))
+                .map(|uri| base_dir.join(uri)),
+        );
+    }
+    files
+}
+
+fn get_modification_times(files: &[PathBuf]) -> BTreeMap<&Path, Option<SystemTime>> {
+    files
+        .iter()
+        .map(|path| {
+            let mtime = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+            (path.as_path(), mtime)
+        })
+        .collect()
+}
+
+fn wait_for_changes(files: &[PathBuf]) {
+    let initial_mtimes = get_modification_times(files);
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        if get_modification_times(files) != initial_mtimes {
+            break;
+        }
+    }
+    // Exporters often write files in multiple steps so wait until modification times are stable
+    let mut last_mtimes = get_modification_times(files);
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        let mtimes = get_modification_times(files);
+        if mtimes == last_mtimes {
+            break;
+        }
+        last_mtimes = mtimes;
+    }
+}
+
+pub(crate) fn watch_and_convert(args: Args) -> BoxResult<()> {
+    loop {
+        // Referenced files can change between exports so collect them again every time
+        let watched_files = collect_watched_files(&args.input_file);
+        match do_convert(args.clone()) {
+            Ok(_) => println!("Conversion finished"),
+            Err(e) => error!("{}", e),
+        }
+        println!(
+            "Watching {} file(s) for changes (press Ctrl+C to stop)...",
+            watched_files.len()
+        );
+        wait_for_changes(&watched_files);
+        println!("Change detected, converting again");
+    }
+}
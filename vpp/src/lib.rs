@@ -0,0 +1,422 @@
+//! Reading and writing of VPP packfiles
+
+use std::cmp;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{Error, Read, Result, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+#[macro_use]
+extern crate log;
+
+pub const VPP_BLOCK_SIZE: usize = 0x800;
+const VPP_VERSION: u32 = 1;
+const VPP_SIGNATURE: u32 = 0x51890ACE;
+
+trait ReadLe: Read {
+    fn read_u32_le(&mut self) -> Result<u32> {
+        let mut temp = [0u8; 4];
+        self.read_exact(&mut temp)?;
+        Ok(u32::from_le_bytes(temp))
+    }
+}
+
+trait WriteLe: Write {
+    fn write_u32_le(&mut self, val: u32) -> Result<()> {
+        self.write_all(&val.to_le_bytes())
+    }
+}
+
+impl<T> ReadLe for T where T: Read {}
+impl<T> WriteLe for T where T: Write {}
+
+struct VppHeader {
+    signature: u32,
+    version: u32,
+    num_files: u32,
+    size: u32,
+}
+
+struct VppEntry {
+    name: Vec<u8>,
+    size: u32,
+}
+
+impl VppHeader {
+    fn read<R: Read>(rdr: &mut R) -> Result<VppHeader> {
+        let signature = rdr.read_u32_le()?;
+        if signature != VPP_SIGNATURE {
+            return Err(Error::other(format!(
+                "invalid file signature {}",
+                signature
+            )));
+        }
+        let version = rdr.read_u32_le()?;
+        if version != VPP_VERSION {
+            return Err(Error::other(format!("unsupported version {}", version)));
+        }
+        let num_files = rdr.read_u32_le()?;
+        let size = rdr.read_u32_le()?;
+        Ok(VppHeader {
+            signature,
+            version,
+            num_files,
+            size,
+        })
+    }
+
+    fn write<W: Write>(&self, wrt: &mut W) -> Result<()> {
+        wrt.write_u32_le(self.signature)?;
+        wrt.write_u32_le(self.version)?;
+        wrt.write_u32_le(self.num_files)?;
+        wrt.write_u32_le(self.size)?;
+        Ok(())
+    }
+}
+
+impl VppEntry {
+    const NAME_MAX_LEN: usize = 60;
+
+    fn read<R: Read>(rdr: &mut R) -> Result<VppEntry> {
+        let mut name_buf = [0u8; Self::NAME_MAX_LEN];
+        rdr.read_exact(&mut name_buf)?;
+        let size = rdr.read_u32_le()?;
+        let name = name_buf.iter().cloned().take_while(|b| b != &0u8).collect();
+        Ok(VppEntry { name, size })
+    }
+
+    fn write<W: Write>(&self, wrt: &mut W) -> Result<()> {
+        let mut name_buf = [0u8; Self::NAME_MAX_LEN];
+        name_buf[..self.name.len()].copy_from_slice(&self.name);
+        wrt.write_all(&name_buf)?;
+        wrt.write_u32_le(self.size)?;
+        Ok(())
+    }
+}
+
+/// Creates packfile containing given files. Files are stored under their base names
+pub fn create_vpp(packfile_path: &str, file_list: &[String]) -> Result<()> {
+    debug!("Opening output file {}", packfile_path);
+    let mut file = File::create(packfile_path)?;
+
+    debug!("Writing file header");
+    let mut hdr = VppHeader {
+        signature: VPP_SIGNATURE,
+        version: VPP_VERSION,
+        num_files: file_list.len() as u32,
+        size: 0,
+    };
+    let mut block = [0u8; VPP_BLOCK_SIZE];
+    hdr.write(&mut block.as_mut())?;
+    file.write_all(&block)?;
+
+    debug!("Writing entries");
+    let mut block_wrt: &mut [u8] = &mut block;
+    for fname in file_list {
+        if block_wrt.is_empty() {
+            file.write_all(&block)?;
+            block_wrt = &mut block;
+        }
+
+        let size = std::fs::metadata(fname)?.len();
+        let basename = Path::new(fname).file_name().unwrap().to_string_lossy();
+        let entry = VppEntry {
+            name: basename.as_bytes().to_vec(),
+            size: size.try_into().unwrap(),
+        };
+        entry.write(&mut block_wrt)?;
+    }
+    if block_wrt.len() < block.len() {
+        file.write_all(&block)?;
+    }
+
+    debug!("Writing data");
+    for fname in file_list {
+        info!("Packing {}", fname);
+        let mut input_file = File::open(fname)?;
+        block_wrt = &mut block;
+        loop {
+            if block_wrt.is_empty() {
+                debug!("Writing data block");
+                file.write_all(&block)?;
+                block_wrt = &mut block;
+            }
+            let num_read_bytes = input_file.read(block_wrt)?;
+            if num_read_bytes == 0 {
+                break;
+            }
+            block_wrt = &mut block_wrt[num_read_bytes..];
+        }
+        if block_wrt.len() < block.len() {
+            debug!("Writing data block");
+            file.write_all(&block)?;
+        }
+    }
+
+    let pos = file.stream_position()?;
+    file.seek(SeekFrom::Start(0))?;
+    hdr.size = pos as u32;
+    hdr.write(&mut file)?;
+
+    Ok(())
+}
+
+/// File stored in a packfile
+pub struct VppFile {
+    pub name: String,
+    pub data: Vec<u8>,
+}
+
+/// Reads header and entries of packfile. The file is left positioned at the first data block
+fn read_entries(file: &mut File) -> Result<Vec<VppEntry>> {
+    debug!("Reading file header");
+    let mut hdr_block = [0u8; VPP_BLOCK_SIZE];
+    file.read_exact(&mut hdr_block)?;
+    let hdr = VppHeader::read(&mut hdr_block.as_ref())?;
+
+    debug!("Reading entries");
+    let mut block = [0u8; VPP_BLOCK_SIZE];
+    let mut block_rdr: &[u8] = &[];
+    let mut entries = Vec::<VppEntry>::new();
+    for _ in 0..hdr.num_files {
+        if block_rdr.is_empty() {
+            file.read_exact(&mut block)?;
+            block_rdr = &block;
+        }
+        entries.push(VppEntry::read(&mut block_rdr)?);
+    }
+    Ok(entries)
+}
+
+/// Reads names of all files stored in packfile without reading their content
+pub fn read_vpp_file_names(packfile_path: &str) -> Result<Vec<String>> {
+    debug!("Opening input packfile {}", packfile_path);
+    let mut file = File::open(packfile_path)?;
+    let entries = read_entries(&mut file)?;
+    Ok(entries
+        .into_iter()
+        .map(|entry| String::from_utf8_lossy(&entry.name).into_owned())
+        .collect())
+}
+
+/// Reads names and content of all files stored in packfile
+pub fn read_vpp(packfile_path: &str) -> Result<Vec<VppFile>> {
+    debug!("Opening input packfile {}", packfile_path);
+    let mut file = File::open(packfile_path)?;
+    let entries = read_entries(&mut file)?;
+
+    debug!("Reading data");
+    let mut files = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let num_blocks = (entry.size as usize).div_ceil(VPP_BLOCK_SIZE);
+        let mut data = vec![0u8; num_blocks * VPP_BLOCK_SIZE];
+        file.read_exact(&mut data)?;
+        data.truncate(entry.size as usize);
+        files.push(VppFile {
+            name: String::from_utf8_lossy(&entry.name).into_owned(),
+            data,
+        });
+    }
+    Ok(files)
+}
+
+/// Extracts all files from packfile into `output_dir` (current directory if `None`)
+pub fn extract_vpp(packfile_path: &str, output_dir: Option<&str>) -> Result<()> {
+    debug!("Opening input packfile {}", packfile_path);
+    let mut file = File::open(packfile_path)?;
+    let entries = read_entries(&mut file)?;
+    let mut block = [0u8; VPP_BLOCK_SIZE];
+
+    debug!("Reading data");
+    for entry in entries {
+        let num_blocks = (entry.size as usize).div_ceil(VPP_BLOCK_SIZE);
+        let name_str = String::from_utf8_lossy(&entry.name);
+        let output_path = output_dir
+            .map(|dir| dir.to_owned() + "/" + &name_str)
+            .unwrap_or_else(|| name_str.to_string());
+        info!("Extracting {}", output_path);
+        let mut output_file = File::create(output_path)?;
+        let mut bytes_left = entry.size as usize;
+        for _ in 0..num_blocks {
+            file.read_exact(&mut block)?;
+            let bytes_to_write = cmp::min(VPP_BLOCK_SIZE, bytes_left);
+            output_file.write_all(&block[..bytes_to_write])?;
+            bytes_left -= bytes_to_write;
+        }
+    }
+
+    Ok(())
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let kb = bytes / 1024;
+    if kb < 1024 {
+        return format!("{} KB", kb);
+    }
+    let mb = kb / 1024;
+    format!("{} MB", mb)
+}
+
+/// Prints names and sizes of files stored in packfile
+pub fn list_vpp_content(packfile_path: &str) -> Result<()> {
+    let mut file = File::open(packfile_path)?;
+    let mut hdr_block = [0u8; VPP_BLOCK_SIZE];
+    file.read_exact(&mut hdr_block)?;
+    let hdr = VppHeader::read(&mut hdr_block.as_ref())?;
+    for _ in 0..hdr.num_files {
+        let entry = VppEntry::read(&mut file)?;
+        let name_str = String::from_utf8_lossy(&entry.name);
+        println!("{:60} {}", name_str, format_size(entry.size.into()));
+    }
+    Ok(())
+}
+
+/// Entry of a packfile identified by its content (size and 64-bit hash)
+struct EntryInfo {
+    packfile: String,
+    name: String,
+    size: usize,
+    hash: u64,
+}
+
+impl EntryInfo {
+    fn location(&self) -> String {
+        format!("{}:{}", self.packfile, self.name)
+    }
+}
+
+/// Prints entries stored in given packfiles more than once: byte-identical files (which could be removed to make
+/// packfiles smaller) and files with the same name but different content (overriding each other in game)
+pub fn report_duplicates(packfile_paths: &[String]) -> Result<()> {
+    let mut entries = Vec::new();
+    for packfile_path in packfile_paths {
+        for file in read_vpp(packfile_path)? {
+            let mut hasher = DefaultHasher::new();
+            file.data.hash(&mut hasher);
+            entries.push(EntryInfo {
+                packfile: packfile_path.clone(),
+                name: file.name,
+                size: file.data.len(),
+                hash: hasher.finish(),
+            });
+        }
+    }
+
+    let mut by_content: BTreeMap<(usize, u64), Vec<&EntryInfo>> = BTreeMap::new();
+    for entry in &entries {
+        by_content
+            .entry((entry.size, entry.hash))
+            .or_default()
+            .push(entry);
+    }
+    let mut wasted_bytes = 0;
+    println!("Identical files:");
+    for ((size, _), group) in by_content.iter().filter(|(_, g)| g.len() > 1) {
+        let locations: Vec<_> = group.iter().map(|e| e.location()).collect();
+        println!(
+            "  {} ({}): {}",
+            group[0].name,
+            format_size(*size as u64),
+            locations.join(", ")
+        );
+        wasted_bytes += size * (group.len() - 1);
+    }
+    println!(
+        "  Space taken by redundant copies: {}",
+        format_size(wasted_bytes as u64)
+    );
+
+    let mut by_name: BTreeMap<String, Vec<&EntryInfo>> = BTreeMap::new();
+    for entry in &entries {
+        by_name
+            .entry(entry.name.to_ascii_lowercase())
+            .or_default()
+            .push(entry);
+    }
+    println!("Files with the same name and different content:");
+    for group in by_name.values() {
+        let differs = group
+            .iter()
+            .any(|e| (e.size, e.hash) != (group[0].size, group[0].hash));
+        if differs {
+            let locations: Vec<_> = group
+                .iter()
+                .map(|e| format!("{} ({})", e.location(), format_size(e.size as u64)))
+                .collect();
+            println!("  {}: {}", group[0].name, locations.join(", "));
+        }
+    }
+    Ok(())
+}
+
+fn is_newer(path: &Path, other: &Path) -> Result<bool> {
+    Ok(std::fs::metadata(path)?.modified()? > std::fs::metadata(other)?.modified()?)
+}
+
+/// Extracts packfile entries that are missing in `dir` or differ from files in `dir`. Files modified after the
+/// packfile was written are kept (they are probably being worked on)
+pub fn sync_dir_from_vpp(packfile_path: &str, dir: &str) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let mut num_extracted = 0;
+    for file in read_vpp(packfile_path)? {
+        let output_path = Path::new(dir).join(&file.name);
+        if output_path.exists() {
+            if std::fs::read(&output_path)? == file.data {
+                continue;
+            }
+            if is_newer(&output_path, Path::new(packfile_path))? {
+                warn!(
+                    "Skipping {} - local file differs and is newer than the packfile",
+                    output_path.display()
+                );
+                continue;
+            }
+        }
+        info!("Extracting {}", output_path.display());
+        std::fs::write(&output_path, &file.data)?;
+        num_extracted += 1;
+    }
+    println!("{} file(s) extracted", num_extracted);
+    Ok(())
+}
+
+/// Recreates packfile from files in `dir` (not recursive) unless it already contains exactly the same files
+pub fn sync_vpp_from_dir(packfile_path: &str, dir: &str) -> Result<()> {
+    let mut paths = std::fs::read_dir(dir)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<Result<Vec<PathBuf>>>()?;
+    paths.retain(|p| p.is_file());
+    paths.sort();
+
+    if Path::new(packfile_path).exists() {
+        let packed = read_vpp(packfile_path)?;
+        let mut changed = packed.len() != paths.len();
+        for path in &paths {
+            let name = path.file_name().unwrap().to_string_lossy();
+            let packed_file = packed.iter().find(|f| f.name.eq_ignore_ascii_case(&name));
+            let is_same = match packed_file {
+                Some(f) => std::fs::read(path)? == f.data,
+                None => false,
+            };
+            if !is_same {
+                info!("Changed {}", path.display());
+                changed = true;
+            }
+        }
+        if !changed {
+            println!("{} is up to date", packfile_path);
+            return Ok(());
+        }
+    }
+
+    let file_list: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+    create_vpp(packfile_path, &file_list)?;
+    println!("{} updated ({} files)", packfile_path, file_list.len());
+    Ok(())
+}
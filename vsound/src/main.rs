@@ -5,10 +5,14 @@ mod wave;
 
 use adpcm::Ps2AdpcmDecoder;
 use byteorder::{LittleEndian, ReadBytesExt, WriteBytesExt};
+use log::{Level, LevelFilter};
 use std::env;
 use std::fs::File;
 use std::io::{BufReader, BufWriter, Read, Result, Write};
 
+#[macro_use]
+extern crate log;
+
 enum Operation {
     Info,
     Convert,
@@ -19,15 +23,16 @@ enum Operation {
 struct ParsedArgs {
     op: Operation,
     positional: Vec<String>,
-    #[allow(dead_code)]
-    verbose: bool,
+    verbose: u8,
+    quiet: bool,
     old: bool,
 }
 
 fn parse_args() -> ParsedArgs {
     let mut op_opt = None;
     let mut positional = Vec::<String>::new();
-    let mut verbose = false;
+    let mut verbose = 0;
+    let mut quiet = false;
     let mut old = false;
 
     for arg in env::args().skip(1) {
@@ -36,7 +41,8 @@ fn parse_args() -> ParsedArgs {
             "-v" => op_opt = Some(Operation::Version),
             "-i" => op_opt = Some(Operation::Info),
             "--old" => old = true,
-            "--verbose" => verbose = true,
+            "--verbose" => verbose += 1,
+            "-q" | "--quiet" => quiet = true,
             _ => positional.push(arg),
         }
     }
@@ -53,6 +59,7 @@ fn parse_args() -> ParsedArgs {
         op,
         positional,
         verbose,
+        quiet,
         old,
     }
 }
@@ -150,6 +157,9 @@ fn print_help() {
     println!("    shows information about VSE/VMU file");
     println!("  vsound input_file.vse output_file.wav");
     println!("    converts VSE/VMU file to WAV file");
+    println!("Additional options:");
+    println!("  --verbose   - print more information (can be used up to 3 times)");
+    println!("  -q, --quiet - do not print warnings (errors are still printed)");
 }
 
 fn print_version() {
@@ -201,13 +211,13 @@ fn print_file_info(pathname: &str, is_old_version: bool) -> Result<()> {
     } else if pathname.ends_with(".vmu") {
         print_vmusic_info(pathname)
     } else {
-        eprintln!("Unknown input file extension! Supported extensions: vse, vmu.");
+        error!("Unknown input file extension! Supported extensions: vse, vmu.");
         Ok(())
     }
 }
 
 fn print_pcm_info(pcm_wf: &wave::PcmWaveFormat) {
-    println!(
+    info!(
         "Converting to WAV (PCM, {}, {} Hz)",
         if pcm_wf.wf.nChannels == 1 {
             "mono"
@@ -301,13 +311,35 @@ fn convert_file(input_pathname: &str, output_pathname: &str, is_old_version: boo
     } else if input_pathname.ends_with(".vmu") {
         convert_vmusic(input_pathname, output_pathname)
     } else {
-        eprintln!("Unknown input file extension! Supported extensions: vse, vmu.");
+        error!("Unknown input file extension! Supported extensions: vse, vmu.");
         Ok(())
     }
 }
 
+fn init_logger(verbose: u8, quiet: bool) {
+    let level = if quiet {
+        LevelFilter::Error
+    } else {
+        match verbose {
+            0 => LevelFilter::Warn,
+            1 => LevelFilter::Info,
+            2 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        }
+    };
+    env_logger::Builder::new()
+        .filter_module("vsound", level)
+        .format(|buf, record| match record.level() {
+            Level::Error => writeln!(buf, "Error: {}", record.args()),
+            Level::Warn => writeln!(buf, "Warning! {}", record.args()),
+            _ => writeln!(buf, "{}", record.args()),
+        })
+        .init();
+}
+
 fn main() -> Result<()> {
     let args = parse_args();
+    init_logger(args.verbose, args.quiet);
     match args.op {
         Operation::Info => print_file_info(&args.positional[0], args.old)?,
         Operation::Convert => convert_file(&args.positional[0], &args.positional[1], args.old)?,